@@ -0,0 +1,135 @@
+//!How the core database operations scale - run with `cargo bench`
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use english_quotes::{
+    db::{
+        add_quote_to_db, add_quotes_batch, rank_substring_matches, read_db_from_reader,
+        sort_list_by, SortKey,
+    },
+    quote::{PunctuationRules, Quote, ALL_PERMS},
+    utils::filter::{build_category_index, filter_quotes, QuoteSelectionFilter},
+};
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+const WORDS: [&str; 12] = [
+    "the", "only", "way", "to", "do", "great", "work", "is", "love", "what", "you", "know",
+];
+
+///`count` quotes of varying length, each in a couple of categories and with one of a handful of authors
+fn synthetic_db(count: usize) -> Vec<Quote> {
+    (0..count)
+        .map(|i| {
+            let text = (0..5 + i % 20)
+                .map(|word| WORDS[(i * 7 + word * 3) % WORDS.len()])
+                .collect::<Vec<_>>()
+                .join(" ");
+            Quote::builder(format!("{text} {i}"))
+                .categories([
+                    ALL_PERMS[i % ALL_PERMS.len()].clone(),
+                    ALL_PERMS[(i / 3) % ALL_PERMS.len()].clone(),
+                ])
+                .author(format!("Author {}", i % 50))
+                .build()
+        })
+        .collect()
+}
+
+fn read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_db");
+    for size in SIZES {
+        let json = serde_json::to_vec(&synthetic_db(size)).expect("quotes serialise");
+        group.bench_with_input(BenchmarkId::from_parameter(size), &json, |b, json| {
+            b.iter(|| read_db_from_reader(black_box(json.as_slice())));
+        });
+    }
+    group.finish();
+}
+
+fn sort(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sort_list");
+    for size in SIZES {
+        let db = synthetic_db(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &db, |b, db| {
+            b.iter_batched(
+                || db.clone(),
+                |mut db| sort_list_by(Some(&mut db), SortKey::Text),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn category_filter(c: &mut Criterion) {
+    let chosen = [ALL_PERMS[0].clone(), ALL_PERMS[1].clone()];
+    let mut group = c.benchmark_group("category_filter");
+    for size in SIZES {
+        let db = synthetic_db(size);
+        group.bench_with_input(BenchmarkId::new("build_index", size), &db, |b, db| {
+            b.iter(|| build_category_index(black_box(db)));
+        });
+
+        let index = build_category_index(&db);
+        let short = |quote: &Quote| quote.0.len() < 60;
+        for (name, filter, narrow) in [
+            ("or", QuoteSelectionFilter::Or, None),
+            ("and", QuoteSelectionFilter::And, None),
+            (
+                "or_short",
+                QuoteSelectionFilter::Or,
+                Some(&short as &dyn Fn(&Quote) -> bool),
+            ),
+        ] {
+            group.bench_with_input(BenchmarkId::new(name, size), &db, |b, db| {
+                b.iter(|| filter_quotes(db, &index, black_box(&chosen), filter, narrow));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    for size in SIZES {
+        let db = synthetic_db(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &db, |b, db| {
+            b.iter(|| rank_substring_matches(black_box("great work"), db));
+        });
+    }
+    group.finish();
+}
+
+///Importing 5,000 new quotes into a database of 10,000
+fn import(c: &mut Criterion) {
+    let mut quotes = synthetic_db(15_000);
+    let incoming = quotes.split_off(10_000);
+
+    let mut group = c.benchmark_group("import_5000");
+    group.sample_size(10);
+    group.bench_function("add_quotes_batch", |b| {
+        b.iter_batched(
+            || (quotes.clone(), incoming.clone()),
+            |(mut db, incoming)| {
+                add_quotes_batch(incoming, &mut db, false, PunctuationRules::default())
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("add_quote_to_db", |b| {
+        b.iter_batched(
+            || (quotes.clone(), incoming.clone()),
+            |(mut db, incoming)| {
+                for quote in incoming {
+                    let _ =
+                        add_quote_to_db(quote, Some(&mut db), false, PunctuationRules::default());
+                }
+                db
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read, sort, category_filter, search, import);
+criterion_main!(benches);
@@ -0,0 +1,198 @@
+#![warn(clippy::pedantic)]
+#![warn(clippy::all)]
+#![warn(clippy::nursery)]
+#![allow(clippy::module_name_repetitions)]
+
+use english_quotes::{
+    db::read_db_from_reader,
+    quote::Quote,
+    utils::{
+        exports::{export_to_writer, ExportExclusions, ExportFormat, ExportOptions, FieldSet},
+        stats::compute_stats,
+        template::TemplateError,
+    },
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    process::ExitCode,
+};
+
+const USAGE: &str = "usage: english_quotes export [--format markdown|csv|html|text|jsonl] [--group category|author|collection] [--separator newline|blank|divider] [--template TEMPLATE] [--wrap COLUMN] [--exclude CATEGORY]... [DB_FILE | -]
+       english_quotes stats [--json] [DB_FILE | -]
+
+Reads the database from DB_FILE, or from stdin if it's missing or -, and writes the export or stats to stdout - eg. `english_quotes stats --json db.json > stats.json`.";
+
+struct ExportArgs {
+    format: ExportFormat,
+    ///The wrap width is only for Markdown and plain text, and the template only for plain text
+    options: ExportOptions,
+    ///Repeatable, eg. `--exclude private`
+    exclusions: ExportExclusions,
+    db: Option<String>,
+}
+
+fn parse_export_args(mut args: impl Iterator<Item = String>) -> Result<ExportArgs, String> {
+    let mut parsed = ExportArgs {
+        format: ExportFormat::default(),
+        options: ExportOptions::default(),
+        exclusions: ExportExclusions::default(),
+        db: None,
+    };
+    let mut separator = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => {
+                parsed.format = args.next().ok_or("--format needs a value")?.parse()?;
+            }
+            "--group" | "-g" => {
+                parsed.options.grouping = args.next().ok_or("--group needs a value")?.parse()?;
+            }
+            "--exclude" | "-x" => {
+                parsed
+                    .exclusions
+                    .categories
+                    .push(args.next().ok_or("--exclude needs a category")?);
+            }
+            "--wrap" | "-w" => {
+                let width = args.next().ok_or("--wrap needs a column")?;
+                parsed.options.wrap_width = Some(
+                    width
+                        .parse()
+                        .ok()
+                        .filter(|&width| width > 0)
+                        .ok_or_else(|| format!("--wrap needs a column above 0, not {width}"))?,
+                );
+            }
+            "--template" | "-t" => {
+                parsed.options.template = args
+                    .next()
+                    .ok_or("--template needs a value")?
+                    .parse()
+                    .map_err(|err: TemplateError| err.to_string())?;
+            }
+            "--separator" | "-s" => {
+                separator = Some(args.next().ok_or("--separator needs a value")?.parse()?);
+            }
+            _ if parsed.db.is_none() && (arg == "-" || !arg.starts_with('-')) => {
+                parsed.db = Some(arg);
+            }
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    if let Some(separator) = separator {
+        match &mut parsed.format {
+            ExportFormat::PlainText(format_separator) => *format_separator = separator,
+            _ => return Err("--separator only works with --format text".to_string()),
+        }
+    }
+
+    Ok(parsed)
+}
+
+struct StatsArgs {
+    json: bool,
+    db: Option<String>,
+}
+
+fn parse_stats_args(args: impl Iterator<Item = String>) -> Result<StatsArgs, String> {
+    let mut parsed = StatsArgs {
+        json: false,
+        db: None,
+    };
+    for arg in args {
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            _ if parsed.db.is_none() && (arg == "-" || !arg.starts_with('-')) => {
+                parsed.db = Some(arg);
+            }
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+    Ok(parsed)
+}
+
+fn read_list(db: Option<&str>) -> Result<Vec<Quote>, Box<dyn std::error::Error>> {
+    Ok(match db {
+        None | Some("-") => read_db_from_reader(std::io::stdin().lock())?,
+        Some(path) => read_db_from_reader(File::open(path)?)?,
+    })
+}
+
+fn run_stats(args: &StatsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let stats = compute_stats(&read_list(args.db.as_deref())?);
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &stats)?;
+        writeln!(out)?;
+    } else {
+        writeln!(out, "Quotes: {}", stats.total)?;
+        writeln!(out, "Authors: {}", stats.authors)?;
+        writeln!(out, "Favorites: {}", stats.favorites)?;
+        writeln!(
+            out,
+            "Length: {:.1} average, {:.1} median",
+            stats.average_length, stats.median_length
+        )?;
+        for (category, count) in &stats.per_category {
+            writeln!(out, "  {category}: {count}")?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn run_export(args: &ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let list = args.exclusions.apply(read_list(args.db.as_deref())?);
+
+    let stdout = std::io::stdout();
+    export_to_writer(
+        &list,
+        BufWriter::new(stdout.lock()),
+        args.format,
+        args.options.clone(),
+        FieldSet::default(),
+    )?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let result = match args.next().as_deref() {
+        Some("export") => match parse_export_args(args) {
+            Ok(export_args) => run_export(&export_args),
+            Err(err) => {
+                eprintln!("{err}\n\n{USAGE}");
+                return ExitCode::from(2);
+            }
+        },
+        Some("stats") => match parse_stats_args(args) {
+            Ok(stats_args) => run_stats(&stats_args),
+            Err(err) => {
+                eprintln!("{err}\n\n{USAGE}");
+                return ExitCode::from(2);
+            }
+        },
+        Some("--help" | "-h") => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
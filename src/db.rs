@@ -1,11 +1,568 @@
 use crate::{
-    quote::{FileType, Quote, ALL_PERMS},
+    quote::{
+        apply_punctuation_rules, category_key, is_valid_source, normalize_text, now_timestamp,
+        FileType, PunctuationRules, Quote, ALL_PERMS, MAX_RATING,
+    },
     utils::Error,
 };
-use std::fs::read_to_string;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::{Display, Formatter},
+    fs::read_to_string,
+    hash::{BuildHasher, RandomState},
+    io::{ErrorKind, Read},
+    ops::Range,
+    path::Path,
+    thread::sleep,
+    time::Duration,
+};
+
+///How many more times a save is tried after the first attempt fails, eg. because a sync client or virus scanner has the file open
+pub const SAVE_RETRIES: u32 = 3;
+///Doubled after each failed attempt
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+///Errors that might well go away on their own if the save is tried again a moment later
+fn is_transient(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::PermissionDenied
+            | ErrorKind::ResourceBusy
+            | ErrorKind::WouldBlock
+            | ErrorKind::Interrupted
+            | ErrorKind::TimedOut
+    )
+}
+
+///Calls `attempt` until it works, a non-transient error comes back, or it's failed `max_retries` more times - waiting longer before each retry
+pub fn retry_with_backoff<T>(
+    max_retries: u32,
+    mut attempt: impl FnMut() -> std::io::Result<T>,
+) -> std::io::Result<T> {
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut retries = 0;
+    loop {
+        match attempt() {
+            Err(err) if retries < max_retries && is_transient(&err) => {
+                sleep(delay);
+                delay *= 2;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+///Writes `contents` to a temporary file next to `location` and then moves it over the top, so a failed save never leaves a half-written file - see [`retry_with_backoff`]
+pub fn write_atomically_with_retries(
+    location: impl AsRef<Path>,
+    contents: &[u8],
+    max_retries: u32,
+) -> std::io::Result<()> {
+    let location = location.as_ref();
+    let mut temporary = location.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    retry_with_backoff(max_retries, || {
+        std::fs::write(&temporary, contents)?;
+        std::fs::rename(&temporary, location)
+    })
+}
+
+///[`write_atomically_with_retries`] with [`SAVE_RETRIES`]
+pub fn write_atomically(location: impl AsRef<Path>, contents: &[u8]) -> std::io::Result<()> {
+    write_atomically_with_retries(location, contents, SAVE_RETRIES)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SortKey {
+    Categories,
+    Author,
+    ///See [`text_sort_key`]
+    #[default]
+    Text,
+    ///Highest rated first, with unrated quotes last
+    Rating,
+}
+
+///What quotes are compared on when sorting by text - trimmed and lowercased, so stray whitespace and capitals don't move anything
+#[must_use]
+pub fn text_sort_key(quote: &Quote) -> String {
+    quote.0.trim().to_lowercase()
+}
+
+impl SortKey {
+    pub const ALL: [Self; 4] = [Self::Categories, Self::Author, Self::Text, Self::Rating];
+
+    ///Every key falls back to [`text_sort_key`] for ties. Expects categories to already be sorted, which [`sort_list_by`] does first.
+    #[must_use]
+    pub fn compare(self, a: &Quote, b: &Quote) -> Ordering {
+        let by_text = || text_sort_key(a).cmp(&text_sort_key(b));
+        match self {
+            Self::Categories => a.1.cmp(&b.1).then_with(by_text),
+            Self::Author => match (a.author(), b.author()) {
+                (Some(a_author), Some(b_author)) => a_author
+                    .trim()
+                    .to_lowercase()
+                    .cmp(&b_author.trim().to_lowercase()),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+            .then_with(by_text),
+            Self::Text => by_text(),
+            Self::Rating => match (a.rating(), b.rating()) {
+                (Some(a_rating), Some(b_rating)) => b_rating.cmp(&a_rating),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+            .then_with(by_text),
+        }
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Categories => write!(f, "Categories"),
+            Self::Author => write!(f, "Author"),
+            Self::Text => write!(f, "Text"),
+            Self::Rating => write!(f, "Rating"),
+        }
+    }
+}
+
+///Anything longer than this is almost certainly a paste gone wrong
+pub const MAX_QUOTE_LENGTH: usize = 10_000;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("The quote is empty")]
+    Empty,
+    #[error("That quote is already in the database, by the same author")]
+    Duplicate,
+    #[error("The quote is {length} characters long, the limit is {MAX_QUOTE_LENGTH}")]
+    TooLong { length: usize },
+}
+
+///Checks a quote before it's added to `db` - it's a duplicate if one there has the same [`Quote::dedup_key`], so the same words by another author are fine
+pub fn validate_quote(quote: &Quote, db: &[Quote]) -> Result<(), ValidationError> {
+    validate(quote, db, None)
+}
+
+///Like [`validate_quote`], for the edited version of the quote at `index` - it doesn't count as a duplicate of itself
+pub fn validate_edit(quote: &Quote, db: &[Quote], index: usize) -> Result<(), ValidationError> {
+    validate(quote, db, Some(index))
+}
+
+fn validate(quote: &Quote, db: &[Quote], skip: Option<usize>) -> Result<(), ValidationError> {
+    let text = quote.0.trim();
+    let length = text.chars().count();
+
+    if text.is_empty() {
+        Err(ValidationError::Empty)
+    } else if length > MAX_QUOTE_LENGTH {
+        Err(ValidationError::TooLong { length })
+    } else if db.iter().enumerate().any({
+        let key = quote.dedup_key();
+        move |(i, other)| Some(i) != skip && other.dedup_key() == key
+    }) {
+        Err(ValidationError::Duplicate)
+    } else {
+        Ok(())
+    }
+}
+
+///Changes the quote at `index` in place with `edit`, stamping it as modified if anything actually changed.
+///
+///Anything that changes a quote should come through here (rather than removing and re-adding it), so its added time and modified time are kept right.
+pub fn update_quote(
+    db: &mut [Quote],
+    index: usize,
+    edit: impl FnOnce(&mut Quote),
+) -> Result<(), Error> {
+    let quote = db.get_mut(index).ok_or(Error::NoQuoteAt(index))?;
+    let before = quote.clone();
+    edit(quote);
+    if quote.0 != before.0 || quote.1 != before.1 || quote.2 != before.2 {
+        quote.touch();
+    }
+    Ok(())
+}
+
+///Adds `tags` to each quote at `indices`, leaving out any it already has (going by [`category_key`], like categories), and gives back how many quotes gained a tag
+pub fn tag_quotes(
+    db: &mut [Quote],
+    indices: impl IntoIterator<Item = usize>,
+    tags: &[String],
+) -> usize {
+    let mut tagged = 0;
+    for index in indices {
+        let Some(before) = db.get(index).map(|quote| quote.tags().len()) else {
+            continue;
+        };
+        //only touches the quote if a tag was actually added
+        let added = update_quote(db, index, |quote| {
+            for tag in tags {
+                let key = category_key(tag);
+                if !quote
+                    .2
+                    .tags
+                    .iter()
+                    .any(|existing| category_key(existing) == key)
+                {
+                    quote.2.tags.push(tag.clone());
+                }
+            }
+        });
+        if added.is_ok() && db[index].tags().len() > before {
+            tagged += 1;
+        }
+    }
+    tagged
+}
+
+///Something wrong with a quote that's already in the database, found by [`quote_issues`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    #[error("empty text")]
+    EmptyText,
+    #[error("{length} characters long")]
+    TooLong { length: usize },
+    #[error("unknown category {0}")]
+    UnknownCategory(String),
+    #[error("blank author")]
+    BlankAuthor,
+    #[error("source isn't a link: {0}")]
+    InvalidSource(String),
+    #[error("rating of {0}")]
+    InvalidRating(u8),
+    #[error("blank tag")]
+    BlankTag,
+}
+
+///Everything wrong with `quote`, with `known_categories` being the ones it's allowed to use - matched by [`category_key`], like everywhere else.
+///
+///Unlike [`validate_quote`], this doesn't look at the rest of the database.
+#[must_use]
+pub fn quote_issues(quote: &Quote, known_categories: &[String]) -> Vec<Issue> {
+    let mut issues = vec![];
+
+    let length = quote.0.trim().chars().count();
+    if length == 0 {
+        issues.push(Issue::EmptyText);
+    } else if length > MAX_QUOTE_LENGTH {
+        issues.push(Issue::TooLong { length });
+    }
+    for cat in &quote.1 {
+        let key = category_key(cat);
+        if !known_categories
+            .iter()
+            .any(|known| category_key(known) == key)
+        {
+            issues.push(Issue::UnknownCategory(cat.clone()));
+        }
+    }
+    if quote
+        .author()
+        .is_some_and(|author| author.trim().is_empty())
+    {
+        issues.push(Issue::BlankAuthor);
+    }
+    if let Some(source) = quote.source() {
+        if !is_valid_source(source) {
+            issues.push(Issue::InvalidSource(source.to_string()));
+        }
+    }
+    if let Some(rating) = quote.rating() {
+        if rating == 0 || rating > MAX_RATING {
+            issues.push(Issue::InvalidRating(rating));
+        }
+    }
+    if quote.tags().iter().any(|tag| tag.trim().is_empty()) {
+        issues.push(Issue::BlankTag);
+    }
+
+    issues
+}
+
+///The index of a quote in the database, and everything wrong with it
+pub type QuoteIssues = (usize, Vec<Issue>);
+
+///The [`QuoteIssues`] of every quote in `db` that has any
+#[must_use]
+pub fn db_issues(db: &[Quote], known_categories: &[String]) -> Vec<QuoteIssues> {
+    db.iter()
+        .enumerate()
+        .map(|(i, quote)| (i, quote_issues(quote, known_categories)))
+        .filter(|(_, issues)| !issues.is_empty())
+        .collect()
+}
+
+///Where quotes are kept - db.json, or a list that never touches the disk, eg. for demos.
+///
+///These just pass the list (or `None` for the file) to the functions that take an `Option<&mut Vec<Quote>>`, like [`add_quote_to_db`].
+#[derive(Clone, Debug, Default)]
+pub enum Storage {
+    #[default]
+    File,
+    InMemory(Vec<Quote>),
+}
+
+impl Storage {
+    const fn in_memory(&mut self) -> Option<&mut Vec<Quote>> {
+        match self {
+            Self::File => None,
+            Self::InMemory(db) => Some(db),
+        }
+    }
+
+    pub fn read(&self) -> Result<Vec<Quote>, Error> {
+        match self {
+            Self::File => read_db(),
+            Self::InMemory(db) => Ok(db.clone()),
+        }
+    }
+
+    ///See [`add_quote_to_db`]
+    pub fn add(
+        &mut self,
+        quote: Quote,
+        normalize: bool,
+        punctuation: PunctuationRules,
+    ) -> Result<(), Error> {
+        add_quote_to_db(quote, self.in_memory(), normalize, punctuation).map(|_| ())
+    }
+
+    ///See [`add_quotes_batch`] - the database file is only read and written once
+    pub fn add_batch(
+        &mut self,
+        quotes: Vec<Quote>,
+        normalize: bool,
+        punctuation: PunctuationRules,
+    ) -> Result<BatchResult, Error> {
+        if let Some(db) = self.in_memory() {
+            return Ok(add_quotes_batch(quotes, db, normalize, punctuation));
+        }
+        let mut db = read_db()?;
+        let result = add_quotes_batch(quotes, &mut db, normalize, punctuation);
+        if result.added + result.merged > 0 {
+            write_db(&db, FileType::Database.get_location())?;
+        }
+        Ok(result)
+    }
+
+    ///See [`remove_quote`]
+    pub fn remove(&mut self, quote: &Quote) -> Result<(), Error> {
+        remove_quote(quote, self.in_memory())
+    }
+
+    ///See [`update_quote`]
+    pub fn edit(&mut self, index: usize, edit: impl FnOnce(&mut Quote)) -> Result<(), Error> {
+        if let Some(db) = self.in_memory() {
+            return update_quote(db, index, edit);
+        }
+        let mut db = read_db()?;
+        update_quote(&mut db, index, edit)?;
+        write_db(&db, FileType::Database.get_location())
+    }
+
+    ///See [`sort_list_by`]
+    pub fn sort(&mut self, key: SortKey) -> Result<SortOutcome, SortError> {
+        sort_list_by(self.in_memory(), key)
+    }
+
+    ///Runs `steps` on a copy of the quotes, only keeping the changes if every step succeeds - see [`with_db_transaction`]
+    pub fn transaction<T>(
+        &mut self,
+        steps: impl FnOnce(&mut Vec<Quote>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.run_transaction(steps).map(|(result, _)| result)
+    }
+
+    ///[`Self::transaction`], along with whether anything changed
+    fn run_transaction<T>(
+        &mut self,
+        steps: impl FnOnce(&mut Vec<Quote>) -> Result<T, Error>,
+    ) -> Result<(T, bool), Error> {
+        match self {
+            Self::File => transaction_at(FileType::Database.get_location(), steps),
+            Self::InMemory(db) => {
+                let mut tx = db.clone();
+                let result = steps(&mut tx)?;
+                let changed = !same_quotes(&tx, db);
+                if changed {
+                    *db = tx;
+                }
+                Ok((result, changed))
+            }
+        }
+    }
+}
+
+///Whether `a` and `b` are exactly the same - `==` on quotes skips the metadata, so this compares everything
+fn same_quotes(a: &[Quote], b: &[Quote]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.0 == b.0 && a.1 == b.1 && a.2 == b.2)
+}
+
+///[`Storage::run_transaction`] for the database at `location`, which is only written if something changed
+fn transaction_at<T>(
+    location: impl AsRef<Path>,
+    steps: impl FnOnce(&mut Vec<Quote>) -> Result<T, Error>,
+) -> Result<(T, bool), Error> {
+    let location = location.as_ref();
+    let before = read_db_at(location)?;
+    let mut tx = before.clone();
+    let result = steps(&mut tx)?;
+    let changed = !same_quotes(&tx, &before);
+    if changed {
+        write_db(&tx, location)?;
+    }
+    Ok((result, changed))
+}
+
+///Applies several changes to db.json as one, eg. adding a merged quote and removing the two it came from.
+///
+///`steps` works on a copy of the database, which is written back once at the end if it returns `Ok` - if it returns an error (or panics) db.json is left exactly as it was. It isn't written at all if nothing changed.
+pub fn with_db_transaction<T>(
+    steps: impl FnOnce(&mut Vec<Quote>) -> Result<T, Error>,
+) -> Result<T, Error> {
+    Storage::File.transaction(steps)
+}
+
+///[`with_db_transaction`] for a database somewhere other than db.json
+pub fn with_db_transaction_at<T>(
+    location: impl AsRef<Path>,
+    steps: impl FnOnce(&mut Vec<Quote>) -> Result<T, Error>,
+) -> Result<T, Error> {
+    transaction_at(location, steps).map(|(result, _)| result)
+}
+
+///Called with all of the quotes after a [`Store`] changes them
+pub type ChangeHook = Box<dyn FnMut(&[Quote])>;
+
+///A [`Storage`] that can say whenever its quotes change, eg. to sync them somewhere else.
+///
+///The hook is only called once a change has gone through - after the list is updated for [`Storage::InMemory`], or after db.json has been written for [`Storage::File`]. It isn't called when something fails, or when nothing changed (like sorting a sorted list).
+///
+///Without a hook this is just the [`Storage`], but with one and [`Storage::File`], the file is read back after each change to hand over the new quotes.
+#[derive(Default)]
+pub struct Store {
+    storage: Storage,
+    on_change: Option<ChangeHook>,
+}
+
+impl Store {
+    #[must_use]
+    pub const fn new(storage: Storage) -> Self {
+        Self {
+            storage,
+            on_change: None,
+        }
+    }
+
+    ///Calls `hook` after every change from now on, replacing any earlier hook
+    #[must_use]
+    pub fn on_change(mut self, hook: impl FnMut(&[Quote]) + 'static) -> Self {
+        self.on_change = Some(Box::new(hook));
+        self
+    }
+
+    pub fn read(&self) -> Result<Vec<Quote>, Error> {
+        self.storage.read()
+    }
+
+    fn changed(&mut self) -> Result<(), Error> {
+        if let Some(hook) = &mut self.on_change {
+            match &self.storage {
+                Storage::File => hook(&read_db()?),
+                Storage::InMemory(db) => hook(db),
+            }
+        }
+        Ok(())
+    }
+
+    ///See [`Storage::add`]
+    pub fn add(
+        &mut self,
+        quote: Quote,
+        normalize: bool,
+        punctuation: PunctuationRules,
+    ) -> Result<(), Error> {
+        self.storage.add(quote, normalize, punctuation)?;
+        self.changed()
+    }
+
+    ///See [`Storage::add_batch`]
+    pub fn add_batch(
+        &mut self,
+        quotes: Vec<Quote>,
+        normalize: bool,
+        punctuation: PunctuationRules,
+    ) -> Result<BatchResult, Error> {
+        let result = self.storage.add_batch(quotes, normalize, punctuation)?;
+        if result.added + result.merged > 0 {
+            self.changed()?;
+        }
+        Ok(result)
+    }
+
+    ///See [`Storage::remove`]
+    pub fn remove(&mut self, quote: &Quote) -> Result<(), Error> {
+        self.storage.remove(quote)?;
+        self.changed()
+    }
+
+    ///See [`Storage::edit`] - an edit that leaves the quote as it was isn't a change
+    pub fn edit(&mut self, index: usize, edit: impl FnOnce(&mut Quote)) -> Result<(), Error> {
+        self.transaction(|db| update_quote(db, index, edit))
+    }
+
+    ///See [`Storage::sort`]
+    pub fn sort(&mut self, key: SortKey) -> Result<SortOutcome, Error> {
+        let outcome = self.storage.sort(key)?;
+        if outcome == SortOutcome::Sorted {
+            self.changed()?;
+        }
+        Ok(outcome)
+    }
+
+    ///See [`Storage::transaction`]
+    pub fn transaction<T>(
+        &mut self,
+        steps: impl FnOnce(&mut Vec<Quote>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let (result, changed) = self.storage.run_transaction(steps)?;
+        if changed {
+            self.changed()?;
+        }
+        Ok(result)
+    }
+}
 
-pub fn add_quote_to_db(mut q: Quote, db: Option<&mut Vec<Quote>>) -> Result<Vec<Quote>, Error> {
+///If `normalize` is set, the quote's text goes through [`normalize_text`] first, and then through [`apply_punctuation_rules`] with `punctuation`.
+///
+///Quotes failing [`validate_quote`] aren't added, and give back [`Error::Validation`].
+pub fn add_quote_to_db(
+    mut q: Quote,
+    db: Option<&mut Vec<Quote>>,
+    normalize: bool,
+    punctuation: PunctuationRules,
+) -> Result<Vec<Quote>, Error> {
+    if normalize {
+        q.0 = normalize_text(&q.0);
+    }
+    if punctuation != PunctuationRules::default() {
+        q.0 = apply_punctuation_rules(&q.0, punctuation);
+    }
     if let Some(db) = db {
+        validate_quote(&q, db)?;
         if q.1.is_empty() {
             q.1.push("Other".into());
         }
@@ -16,8 +573,9 @@ pub fn add_quote_to_db(mut q: Quote, db: Option<&mut Vec<Quote>>) -> Result<Vec<
         let db_content = read_to_string(FileType::Database.get_location()).unwrap_or_default();
         let mut parsed: Vec<Quote> = serde_json::from_str(&db_content).unwrap_or_default();
 
+        validate_quote(&q, &parsed)?;
         parsed.push(q);
-        std::fs::write(
+        write_atomically(
             FileType::Database.get_location(),
             &serde_json::to_vec(&parsed)?,
         )?;
@@ -26,39 +584,382 @@ pub fn add_quote_to_db(mut q: Quote, db: Option<&mut Vec<Quote>>) -> Result<Vec<
     }
 }
 
+///What [`add_quotes_batch`] did with each of the quotes it was given
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchResult {
+    pub added: usize,
+    ///Invalid quotes, and ones already in the database with nothing new to add
+    pub skipped: usize,
+    ///Quotes already in the database that gained categories, tags or collections
+    pub merged: usize,
+}
+
+///Adds all of `quotes` to `db` at once - like calling [`add_quote_to_db`] for each, but without going through the whole database every time.
+///
+///Quotes with the same [`Quote::dedup_key`] as one already there (or earlier in the batch) have their categories, tags and collections folded into it instead (see [`Quote::absorb`]) - the same words from another author are added as a quote of their own. Anything else that fails [`validate_quote`] is skipped.
+pub fn add_quotes_batch(
+    quotes: Vec<Quote>,
+    db: &mut Vec<Quote>,
+    normalize: bool,
+    punctuation: PunctuationRules,
+) -> BatchResult {
+    let mut result = BatchResult::default();
+    let mut by_key: HashMap<(String, Option<String>), usize> = HashMap::new();
+    for (i, quote) in db.iter().enumerate() {
+        by_key.entry(quote.dedup_key()).or_insert(i);
+    }
+
+    for mut q in quotes {
+        if normalize {
+            q.0 = normalize_text(&q.0);
+        }
+        if punctuation != PunctuationRules::default() {
+            q.0 = apply_punctuation_rules(&q.0, punctuation);
+        }
+        if validate_quote(&q, &[]).is_err() {
+            result.skipped += 1;
+            continue;
+        }
+        if q.1.is_empty() {
+            q.1.push("Other".into());
+        }
+
+        let Some(&index) = by_key.get(&q.dedup_key()) else {
+            by_key.insert(q.dedup_key(), db.len());
+            db.push(q);
+            result.added += 1;
+            continue;
+        };
+
+        let mut absorbed = false;
+        let merged = update_quote(db, index, |existing| absorbed = existing.absorb(&q));
+        if merged.is_ok() && absorbed {
+            result.merged += 1;
+        } else {
+            result.skipped += 1;
+        }
+    }
+
+    result
+}
+
+///Where `q` is in `db` - an exact match if there is one, otherwise the first that's [the same quote](Quote::same_quote)
+fn position_of(db: &[Quote], q: &Quote) -> Option<usize> {
+    db.iter()
+        .position(|q_loco| q == q_loco)
+        .or_else(|| db.iter().position(|q_loco| q.same_quote(q_loco)))
+}
+
 pub fn remove_quote(q: &Quote, db: Option<&mut Vec<Quote>>) -> Result<(), Error> {
     if let Some(db) = db {
-        if let Some(pos) = db.iter().position(|q_loco| q == q_loco) {
+        if let Some(pos) = position_of(db, q) {
             db.remove(pos);
         } else {
-            return Err(Error::QuoteNotFoundInDB(q.clone()));
+            return Err(Error::QuoteNotFoundInDB(Box::new(q.clone())));
         }
     } else {
         let db_content = read_to_string(FileType::Database.get_location()).unwrap_or_default();
         let mut parsed: Vec<Quote> = serde_json::from_str(&db_content).unwrap_or_default();
 
-        if let Some(pos) = parsed.iter().position(|q_loco| q == q_loco) {
+        if let Some(pos) = position_of(&parsed, q) {
             parsed.remove(pos);
 
-            std::fs::write(
+            write_atomically(
                 FileType::Database.get_location(),
                 &serde_json::to_vec(&parsed)?,
             )?;
         } else {
-            return Err(Error::QuoteNotFoundInDB(q.clone()));
+            return Err(Error::QuoteNotFoundInDB(Box::new(q.clone())));
+        }
+    }
+
+    Ok(())
+}
+
+///Removes every quote at the given indices, returning how many were removed. Out of range indices are ignored.
+pub fn remove_quotes(db: &mut Vec<Quote>, indices: &[usize]) -> usize {
+    let mut indices = indices.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+
+    let mut removed = 0;
+    for index in indices.into_iter().rev() {
+        if index < db.len() {
+            db.remove(index);
+            removed += 1;
+        }
+    }
+    removed
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+///A deleted quote, kept in trash.json until it's restored or purged
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrashedQuote {
+    pub quote: Quote,
+    ///Seconds since the unix epoch when the quote was deleted
+    pub deleted: u64,
+}
+
+///An empty list if there's no trash.json yet
+pub fn read_trash() -> Result<Vec<TrashedQuote>, Error> {
+    match read_to_string(FileType::Trash.get_location()) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn write_trash(trash: &[TrashedQuote]) -> Result<(), Error> {
+    write_atomically(FileType::Trash.get_location(), &serde_json::to_vec(trash)?)?;
+    Ok(())
+}
+
+///Like [`remove_quote`], but the quote goes into `trash` rather than being lost
+pub fn trash_quote(
+    q: &Quote,
+    db: &mut Vec<Quote>,
+    trash: &mut Vec<TrashedQuote>,
+) -> Result<(), Error> {
+    let pos = position_of(db, q).ok_or_else(|| Error::QuoteNotFoundInDB(Box::new(q.clone())))?;
+    trash.push(TrashedQuote {
+        quote: db.remove(pos),
+        deleted: now_timestamp(),
+    });
+    Ok(())
+}
+
+///Like [`remove_quotes`], but the quotes go into `trash` rather than being lost
+pub fn trash_quotes(
+    db: &mut Vec<Quote>,
+    indices: &[usize],
+    trash: &mut Vec<TrashedQuote>,
+) -> usize {
+    let mut indices = indices.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+
+    let deleted = now_timestamp();
+    let before = trash.len();
+    for index in indices.into_iter().rev() {
+        if index < db.len() {
+            trash.push(TrashedQuote {
+                quote: db.remove(index),
+                deleted,
+            });
         }
     }
+    trash.len() - before
+}
 
+///Puts the quote at `index` in `trash` back into `db`.
+///
+///It stays in the trash if the same quote by the same author has been added since (see [`validate_quote`]), giving back [`Error::Validation`].
+pub fn restore_quote(
+    trash: &mut Vec<TrashedQuote>,
+    index: usize,
+    db: &mut Vec<Quote>,
+) -> Result<(), Error> {
+    let trashed = trash.get(index).ok_or(Error::NoQuoteAt(index))?;
+    validate_quote(&trashed.quote, db)?;
+    db.push(trash.remove(index).quote);
     Ok(())
 }
 
+///Permanently deletes everything that's been in `trash` for more than `days` days, returning how many were deleted
+pub fn purge_trash(trash: &mut Vec<TrashedQuote>, days: u64) -> usize {
+    purge_trash_before(
+        trash,
+        now_timestamp().saturating_sub(days.saturating_mul(SECONDS_PER_DAY)),
+    )
+}
+
+///Like [`purge_trash`], for anything deleted before `cutoff` - anything deleted right at it is kept
+fn purge_trash_before(trash: &mut Vec<TrashedQuote>, cutoff: u64) -> usize {
+    let before = trash.len();
+    trash.retain(|trashed| trashed.deleted >= cutoff);
+    before - trash.len()
+}
+
+///What [`enforce_category_cap`] did to make room
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CapEvictions {
+    ///Quotes that were only in the capped category, and so have been removed
+    pub removed: Vec<Quote>,
+    ///Quotes that were in other categories too, and so have just been taken out of the capped one - as they are now
+    pub untagged: Vec<Quote>,
+}
+
+impl CapEvictions {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.untagged.is_empty()
+    }
+}
+
+///Keeps only the `max` newest quotes in `category` (going by when they were added), for rolling categories like "daily".
+///
+///Quotes without an added date count as the oldest, and ties go to the one further down the list. Older quotes are taken out of the category, or removed altogether if it was their only one.
+pub fn enforce_category_cap(db: &mut Vec<Quote>, category: &str, max: usize) -> CapEvictions {
+    let mut in_category: Vec<usize> = (0..db.len())
+        .filter(|&i| db[i].matches_category(category))
+        .collect();
+    if in_category.len() <= max {
+        return CapEvictions::default();
+    }
+    //oldest first, so the ones to go are at the front
+    in_category.sort_by_key(|&i| (db[i].2.added, i));
+    let mut over: Vec<usize> = in_category[..in_category.len() - max].to_vec();
+    over.sort_unstable();
+
+    let key = category_key(category);
+    let mut evictions = CapEvictions::default();
+    for &i in over.iter().rev() {
+        if db[i].1.iter().all(|cat| category_key(cat) == key) {
+            evictions.removed.push(db.remove(i));
+        } else {
+            let quote = &mut db[i];
+            quote.1.retain(|cat| category_key(cat) != key);
+            quote.touch();
+            evictions.untagged.push(quote.clone());
+        }
+    }
+    evictions.removed.reverse();
+    evictions.untagged.reverse();
+    evictions
+}
+
+///Days since the unix epoch, in UTC
+#[must_use]
+pub fn today() -> u64 {
+    now_timestamp() / SECONDS_PER_DAY
+}
+
+///Picks one of the favourites (rated [`MAX_RATING`]) with `seed`, or any quote if there aren't any favourites.
+///
+///`None` if there aren't any quotes at all.
+#[must_use]
+pub fn pick_quote(quotes: &[Quote], seed: u64) -> Option<&Quote> {
+    let favourites: Vec<&Quote> = quotes
+        .iter()
+        .filter(|quote| quote.rating() == Some(MAX_RATING))
+        .collect();
+    let pool = if favourites.is_empty() {
+        quotes.iter().collect()
+    } else {
+        favourites
+    };
+    let len = u64::try_from(pool.len()).ok().filter(|&len| len > 0)?;
+    pool.get(usize::try_from(seed % len).ok()?).copied()
+}
+
+///A different [`pick_quote`] every time
+#[must_use]
+pub fn random_quote(quotes: &[Quote]) -> Option<&Quote> {
+    pick_quote(quotes, RandomState::new().hash_one(now_timestamp()))
+}
+
+///A random quote from `pool` that isn't one of the last `window` in `recently_shown` (oldest first), so "Next" never shows the same quote twice in a row.
+///
+///If the pool is too small to avoid all of them, only the most recent few are avoided - with two quotes, it just alternates. `None` if `pool` is empty.
+#[must_use]
+pub fn next_random<'a>(
+    pool: &'a [Quote],
+    recently_shown: &[Quote],
+    window: usize,
+) -> Option<&'a Quote> {
+    //always leave at least one quote to pick
+    let window = window.min(pool.len().saturating_sub(1));
+    let avoid = &recently_shown[recently_shown.len().saturating_sub(window)..];
+    let candidates: Vec<&Quote> = pool.iter().filter(|quote| !avoid.contains(quote)).collect();
+    //the recent quotes might not all be in the pool, but duplicates in it can rule out more than `window`
+    let candidates = if candidates.is_empty() {
+        pool.iter().collect()
+    } else {
+        candidates
+    };
+
+    let len = u64::try_from(candidates.len())
+        .ok()
+        .filter(|&len| len > 0)?;
+    let seed = RandomState::new().hash_one(now_timestamp());
+    candidates.get(usize::try_from(seed % len).ok()?).copied()
+}
+
+///The same [`pick_quote`] all day, given [`today`]
+#[must_use]
+pub fn quote_of_the_day(quotes: &[Quote], day: u64) -> Option<&Quote> {
+    //spread the days out, so the quotes don't just go in database order
+    pick_quote(quotes, day.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+///Returns every category in `known` that isn't used by any of the quotes
+#[must_use]
+pub fn prune_unused_categories(known: &[String], quotes: &[Quote]) -> Vec<String> {
+    known
+        .iter()
+        .filter(|cat| !quotes.iter().any(|quote| quote.matches_category(cat)))
+        .cloned()
+        .collect()
+}
+
+///Parses a JSON array of quotes from anything readable, eg. stdin
+pub fn read_db_from_reader(reader: impl Read) -> Result<Vec<Quote>, Error> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
 pub fn read_db() -> Result<Vec<Quote>, Error> {
-    let db_content =
-        read_to_string(FileType::Database.get_location()).unwrap_or_else(|_| "[]".into());
+    read_db_at(FileType::Database.get_location())
+}
+
+///[`read_db`] for a database somewhere other than db.json - a missing file is an empty database
+pub fn read_db_at(location: impl AsRef<Path>) -> Result<Vec<Quote>, Error> {
+    let db_content = read_to_string(location).unwrap_or_else(|_| "[]".into());
     let parsed: Vec<Quote> = serde_json::from_str(&db_content)?;
     Ok(parsed)
 }
 
+///Like [`read_db`], but also gives back the [`db_issues`] rather than quietly loading them
+pub fn read_db_strict(
+    known_categories: &[String],
+) -> Result<(Vec<Quote>, Vec<QuoteIssues>), Error> {
+    let db = read_db()?;
+    let issues = db_issues(&db, known_categories);
+    Ok((db, issues))
+}
+
+pub fn write_db(db: &[Quote], location: impl AsRef<Path>) -> Result<(), Error> {
+    write_atomically(location, &serde_json::to_vec(db)?)?;
+    Ok(())
+}
+
+///Like [`write_db`], but one quote that can't be serialised doesn't stop the rest being saved.
+///
+///If the database as a whole fails, each quote is tried on its own and any that fail are left out. Gives back the indices of the quotes left out.
+pub fn write_db_lenient(db: &[Quote], location: &str) -> Result<Vec<usize>, Error> {
+    let (contents, skipped) = to_vec_lenient(db)?;
+    write_atomically(location, &contents)?;
+    Ok(skipped)
+}
+
+///A JSON array of everything in `items` that can be serialised, along with the indices of those that couldn't
+fn to_vec_lenient<T: serde::Serialize>(items: &[T]) -> Result<(Vec<u8>, Vec<usize>), Error> {
+    if let Ok(contents) = serde_json::to_vec(items) {
+        return Ok((contents, vec![]));
+    }
+
+    let mut saved = vec![];
+    let mut skipped = vec![];
+    for (i, item) in items.iter().enumerate() {
+        match serde_json::to_value(item) {
+            Ok(value) => saved.push(value),
+            Err(_) => skipped.push(i),
+        }
+    }
+    Ok((serde_json::to_vec(&saved)?, skipped))
+}
+
 pub fn get_quote(
     category_index: usize,
     item_index: usize,
@@ -68,10 +969,10 @@ pub fn get_quote(
         let db_content = read_to_string(FileType::Database.get_location()).unwrap_or_default();
         serde_json::from_str(&db_content).unwrap_or_default()
     });
-    let q = ALL_PERMS[category_index].to_string();
+    let q = ALL_PERMS[category_index].clone();
 
     db.into_iter()
-        .filter(|quote| quote.1.contains(&q))
+        .filter(|quote| quote.matches_category(&q))
         .nth(item_index)
         .ok_or(Error::QuoteNotFoundIndex(category_index, item_index))
 }
@@ -83,7 +984,36 @@ pub fn get_quote_by_content(content: &str, db: Option<Vec<Quote>>) -> Result<Quo
         .ok_or_else(|| Error::QuoteNotFoundStr(content.to_string()))
 }
 
-pub fn sort_list(db: Option<&mut Vec<Quote>>) -> Result<(), Error> {
+///What [`sort_list_by`] did when it didn't fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOutcome {
+    Sorted,
+    ///Everything was already in order, so the DB file wasn't rewritten
+    AlreadySorted,
+    ///There were no quotes to sort
+    NothingToSort,
+}
+
+///Why sorting the DB file failed - sorting a list in memory can't fail
+#[derive(thiserror::Error, Debug)]
+pub enum SortError {
+    #[error("unable to read the DB to sort it: {0}")]
+    Read(Box<Error>),
+    #[error("unable to save the sorted DB: {0}")]
+    Write(#[from] std::io::Error),
+    #[error("unable to serialise the sorted DB: {0}")]
+    Serialise(#[from] serde_json::Error),
+}
+
+///Sorts by the default [`SortKey`], which is [`text_sort_key`] - see [`sort_list_by`]
+pub fn sort_list(db: Option<&mut Vec<Quote>>) -> Result<SortOutcome, SortError> {
+    sort_list_by(db, SortKey::default())
+}
+
+///Sorts each quote's categories, then the quotes themselves by `key`.
+///
+///The sort is stable, so quotes that compare equal keep their order and sorting a sorted list changes nothing.
+pub fn sort_list_by(db: Option<&mut Vec<Quote>>, key: SortKey) -> Result<SortOutcome, SortError> {
     let do_the_sort = |original: Vec<Quote>| {
         let mut db: Vec<_> = original
             .into_iter()
@@ -91,22 +1021,1489 @@ pub fn sort_list(db: Option<&mut Vec<Quote>>) -> Result<(), Error> {
                 let mut l = quote.1.clone();
                 l.sort();
 
-                Quote(quote.0, l)
+                Quote(quote.0, l, quote.2)
             })
             .collect();
-        db.sort();
+        db.sort_by(|a, b| key.compare(a, b));
         db
     };
 
+    //`Quote`'s `==` ignores category order, which sorting changes too
+    let outcome = |original: &[Quote], sorted: &[Quote]| {
+        if original.is_empty() {
+            SortOutcome::NothingToSort
+        } else if original
+            .iter()
+            .zip(sorted)
+            .all(|(a, b)| a.0 == b.0 && a.1 == b.1)
+        {
+            SortOutcome::AlreadySorted
+        } else {
+            SortOutcome::Sorted
+        }
+    };
+
     if let Some(db) = db {
-        *db = do_the_sort(db.clone());
+        let sorted = do_the_sort(db.clone());
+        let outcome = outcome(db, &sorted);
+        *db = sorted;
+        Ok(outcome)
     } else {
-        let new_db = do_the_sort(read_db()?);
-        std::fs::write(
-            FileType::Database.get_location(),
-            &serde_json::to_vec(&new_db)?,
-        )?;
+        let original = read_db().map_err(|err| SortError::Read(Box::new(err)))?;
+        let sorted = do_the_sort(original.clone());
+        let outcome = outcome(&original, &sorted);
+        if outcome == SortOutcome::Sorted {
+            write_atomically(
+                FileType::Database.get_location(),
+                &serde_json::to_vec(&sorted)?,
+            )?;
+        }
+        Ok(outcome)
     }
+}
 
-    Ok(())
+pub fn all_quotes() -> Result<Vec<Quote>, Error> {
+    read_db()
+}
+
+pub fn quote_count() -> Result<usize, Error> {
+    Ok(read_db()?.len())
+}
+
+pub fn quotes_in_category(name: &str) -> Result<Vec<Quote>, Error> {
+    Ok(in_category(read_db()?, name))
+}
+
+fn in_category(quotes: Vec<Quote>, name: &str) -> Vec<Quote> {
+    quotes
+        .into_iter()
+        .filter(|quote| quote.matches_category(name))
+        .collect()
+}
+
+pub fn find_quote(text: &str) -> Result<Option<Quote>, Error> {
+    Ok(first_containing(read_db()?, text))
+}
+
+///The first quote with `text` in it, ignoring case
+fn first_containing(quotes: Vec<Quote>, text: &str) -> Option<Quote> {
+    let text = text.to_lowercase();
+    quotes
+        .into_iter()
+        .find(|quote| quote.0.to_lowercase().contains(&text))
+}
+
+///Every collection any quote is in, sorted ignoring case - spelled however it was first seen, as collections are matched like categories
+#[must_use]
+pub fn all_collections(quotes: &[Quote]) -> Vec<String> {
+    let mut collections: Vec<String> = vec![];
+    for collection in quotes.iter().flat_map(Quote::collections) {
+        let key = category_key(collection);
+        if !collections
+            .iter()
+            .any(|existing| category_key(existing) == key)
+        {
+            collections.push(collection.clone());
+        }
+    }
+    collections.sort_by_key(|collection| category_key(collection));
+    collections
+}
+
+///Quote texts attributed to more than one author, each with its authors in the order they first turn up, sorted by text.
+///
+///Texts are matched up like [`Quote::dedup_key`] does, and authors ignoring case. Quotes without an author don't count.
+#[must_use]
+pub fn find_attribution_conflicts(quotes: &[Quote]) -> Vec<(String, Vec<String>)> {
+    let mut by_text: HashMap<String, (String, Vec<String>)> = HashMap::new();
+    for quote in quotes {
+        let Some(author) = quote.author().map(str::trim).filter(|a| !a.is_empty()) else {
+            continue;
+        };
+        let (_, authors) = by_text
+            .entry(normalize_text(&quote.0))
+            .or_insert_with(|| (quote.0.clone(), vec![]));
+        if !authors
+            .iter()
+            .any(|a| a.to_lowercase() == author.to_lowercase())
+        {
+            authors.push(author.to_string());
+        }
+    }
+
+    let mut conflicts: Vec<(String, Vec<String>)> = by_text
+        .into_values()
+        .filter(|(_, authors)| authors.len() > 1)
+        .collect();
+    conflicts.sort();
+    conflicts
+}
+
+///Removes quotes with the same [`Quote::dedup_key`] as an earlier one, returning how many were removed.
+///
+///The first copy is kept, with the categories, tags and collections of the later copies added to it (see [`Quote::absorb`]).
+pub fn dedupe(db: &mut Vec<Quote>) -> usize {
+    let before = db.len();
+    let mut kept: Vec<Quote> = Vec::with_capacity(db.len());
+    let mut seen: HashMap<(String, Option<String>), usize> = HashMap::new();
+
+    for quote in db.drain(..) {
+        match seen.entry(quote.dedup_key()) {
+            Entry::Occupied(entry) => {
+                let first = &mut kept[*entry.get()];
+                if first.absorb(&quote) {
+                    first.touch();
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(kept.len());
+                kept.push(quote);
+            }
+        }
+    }
+
+    *db = kept;
+    before - db.len()
+}
+
+///Folds the quote at `drop_index` into the one at `keep_index`, then removes it.
+///
+///The kept quote keeps its text, and gains the other's categories, tags and collections (see [`Quote::absorb`]). Its author, source and attribution are only filled in if it didn't have one, the higher rating wins, and the earlier added time is kept.
+pub fn merge_quotes(
+    db: &mut Vec<Quote>,
+    keep_index: usize,
+    drop_index: usize,
+) -> Result<(), Error> {
+    if keep_index == drop_index || keep_index >= db.len() || drop_index >= db.len() {
+        return Err(Error::InvalidMerge(keep_index, drop_index));
+    }
+
+    let dropped = db.remove(drop_index);
+    let keep_index = if drop_index < keep_index {
+        keep_index - 1
+    } else {
+        keep_index
+    };
+    let kept = &mut db[keep_index];
+
+    kept.absorb(&dropped);
+    if kept.2.author.is_none() {
+        kept.2.author = dropped.2.author;
+    }
+    if kept.2.source.is_none() {
+        kept.2.source = dropped.2.source;
+    }
+    if kept.2.attribution.is_none() {
+        kept.2.attribution = dropped.2.attribution;
+    }
+    kept.2.rating = kept.2.rating.max(dropped.2.rating);
+    kept.2.added = match (kept.2.added, dropped.2.added) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    kept.touch();
+
+    Ok(())
+}
+
+///Runs [`normalize_text`] over every quote, returning how many were changed
+pub fn normalize_all(db: &mut [Quote]) -> usize {
+    let mut changed = 0;
+    for quote in db {
+        let normalized = normalize_text(&quote.0);
+        if normalized != quote.0 {
+            quote.0 = normalized;
+            quote.touch();
+            changed += 1;
+        }
+    }
+    changed
+}
+
+///Which parts of a quote a search term was found in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MatchedFields {
+    pub text: bool,
+    pub author: bool,
+    ///The categories containing the term
+    pub categories: Vec<String>,
+    ///The tags containing the term
+    pub tags: Vec<String>,
+    ///The collections containing the term
+    pub collections: Vec<String>,
+}
+
+impl MatchedFields {
+    #[must_use]
+    pub const fn any(&self) -> bool {
+        self.text
+            || self.author
+            || !self.categories.is_empty()
+            || !self.tags.is_empty()
+            || !self.collections.is_empty()
+    }
+
+    ///A short note on why the quote matched, if it wasn't on its text - eg. `(author)` or `(category: deep)`
+    #[must_use]
+    pub fn note(&self) -> Option<String> {
+        if self.text || !self.any() {
+            return None;
+        }
+
+        let mut reasons = vec![];
+        if self.author {
+            reasons.push("author".to_string());
+        }
+        if !self.categories.is_empty() {
+            reasons.push(format!("category: {}", self.categories.join(", ")));
+        }
+        if !self.tags.is_empty() {
+            reasons.push(format!("tag: {}", self.tags.join(", ")));
+        }
+        if !self.collections.is_empty() {
+            reasons.push(format!("collection: {}", self.collections.join(", ")));
+        }
+        Some(format!("({})", reasons.join(", ")))
+    }
+}
+
+///Looks for `term` in the text, author, categories and tags of a quote
+#[must_use]
+pub fn quote_matches(term: &str, quote: &Quote) -> MatchedFields {
+    MatchedFields {
+        text: quote.0.contains(term),
+        author: quote.author().is_some_and(|author| author.contains(term)),
+        categories: quote
+            .1
+            .iter()
+            .filter(|cat| cat.contains(term))
+            .cloned()
+            .collect(),
+        tags: quote
+            .tags()
+            .iter()
+            .filter(|tag| tag.contains(term.trim_start_matches('#')))
+            .cloned()
+            .collect(),
+        collections: quote
+            .collections()
+            .iter()
+            .filter(|collection| collection.contains(term))
+            .cloned()
+            .collect(),
+    }
+}
+
+///How close a typed category name has to be to a known one (see [`name_similarity`]) for `cat:` to match it
+pub const CATEGORY_MATCH_THRESHOLD: f32 = 0.75;
+
+///One `cat:NAME` in a search, along with the known categories it stands for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CategoryTerm {
+    ///The name as typed
+    pub typed: String,
+    ///The categories a quote can be in to match - just the one for an exact match, otherwise every category close enough to `typed`, closest first
+    pub resolved: Vec<String>,
+    ///Whether `typed` was exactly a known category (ignoring case), rather than a near miss
+    pub exact: bool,
+}
+
+impl CategoryTerm {
+    ///Matches `typed` against `known` - exactly if it can, and otherwise to anything within [`CATEGORY_MATCH_THRESHOLD`], to allow for typos
+    #[must_use]
+    pub fn resolve(typed: &str, known: &[String]) -> Self {
+        if let Some(category) = known
+            .iter()
+            .find(|category| category_key(category) == category_key(typed))
+        {
+            return Self {
+                typed: typed.to_string(),
+                resolved: vec![category.clone()],
+                exact: true,
+            };
+        }
+
+        let mut close: Vec<(f32, &String)> = known
+            .iter()
+            .map(|category| (name_similarity(typed, category), category))
+            .filter(|&(score, _)| score >= CATEGORY_MATCH_THRESHOLD)
+            .collect();
+        close.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Self {
+            typed: typed.to_string(),
+            resolved: close
+                .into_iter()
+                .map(|(_, category)| category.clone())
+                .collect(),
+            exact: false,
+        }
+    }
+}
+
+///A search term with any `cat:NAME` operators taken out, eg. `cat:funy cats` looks for "cats" in the funny category
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    ///Everything that wasn't an operator, to go to [`quote_matches`]
+    pub text: String,
+    pub categories: Vec<CategoryTerm>,
+}
+
+impl SearchQuery {
+    ///`known` is every category, for the `cat:` operators to be matched against
+    #[must_use]
+    pub fn parse(term: &str, known: &[String]) -> Self {
+        let mut text = vec![];
+        let mut categories = vec![];
+        for word in term.split_whitespace() {
+            match word.strip_prefix("cat:") {
+                Some(name) if !name.is_empty() => {
+                    categories.push(CategoryTerm::resolve(name, known));
+                }
+                _ => text.push(word),
+            }
+        }
+        Self {
+            text: text.join(" "),
+            categories,
+        }
+    }
+
+    ///Whether `quote` is in one of the categories each `cat:` resolved to
+    #[must_use]
+    pub fn matches_categories(&self, quote: &Quote) -> bool {
+        self.categories
+            .iter()
+            .all(|term| quote.matches_any(&term.resolved))
+    }
+}
+
+///Indices of every quote matching `term` (see [`quote_matches`]), best matches first.
+///
+///Whole-word matches beat partial ones, then earlier matches beat later ones, and quotes that only matched on their author, categories, tags or collections come last.
+///Ties go by text and then by position in `quotes`, so the order is the same every frame.
+#[must_use]
+pub fn rank_substring_matches(term: &str, quotes: &[Quote]) -> Vec<usize> {
+    let mut ranked: Vec<(usize, (bool, usize))> = quotes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, quote)| {
+            substring_score(term, &quote.0)
+                .or_else(|| {
+                    quote_matches(term, quote)
+                        .any()
+                        .then_some((true, usize::MAX))
+                })
+                .map(|score| (i, score))
+        })
+        .collect();
+    ranked.sort_by(|&(a, a_score), &(b, b_score)| {
+        a_score
+            .cmp(&b_score)
+            .then_with(|| quotes[a].0.cmp(&quotes[b].0))
+            .then(a.cmp(&b))
+    });
+    ranked.into_iter().map(|(i, _)| i).collect()
+}
+
+///What counts as part of a word when looking for whole-word matches - apostrophes too, so `don't` is one word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+///`(is_partial, position)` of the best occurrence of `term`, so that lower is better
+fn substring_score(term: &str, text: &str) -> Option<(bool, usize)> {
+    let mut first = None;
+
+    for (pos, _) in text.match_indices(term) {
+        let before = text[..pos].chars().next_back();
+        let after = text[pos + term.len()..].chars().next();
+        if !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char) {
+            return Some((false, pos));
+        }
+        first.get_or_insert((true, pos));
+    }
+
+    first
+}
+
+///How [`search_quotes`] looks for its term
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub case_insensitive: bool,
+    ///Only match the term where it isn't part of a longer word
+    pub whole_word: bool,
+}
+
+///Where the term turned up in the text of one quote
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    ///Index of the quote in the database
+    pub index: usize,
+    ///Byte ranges of each match in the text, in order and not overlapping
+    pub byte_ranges: Vec<Range<usize>>,
+    ///The same matches as `byte_ranges`, counted in chars rather than bytes
+    pub char_ranges: Vec<Range<usize>>,
+}
+
+///The byte length of the match of `term` at the very start of `text`, if there is one
+fn match_at(text: &str, term: &str, case_insensitive: bool) -> Option<usize> {
+    let mut text_chars = text.char_indices();
+    for term_char in term.chars() {
+        let (_, text_char) = text_chars.next()?;
+        let same = if case_insensitive {
+            text_char.to_lowercase().eq(term_char.to_lowercase())
+        } else {
+            text_char == term_char
+        };
+        if !same {
+            return None;
+        }
+    }
+    Some(text_chars.next().map_or(text.len(), |(end, _)| end))
+}
+
+///Byte ranges of every (non-overlapping) match of `term` in `text`
+fn match_ranges(text: &str, term: &str, options: SearchOptions) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut from = 0;
+    while from < text.len() {
+        let Some(length) = match_at(&text[from..], term, options.case_insensitive) else {
+            from += text[from..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        let end = from + length;
+        let whole = !text[..from].chars().next_back().is_some_and(is_word_char)
+            && !text[end..].chars().next().is_some_and(is_word_char);
+        if options.whole_word && !whole {
+            from += text[from..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        ranges.push(from..end);
+        from = end;
+    }
+    ranges
+}
+
+///Every quote whose text contains `term`, in database order, along with where it matched - for frontends doing their own highlighting.
+///
+///Only the text is searched, unlike [`quote_matches`]. An empty `term` matches nothing.
+#[must_use]
+pub fn search_quotes(db: &[Quote], term: &str, options: SearchOptions) -> Vec<SearchHit> {
+    if term.is_empty() {
+        return vec![];
+    }
+
+    db.iter()
+        .enumerate()
+        .filter_map(|(index, quote)| {
+            let byte_ranges = match_ranges(&quote.0, term, options);
+            if byte_ranges.is_empty() {
+                return None;
+            }
+            let char_offset = |byte: usize| quote.0[..byte].chars().count();
+            let char_ranges = byte_ranges
+                .iter()
+                .map(|range| char_offset(range.start)..char_offset(range.end))
+                .collect();
+            Some(SearchHit {
+                index,
+                byte_ranges,
+                char_ranges,
+            })
+        })
+        .collect()
+}
+
+///Lowercased words, ignoring punctuation
+fn word_set(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+///How alike two names are letter by letter, ignoring case (0.0 to 1.0) - one minus the edit distance over the longer length.
+///
+///Unlike [`similarity`], which goes by whole words, this copes with a single mistyped word like `funy`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn name_similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    //the previous row of the edit distance table
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, &a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f32 / longest as f32
+}
+
+///How many words two quotes share, out of all the words in either (0.0 to 1.0)
+#[must_use]
+pub fn similarity(a: &str, b: &str) -> f32 {
+    overlap(&word_set(a), &word_set(b))
+}
+
+///Every pair of quotes at least `threshold` similar (see [`similarity`]), most similar first, as `(index, index, similarity)`.
+///
+///Exact duplicates show up with a similarity of 1.0, and pairs that are just as similar go by their indices.
+#[must_use]
+pub fn find_near_duplicates(quotes: &[Quote], threshold: f32) -> Vec<(usize, usize, f32)> {
+    let words: Vec<HashSet<String>> = quotes.iter().map(|quote| word_set(&quote.0)).collect();
+
+    let mut pairs = vec![];
+    for (i, a) in words.iter().enumerate() {
+        for (j, b) in words.iter().enumerate().skip(i + 1) {
+            let score = overlap(a, b);
+            //quotes without any words would otherwise match everything at a threshold of 0
+            if !a.is_empty() && !b.is_empty() && score >= threshold {
+                pairs.push((i, j, score));
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    fn quote(text: &str, categories: &[&str]) -> Quote {
+        Quote::new(text, categories.iter().map(ToString::to_string).collect())
+    }
+
+    fn with_author(text: &str, author: Option<&str>) -> Quote {
+        let mut quote = quote(text, &[]);
+        quote.2.author = author.map(ToString::to_string);
+        quote
+    }
+
+    fn texts(quotes: &[Quote]) -> Vec<&str> {
+        quotes.iter().map(|quote| quote.0.as_str()).collect()
+    }
+
+    #[test]
+    fn sorting_by_author_puts_authorless_quotes_last() {
+        let mut quotes = vec![
+            with_author("nobody b", None),
+            with_author("zed", Some("Zed")),
+            with_author("nobody a", None),
+            with_author("amy 2", Some("Amy")),
+            with_author("amy 1", Some("amy ")),
+            with_author("alice", Some("alice")),
+            with_author("bob", Some(" Bob")),
+        ];
+        sort_list_by(Some(&mut quotes), SortKey::Author).unwrap();
+        //case and surrounding whitespace don't matter
+        assert_eq!(
+            texts(&quotes),
+            ["alice", "amy 1", "amy 2", "bob", "zed", "nobody a", "nobody b"]
+        );
+    }
+
+    #[test]
+    fn adding_can_normalize_the_text() {
+        let mut db = vec![];
+        add_quote_to_db(
+            quote("  \u{201C}Hi\u{201D}\u{A0} there ", &["Stella"]),
+            Some(&mut db),
+            true,
+            PunctuationRules::default(),
+        )
+        .unwrap();
+        add_quote_to_db(
+            quote("  left  alone ", &["Stella"]),
+            Some(&mut db),
+            false,
+            PunctuationRules::default(),
+        )
+        .unwrap();
+        assert_eq!(texts(&db), ["\"Hi\" there", "  left  alone "]);
+    }
+
+    #[test]
+    fn duplicates_go_by_the_dedup_key() {
+        let db = vec![with_author("X", Some("A")), with_author("Y", None)];
+        assert_eq!(validate_quote(&with_author("X", Some("B")), &db), Ok(()));
+        assert_eq!(validate_quote(&with_author("X", None), &db), Ok(()));
+        assert_eq!(
+            validate_quote(&with_author("X ", Some(" a")), &db),
+            Err(ValidationError::Duplicate)
+        );
+        assert_eq!(
+            validate_quote(&with_author(" Y", None), &db),
+            Err(ValidationError::Duplicate)
+        );
+        assert_eq!(
+            validate_quote(&with_author("  ", None), &db),
+            Err(ValidationError::Empty)
+        );
+
+        //a quote doesn't clash with itself, but can be edited into another
+        assert_eq!(validate_edit(&with_author("X", Some("a")), &db, 0), Ok(()));
+        assert_eq!(
+            validate_edit(&with_author("X", Some("a")), &db, 1),
+            Err(ValidationError::Duplicate)
+        );
+
+        let mut db = db;
+        add_quote_to_db(
+            with_author("X", Some("B")),
+            Some(&mut db),
+            false,
+            PunctuationRules::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            add_quote_to_db(
+                with_author("X ", Some(" a")),
+                Some(&mut db),
+                false,
+                PunctuationRules::default(),
+            ),
+            Err(Error::Validation(ValidationError::Duplicate))
+        ));
+        assert_eq!(texts(&db), ["X", "Y", "X"]);
+    }
+
+    #[test]
+    fn tagging_skips_tags_already_there() {
+        let mut db = vec![quote("a", &[]), quote("b", &[]), quote("c", &[])];
+        db[1].2.tags = vec!["Fog".to_string()];
+        let tags = vec!["fog".to_string(), "grey".to_string()];
+
+        assert_eq!(tag_quotes(&mut db, [1, 2, 7], &tags), 2);
+        assert!(db[0].tags().is_empty());
+        assert_eq!(db[1].tags(), ["Fog", "grey"]);
+        assert_eq!(db[2].tags(), ["fog", "grey"]);
+        assert!(db[2].modified().is_some());
+
+        //nothing new the second time
+        assert_eq!(tag_quotes(&mut db, [1, 2], &tags), 0);
+    }
+
+    fn trashed(text: &str, deleted: u64) -> TrashedQuote {
+        TrashedQuote {
+            quote: quote(text, &[]),
+            deleted,
+        }
+    }
+
+    #[test]
+    fn trashing_moves_quotes_into_the_trash() {
+        let mut db = vec![quote("a", &[]), quote("b", &[]), quote("c", &[])];
+        let mut trash = vec![];
+
+        let before = now_timestamp();
+        trash_quote(&quote("b", &[]), &mut db, &mut trash).unwrap();
+        assert_eq!(texts(&db), ["a", "c"]);
+        assert_eq!(trash[0].quote.0, "b");
+        assert!(trash[0].deleted >= before);
+        assert!(matches!(
+            trash_quote(&quote("b", &[]), &mut db, &mut trash),
+            Err(Error::QuoteNotFoundInDB(_))
+        ));
+
+        //repeats and indices past the end are ignored
+        assert_eq!(trash_quotes(&mut db, &[1, 0, 1, 9], &mut trash), 2);
+        assert!(db.is_empty());
+        assert_eq!(trash.len(), 3);
+    }
+
+    #[test]
+    fn restoring_puts_quotes_back_at_the_end() {
+        let mut db = vec![quote("a", &[]), quote("b", &[])];
+        let mut trash = vec![trashed("old", 1), trashed("older", 0)];
+
+        restore_quote(&mut trash, 1, &mut db).unwrap();
+        assert_eq!(texts(&db), ["a", "b", "older"]);
+        assert_eq!(trash.len(), 1);
+        assert!(matches!(
+            restore_quote(&mut trash, 1, &mut db),
+            Err(Error::NoQuoteAt(1))
+        ));
+    }
+
+    #[test]
+    fn restoring_is_refused_while_a_duplicate_is_there() {
+        let mut db = vec![with_author("Fog", Some("Anne"))];
+        let mut trash = vec![TrashedQuote {
+            quote: with_author("Fog ", Some("anne")),
+            deleted: 0,
+        }];
+        assert!(matches!(
+            restore_quote(&mut trash, 0, &mut db),
+            Err(Error::Validation(ValidationError::Duplicate))
+        ));
+        assert_eq!(trash.len(), 1);
+        assert_eq!(db.len(), 1);
+
+        //the same words by someone else are fine
+        db[0].2.author = Some("Bea".to_string());
+        restore_quote(&mut trash, 0, &mut db).unwrap();
+        assert!(trash.is_empty());
+        assert_eq!(db[1].author(), Some("anne"));
+    }
+
+    #[test]
+    fn purging_keeps_anything_deleted_at_the_cutoff() {
+        let mut trash = vec![
+            trashed("before", 999),
+            trashed("at", 1000),
+            trashed("after", 1001),
+        ];
+        assert_eq!(purge_trash_before(&mut trash, 1000), 1);
+        let left: Vec<&str> = trash
+            .iter()
+            .map(|trashed| trashed.quote.0.as_str())
+            .collect();
+        assert_eq!(left, ["at", "after"]);
+
+        let now = now_timestamp();
+        let mut trash = vec![
+            trashed("just over a day", now - SECONDS_PER_DAY - 60),
+            trashed("just under a day", now - SECONDS_PER_DAY + 60),
+        ];
+        assert_eq!(purge_trash(&mut trash, 1), 1);
+        assert_eq!(trash[0].quote.0, "just under a day");
+        //nothing's deleted in the future, so 0 days empties it
+        assert_eq!(purge_trash(&mut trash, 0), 1);
+        assert!(trash.is_empty());
+    }
+
+    #[test]
+    fn every_issue_is_found() {
+        let known = vec!["Stella".to_string(), "Esme".to_string()];
+        let issues = |edit: fn(&mut Quote)| {
+            let mut quote = quote("Fine", &[" stella", "ESME "]);
+            edit(&mut quote);
+            quote_issues(&quote, &known)
+        };
+
+        //categories are matched ignoring case and surrounding whitespace
+        assert_eq!(issues(|_| {}), []);
+        assert_eq!(issues(|quote| quote.0 = "  ".into()), [Issue::EmptyText]);
+        assert_eq!(
+            issues(|quote| quote.0 = "a".repeat(MAX_QUOTE_LENGTH + 1)),
+            [Issue::TooLong {
+                length: MAX_QUOTE_LENGTH + 1
+            }]
+        );
+        assert_eq!(
+            issues(|quote| quote.1.push("Nadia".into())),
+            [Issue::UnknownCategory("Nadia".into())]
+        );
+        assert_eq!(
+            issues(|quote| quote.2.author = Some(" ".into())),
+            [Issue::BlankAuthor]
+        );
+        assert_eq!(
+            issues(|quote| quote.2.source = Some("not a link".into())),
+            [Issue::InvalidSource("not a link".into())]
+        );
+        assert_eq!(
+            issues(|quote| quote.2.rating = Some(0)),
+            [Issue::InvalidRating(0)]
+        );
+        assert_eq!(
+            issues(|quote| quote.2.rating = Some(MAX_RATING + 1)),
+            [Issue::InvalidRating(MAX_RATING + 1)]
+        );
+        assert_eq!(
+            issues(|quote| quote.2.tags = vec!["ok".into(), " ".into()]),
+            [Issue::BlankTag]
+        );
+    }
+
+    #[test]
+    fn normalize_all_counts_and_touches_what_changed() {
+        let mut db = vec![quote("fine", &[]), quote("not\u{A0}\u{A0}fine", &[])];
+        assert_eq!(normalize_all(&mut db), 1);
+        assert_eq!(texts(&db), ["fine", "not fine"]);
+        assert_eq!(db[0].modified(), None);
+        assert!(db[1].modified().is_some());
+    }
+
+    #[test]
+    fn similarity_goes_by_shared_words() {
+        assert!((similarity("The fog is thick!", "the fog is THICK") - 1.0).abs() < f32::EPSILON);
+        assert!((similarity("the fog is thick", "the fog is thick tonight") - 0.8).abs() < 1e-6);
+        assert!(similarity("the fog", "a cat").abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn near_duplicates_are_most_similar_first() {
+        let quotes = vec![
+            quote("the fog is thick", &[]),
+            quote("something else entirely", &[]),
+            quote("the fog is thick tonight", &[]),
+            quote("The fog is thick.", &[]),
+            quote("...", &[]),
+            quote("!!!", &[]),
+        ];
+        let pairs = find_near_duplicates(&quotes, 0.75);
+        let indices: Vec<(usize, usize)> = pairs.iter().map(|&(i, j, _)| (i, j)).collect();
+        assert_eq!(indices, [(0, 3), (0, 2), (2, 3)]);
+        assert!((pairs[0].2 - 1.0).abs() < f32::EPSILON);
+
+        assert_eq!(find_near_duplicates(&quotes, 0.9).len(), 1);
+        //quotes without any words never match, even at 0
+        assert!(find_near_duplicates(&quotes, 0.0)
+            .iter()
+            .all(|&(i, j, _)| i < 4 && j < 4));
+    }
+
+    #[test]
+    fn sort_list_sorts_by_trimmed_lowercase_text() {
+        let mut quotes = vec![
+            quote("  banana", &[]),
+            quote("Cherry", &[]),
+            quote("apple ", &[]),
+        ];
+        assert_eq!(sort_list(Some(&mut quotes)).unwrap(), SortOutcome::Sorted);
+        assert_eq!(texts(&quotes), ["apple ", "  banana", "Cherry"]);
+    }
+
+    #[test]
+    fn sorting_a_sorted_list_changes_nothing() {
+        let mut quotes = vec![quote("b", &["Esme", "Stella"]), quote("a", &["Stella"])];
+        sort_list(Some(&mut quotes)).unwrap();
+        let sorted = quotes.clone();
+        assert_eq!(
+            sort_list(Some(&mut quotes)).unwrap(),
+            SortOutcome::AlreadySorted
+        );
+        assert_eq!(texts(&quotes), texts(&sorted));
+        assert_eq!(
+            sort_list(Some(&mut vec![])).unwrap(),
+            SortOutcome::NothingToSort
+        );
+    }
+
+    #[test]
+    fn sorting_is_stable() {
+        let mut quotes = vec![
+            with_author("Same", Some("first")),
+            quote("earlier", &[]),
+            with_author("same ", Some("second")),
+        ];
+        sort_list(Some(&mut quotes)).unwrap();
+        let authors: Vec<_> = quotes.iter().map(Quote::author).collect();
+        assert_eq!(authors, [None, Some("first"), Some("second")]);
+    }
+
+    #[test]
+    fn sort_list_uses_the_default_key() {
+        assert_eq!(SortKey::default(), SortKey::Text);
+    }
+
+    #[test]
+    fn attribution_conflicts_need_two_different_authors() {
+        let quotes = vec![
+            with_author("Two of them", Some("Anne")),
+            with_author("One twice", Some("Bea")),
+            with_author("Two  of them ", Some("Cal")),
+            with_author("One twice", Some(" bea")),
+            with_author("Unattributed", None),
+            with_author("Unattributed", Some("Dee")),
+            with_author("Blank", Some("Eve")),
+            with_author("Blank", Some("  ")),
+            with_author("Two of them", Some("ANNE")),
+        ];
+        assert_eq!(
+            find_attribution_conflicts(&quotes),
+            [(
+                "Two of them".to_string(),
+                vec!["Anne".to_string(), "Cal".to_string()]
+            )]
+        );
+        assert!(find_attribution_conflicts(&[]).is_empty());
+    }
+
+    #[test]
+    fn dedupe_keeps_the_same_words_from_different_authors() {
+        let mut db = vec![
+            with_author("To be or not to be", Some("Shakespeare")),
+            with_author("To be  or not to be", Some("Someone Else")),
+            with_author("To be or not to be", None),
+        ];
+        assert_eq!(dedupe(&mut db), 0);
+        assert_eq!(db.len(), 3);
+    }
+
+    #[test]
+    fn dedupe_merges_identical_quotes_into_the_first() {
+        let mut first = with_author("To be or not to be", Some("Shakespeare"));
+        first.1 = vec!["Stella".to_string()];
+        let mut second = with_author(" To be \u{A0}or not to be", Some("shakespeare"));
+        second.1 = vec!["Esme".to_string(), "Stella".to_string()];
+        second.2.tags = vec!["hamlet".to_string()];
+        second.2.collections = vec!["Plays".to_string()];
+        let mut db = vec![first, quote("other", &[]), second];
+
+        assert_eq!(dedupe(&mut db), 1);
+        assert_eq!(texts(&db), ["To be or not to be", "other"]);
+        assert_eq!(db[0].1, ["Stella", "Esme"]);
+        assert_eq!(db[0].tags(), ["hamlet"]);
+        assert_eq!(db[0].collections(), ["Plays"]);
+    }
+
+    #[test]
+    fn merging_keeps_one_text_and_unions_the_rest() {
+        let mut keep = quote("Kept words", &["Stella"]);
+        keep.2.tags = vec!["fog".to_string()];
+        keep.2.collections = vec!["Openers".to_string()];
+        keep.2.rating = Some(2);
+        keep.2.added = Some(200);
+        let mut drop = with_author("Dropped words", Some("Anne"));
+        drop.1 = vec![" stella".to_string(), "Esme".to_string()];
+        drop.2.tags = vec!["Fog".to_string(), "marsh".to_string()];
+        drop.2.collections = vec!["openers ".to_string(), "Favourites".to_string()];
+        drop.2.source = Some("https://example.com".to_string());
+        drop.2.rating = Some(4);
+        drop.2.added = Some(100);
+        let mut db = vec![drop, quote("bystander", &[]), keep];
+
+        merge_quotes(&mut db, 2, 0).unwrap();
+        assert_eq!(texts(&db), ["bystander", "Kept words"]);
+        let merged = &db[1];
+        assert_eq!(merged.1, ["Stella", "Esme"]);
+        assert_eq!(merged.tags(), ["fog", "marsh"]);
+        //collections survive the merge too
+        assert_eq!(merged.collections(), ["Openers", "Favourites"]);
+        assert_eq!(merged.author(), Some("Anne"));
+        assert_eq!(merged.source(), Some("https://example.com"));
+        assert_eq!(merged.rating(), Some(4));
+        assert_eq!(merged.2.added, Some(100));
+        assert!(merged.modified().is_some());
+    }
+
+    #[test]
+    fn merging_never_overwrites_what_the_kept_quote_has() {
+        let mut db = vec![
+            with_author("keep", Some("Anne")),
+            with_author("drop", Some("Bea")),
+        ];
+        merge_quotes(&mut db, 0, 1).unwrap();
+        assert_eq!(db.len(), 1);
+        assert_eq!(db[0].author(), Some("Anne"));
+    }
+
+    #[test]
+    fn merging_needs_two_different_quotes() {
+        let mut db = vec![quote("a", &[]), quote("b", &[])];
+        assert!(merge_quotes(&mut db, 0, 0).is_err());
+        assert!(merge_quotes(&mut db, 0, 2).is_err());
+        assert_eq!(db.len(), 2);
+    }
+
+    ///Fails to serialise when it's `None`, like a quote with some state JSON can't hold
+    struct Fussy(Option<&'static str>);
+
+    impl serde::Serialize for Fussy {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.map_or_else(
+                || Err(serde::ser::Error::custom("can't be saved")),
+                |text| serializer.serialize_str(text),
+            )
+        }
+    }
+
+    #[test]
+    fn lenient_saving_skips_only_what_fails() {
+        let (contents, skipped) =
+            to_vec_lenient(&[Fussy(Some("a")), Fussy(None), Fussy(Some("c")), Fussy(None)])
+                .unwrap();
+        assert_eq!(String::from_utf8(contents).unwrap(), r#"["a","c"]"#);
+        assert_eq!(skipped, [1, 3]);
+
+        let (contents, skipped) = to_vec_lenient(&[Fussy(Some("a"))]).unwrap();
+        assert_eq!(contents, br#"["a"]"#);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn equal_scores_are_ranked_by_text_then_position() {
+        let quotes = vec![
+            quote("fog c", &[]),
+            quote("fog a", &[]),
+            with_author("no match", Some("fog")),
+            quote("fog b", &[]),
+            quote("fog a", &[]),
+            quote("foggy", &[]),
+            quote("the fog", &[]),
+        ];
+        let ranked = rank_substring_matches("fog", &quotes);
+        assert_eq!(ranked, [1, 4, 3, 0, 6, 5, 2]);
+        //and it's the same every time
+        for _ in 0..10 {
+            assert_eq!(rank_substring_matches("fog", &quotes), ranked);
+        }
+    }
+
+    #[test]
+    fn in_category_ignores_case_and_whitespace() {
+        let quotes = vec![
+            quote("one", &["Stella"]),
+            quote("two", &["Esme"]),
+            quote("three", &[" stella ", "Esme"]),
+        ];
+        let found = in_category(quotes, "STELLA");
+        assert_eq!(
+            found,
+            vec![
+                quote("one", &["Stella"]),
+                quote("three", &[" stella ", "Esme"])
+            ]
+        );
+        assert!(in_category(vec![quote("one", &["Stella"])], "Arthur").is_empty());
+    }
+
+    #[test]
+    fn first_containing_finds_the_first_match_ignoring_case() {
+        let quotes = vec![quote("The fog rolled in", &[]), quote("Fog again", &[])];
+        assert_eq!(
+            first_containing(quotes.clone(), "FOG").map(|quote| quote.0),
+            Some("The fog rolled in".to_string())
+        );
+        assert_eq!(first_containing(quotes, "sunshine"), None);
+    }
+
+    #[test]
+    fn read_db_from_reader_parses_a_quote_array() {
+        let quotes = read_db_from_reader(
+            r#"[["Hello", ["Stella"]], ["World", [], {"author": "Me"}]]"#.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[1].author(), Some("Me"));
+        assert!(read_db_from_reader(&b"not json"[..]).is_err());
+    }
+
+    #[test]
+    fn in_memory_storage_never_needs_the_file() {
+        let mut storage = Storage::InMemory(vec![quote("b", &["Stella"])]);
+        storage
+            .add(quote("a", &[]), false, PunctuationRules::default())
+            .unwrap();
+        assert_eq!(texts(&storage.read().unwrap()), ["b", "a"]);
+        assert_eq!(storage.read().unwrap()[1].1, ["Other"]);
+
+        assert_eq!(storage.sort(SortKey::Text).unwrap(), SortOutcome::Sorted);
+        storage.edit(0, |quote| quote.0 = "c".into()).unwrap();
+        assert_eq!(texts(&storage.read().unwrap()), ["c", "b"]);
+
+        storage.remove(&quote("b", &["Stella"])).unwrap();
+        assert_eq!(texts(&storage.read().unwrap()), ["c"]);
+        assert!(storage.remove(&quote("b", &["Stella"])).is_err());
+        assert!(storage.edit(5, |_| ()).is_err());
+    }
+
+    ///Fails with `kind` the first `failures` times it's called
+    fn failing(
+        failures: u32,
+        kind: ErrorKind,
+    ) -> (impl FnMut() -> std::io::Result<u32>, Rc<Cell<u32>>) {
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let attempt = move || {
+            counter.set(counter.get() + 1);
+            if counter.get() <= failures {
+                Err(kind.into())
+            } else {
+                Ok(counter.get())
+            }
+        };
+        (attempt, calls)
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        let (attempt, calls) = failing(2, ErrorKind::Interrupted);
+        assert_eq!(retry_with_backoff(2, attempt).unwrap(), 3);
+        assert_eq!(calls.get(), 3);
+
+        let (attempt, calls) = failing(3, ErrorKind::PermissionDenied);
+        assert_eq!(
+            retry_with_backoff(2, attempt).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn other_errors_arent_retried() {
+        let (attempt, calls) = failing(1, ErrorKind::NotFound);
+        assert_eq!(
+            retry_with_backoff(2, attempt).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(calls.get(), 1);
+
+        let (attempt, calls) = failing(0, ErrorKind::Interrupted);
+        assert_eq!(retry_with_backoff(0, attempt).unwrap(), 1);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_whole_file() {
+        let dir =
+            std::env::temp_dir().join(format!("english_quotes_atomic_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let location = dir.join("db.json");
+
+        write_atomically(&location, b"a much longer first version").unwrap();
+        write_atomically(&location, b"second").unwrap();
+        assert_eq!(std::fs::read(&location).unwrap(), b"second");
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            1,
+            "no .tmp file is left"
+        );
+
+        assert!(write_atomically(dir.join("missing").join("db.json"), b"x").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn batches_merge_on_the_dedup_key() {
+        let mut db = vec![with_author("Fog", Some("Dickens"))];
+        db[0].1 = vec!["Stella".into()];
+
+        let mut same = with_author("Fog ", Some(" dickens"));
+        same.1 = vec!["Esme".into()];
+        let mut again = with_author("Fog", Some("Dickens"));
+        again.1 = vec!["Stella".into()];
+        //the same category spelled differently is nothing new
+        let mut respelled = with_author("Fog", Some("Dickens"));
+        respelled.1 = vec!["stella".into(), " ESME".into()];
+        let result = add_quotes_batch(
+            vec![
+                same,
+                again,
+                respelled,
+                with_author("Fog", Some("Someone Else")),
+                with_author("Fog", None),
+                with_author("Rain", None),
+                with_author("Rain", None),
+                with_author("  ", None),
+            ],
+            &mut db,
+            false,
+            PunctuationRules::default(),
+        );
+
+        assert_eq!(
+            result,
+            BatchResult {
+                added: 3,
+                skipped: 4,
+                merged: 1,
+            }
+        );
+        assert_eq!(texts(&db), ["Fog", "Fog", "Fog", "Rain"]);
+        assert_eq!(db[0].1, ["Stella", "Esme"]);
+        assert_eq!(db[1].author(), Some("Someone Else"));
+        assert_eq!(db[2].author(), None);
+    }
+
+    #[test]
+    fn the_store_hook_sees_every_change() {
+        let seen: Rc<RefCell<Vec<Vec<String>>>> = Rc::default();
+        let log = seen.clone();
+        let mut store =
+            Store::new(Storage::InMemory(vec![quote("b", &["Stella"])])).on_change(move |quotes| {
+                log.borrow_mut()
+                    .push(texts(quotes).iter().map(ToString::to_string).collect());
+            });
+
+        store
+            .add(quote("a", &["Stella"]), false, PunctuationRules::default())
+            .unwrap();
+        store.sort(SortKey::Text).unwrap();
+        store.edit(0, |quote| quote.0 = "c".into()).unwrap();
+        store.remove(&quote("b", &["Stella"])).unwrap();
+        assert_eq!(
+            *seen.borrow(),
+            [vec!["b", "a"], vec!["a", "b"], vec!["c", "b"], vec!["c"]]
+        );
+
+        //nothing changed, or something failed
+        store.sort(SortKey::Text).unwrap();
+        store.edit(0, |_| ()).unwrap();
+        assert!(store.remove(&quote("b", &["Stella"])).is_err());
+        assert!(store
+            .add(quote("c", &["Stella"]), false, PunctuationRules::default())
+            .is_err());
+        assert_eq!(seen.borrow().len(), 4);
+        assert_eq!(texts(&store.read().unwrap()), ["c"]);
+    }
+
+    #[test]
+    fn transactions_are_all_or_nothing() {
+        let mut storage = Storage::InMemory(vec![quote("a", &["Stella"]), quote("b", &["Stella"])]);
+
+        let failed: Result<(), Error> = storage.transaction(|db| {
+            db.push(quote("c", &["Stella"]));
+            db.remove(0);
+            Err(Error::NoQuoteAt(5))
+        });
+        assert!(matches!(failed, Err(Error::NoQuoteAt(5))));
+        assert_eq!(texts(&storage.read().unwrap()), ["a", "b"]);
+
+        let removed = storage
+            .transaction(|db| {
+                db.push(quote("c", &["Stella"]));
+                Ok(db.remove(0))
+            })
+            .unwrap();
+        assert_eq!(removed.0, "a");
+        assert_eq!(texts(&storage.read().unwrap()), ["b", "c"]);
+    }
+
+    #[test]
+    fn transactions_notice_metadata_changes() {
+        let mut storage = Storage::InMemory(vec![quote("a", &["Stella"])]);
+        assert!(!storage.run_transaction(|_| Ok(())).unwrap().1);
+        //`==` would miss this, as it only looks at the text and categories
+        let ((), changed) = storage
+            .run_transaction(|db| {
+                db[0].2.author = Some("Me".into());
+                Ok(())
+            })
+            .unwrap();
+        assert!(changed);
+        assert_eq!(storage.read().unwrap()[0].author(), Some("Me"));
+    }
+
+    #[test]
+    fn failed_file_transactions_leave_the_file_alone() {
+        let dir = std::env::temp_dir().join(format!("english_quotes_tx_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let location = dir.join("db.json");
+        write_db(&[quote("a", &["Stella"])], &location).unwrap();
+        let before = std::fs::read(&location).unwrap();
+
+        let failed: Result<(), Error> = with_db_transaction_at(&location, |db| {
+            db.push(quote("b", &["Stella"]));
+            Err(Error::NoQuoteAt(5))
+        });
+        assert!(matches!(failed, Err(Error::NoQuoteAt(5))));
+        assert_eq!(std::fs::read(&location).unwrap(), before);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_transactions_write_once_at_the_end() {
+        let dir =
+            std::env::temp_dir().join(format!("english_quotes_tx_write_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let location = dir.join("db.json");
+        //pretty printed, so any write at all would change the bytes
+        std::fs::write(
+            &location,
+            serde_json::to_vec_pretty(&[quote("a", &["Stella"])]).unwrap(),
+        )
+        .unwrap();
+        let before = std::fs::read(&location).unwrap();
+
+        with_db_transaction_at(&location, |_| Ok(())).unwrap();
+        assert_eq!(std::fs::read(&location).unwrap(), before, "nothing changed");
+
+        with_db_transaction_at(&location, |db| {
+            db.push(quote("b", &["Stella"]));
+            assert_eq!(std::fs::read(&location).unwrap(), before);
+            db.push(quote("c", &["Stella"]));
+            assert_eq!(std::fs::read(&location).unwrap(), before);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(texts(&read_db_at(&location).unwrap()), ["a", "b", "c"]);
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            1,
+            "no .tmp file is left"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn search_gives_back_where_it_matched() {
+        let db = vec![
+            quote("Café au lait, café noir", &[]),
+            quote("No match here", &[]),
+            quote("CAFÉ", &[]),
+        ];
+
+        let hits = search_quotes(&db, "café", SearchOptions::default());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index, 0);
+        assert_eq!(hits[0].byte_ranges, [15..20]);
+        assert_eq!(hits[0].char_ranges, [14..18]);
+
+        let hits = search_quotes(
+            &db,
+            "café",
+            SearchOptions {
+                case_insensitive: true,
+                ..SearchOptions::default()
+            },
+        );
+        assert_eq!(hits.iter().map(|hit| hit.index).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(hits[0].char_ranges, [0..4, 14..18]);
+        assert_eq!(hits[1].byte_ranges, [0..5]);
+
+        assert!(search_quotes(&db, "", SearchOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn whole_word_search() {
+        let db = vec![quote("cat catalogue cat's scat cat", &[])];
+        let whole_word = SearchOptions {
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        //`cat's` is a word of its own
+        assert_eq!(
+            search_quotes(&db, "cat", whole_word)[0].byte_ranges,
+            [0..3, 25..28]
+        );
+        assert_eq!(
+            search_quotes(&db, "cat", SearchOptions::default())[0]
+                .byte_ranges
+                .len(),
+            5
+        );
+        //matches don't overlap
+        let db = vec![quote("aaaa", &[])];
+        assert_eq!(
+            search_quotes(&db, "aa", SearchOptions::default())[0].byte_ranges,
+            [0..2, 2..4]
+        );
+    }
+
+    #[test]
+    fn category_caps_keep_the_newest() {
+        let added = |text: &str, categories: &[&str], added: Option<u64>| {
+            let mut quote = quote(text, categories);
+            quote.2.added = added;
+            quote
+        };
+        let mut db = vec![
+            added("undated", &["Daily"], None),
+            added("old", &["daily", "Stella"], Some(1)),
+            added("other", &["Stella"], Some(1)),
+            added("newest", &["Daily"], Some(3)),
+            added("tie first", &[" DAILY "], Some(2)),
+            added("tie second", &["Daily"], Some(2)),
+        ];
+
+        let evictions = enforce_category_cap(&mut db, "Daily", 2);
+        assert_eq!(texts(&evictions.removed), ["undated", "tie first"]);
+        assert_eq!(texts(&evictions.untagged), ["old"]);
+        assert_eq!(texts(&db), ["old", "other", "newest", "tie second"]);
+        assert_eq!(db[0].1, ["Stella"]);
+        assert!(db[0].modified().is_some());
+
+        assert!(enforce_category_cap(&mut db, "daily", 2).is_empty());
+        assert!(enforce_category_cap(&mut db, "Nothing", 0).is_empty());
+        let evictions = enforce_category_cap(&mut db, "Daily", 0);
+        assert_eq!(texts(&evictions.removed), ["newest", "tie second"]);
+        assert_eq!(texts(&db), ["old", "other"]);
+    }
+
+    #[test]
+    fn cat_operators_allow_for_typos() {
+        let known: Vec<String> = ["Funny", "Sunny", "Fun", "Esme"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let query = SearchQuery::parse("cat:funy  cats cat: cat:ESME", &known);
+        assert_eq!(query.text, "cats cat:");
+        assert_eq!(
+            query.categories,
+            [
+                //closest first
+                CategoryTerm {
+                    typed: "funy".into(),
+                    resolved: vec!["Funny".into(), "Fun".into()],
+                    exact: false,
+                },
+                CategoryTerm {
+                    typed: "ESME".into(),
+                    resolved: vec!["Esme".into()],
+                    exact: true,
+                },
+            ]
+        );
+        assert!(query.matches_categories(&quote("a", &["funny", "Esme"])));
+        assert!(!query.matches_categories(&quote("a", &["Sunny", "Esme"])));
+
+        //closest first
+        assert_eq!(
+            CategoryTerm::resolve("Funn", &known).resolved,
+            ["Funny", "Fun"]
+        );
+        //an exact match doesn't bring in near misses
+        assert_eq!(CategoryTerm::resolve("fun", &known).resolved, ["Fun"]);
+
+        let nothing_close = SearchQuery::parse("cat:zzz", &known);
+        assert!(nothing_close.categories[0].resolved.is_empty());
+        assert!(!nothing_close.matches_categories(&quote("a", &["Funny"])));
+        assert!(SearchQuery::parse("plain", &known).matches_categories(&quote("a", &[])));
+    }
+
+    #[test]
+    fn next_random_skips_recent_quotes() {
+        let pool: Vec<Quote> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|text| quote(text, &["Stella"]))
+            .collect();
+        let next = |pool: &[Quote], recent: &[Quote], window| {
+            next_random(pool, recent, window).map(|quote| quote.0.clone())
+        };
+
+        for _ in 0..100 {
+            let picked = next(&pool, &pool[..3], 3).unwrap();
+            assert!(picked == "d" || picked == "e", "{picked}");
+            //only the last two of the recent quotes are avoided
+            assert_ne!(next(&pool, &pool[..3], 2).unwrap(), "b");
+            //with three quotes, at most two can be avoided
+            assert_eq!(next(&pool[..3], &pool[..3], 10).unwrap(), "a");
+            assert_eq!(next(&pool[..2], &pool[..1], 1).unwrap(), "b");
+        }
+        assert_eq!(next(&pool[..1], &pool[..1], 1).unwrap(), "a");
+        assert_eq!(next(&[], &pool, 1), None);
+
+        //a duplicate can rule out the whole pool, which is then used as it is
+        let twice = [pool[0].clone(), pool[0].clone()];
+        assert_eq!(next(&twice, &pool[..1], 1).unwrap(), "a");
+    }
 }
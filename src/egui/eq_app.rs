@@ -1,213 +1,3161 @@
 //TODO: stop cloning so much
 
-use crate::utility::{
-    display_quotes_list, get_chosen_types, reverse_chosen_types, vertical_category_checkbox,
-    QuoteSelectionFilter,
+use crate::{
+    palette::{Command, CommandPalette},
+    settings::{load_settings, save_settings, AppSettings, ListDensity, StartupQuote, View},
+    utility::{
+        all_categories, counts_header, describe_age, display_quotes_list, get_chosen_types,
+        render_empty_state, reverse_chosen_types, selection_toolbar, show_flash, show_word_diff,
+        spellchecked_text_edit, vertical_category_checkbox, ActiveFilters, EmptyState, Flash,
+        SelectionAction,
+    },
 };
 use eframe::glow::Context;
-use egui::panel::Side;
+use egui::{panel::Side, Event, Key};
+#[cfg(feature = "feed")]
+use english_quotes::utils::feed::fetch_feed;
 use english_quotes::{
-    db::{add_quote_to_db, read_db, remove_quote, sort_list},
-    quote::{FileType, Quote, ALL_PERMS},
-    utils::exports::export,
+    db::{
+        add_quote_to_db, add_quotes_batch, all_collections, db_issues, enforce_category_cap,
+        find_attribution_conflicts, find_near_duplicates, merge_quotes, next_random, normalize_all,
+        prune_unused_categories, purge_trash, quote_matches, quote_of_the_day, random_quote,
+        rank_substring_matches, read_db, read_db_strict, read_trash, remove_quote, remove_quotes,
+        restore_quote, sort_list_by, tag_quotes, today, trash_quote, trash_quotes, update_quote,
+        validate_edit, validate_quote, write_db, write_db_lenient, write_trash, QuoteIssues,
+        SearchQuery, SortKey, SortOutcome, TrashedQuote, ValidationError,
+    },
+    quote::{
+        category_key, format_quote, is_valid_source, normalize_text, now_timestamp, parse_tags,
+        truncate_graphemes, Attribution, FileType, Quote, MAX_RATING,
+    },
+    utils::{
+        diff::{word_diff, DiffSpan},
+        exports::{
+            export_all, export_quotes_to, export_with, fallback_export_location, DateRange,
+            ExportError, ExportExclusions, ExportFormat, ExportGrouping, ExportOptions, Exporter,
+            ExporterRegistry, FieldSet, Separator,
+        },
+        filter::{build_category_index, filter_quotes, CategoryIndex, QuoteSelectionFilter},
+        imports::{
+            csv_first_row, import_csv_with, parse_import, preview_import, read_import, CsvField,
+            CsvMapping, ImportFormat, ImportPreview,
+        },
+        template::QuoteTemplate,
+        Error,
+    },
 };
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    mem::Discriminant,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
+use tracing::Level;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CurrentAppState {
+    QuoteCategories,
+    QuoteEntry {
+        current_text: String,
+        current_author: String,
+        current_source: String,
+        ///Comma-separated, see [`parse_tags`]
+        current_tags: String,
+        ///The quote being edited, as it was before - submitting updates it in place rather than adding a new one
+        editing: Option<Box<Quote>>,
+        ///Why the last submit was rejected, cleared as soon as the text is valid again
+        validation: Option<ValidationError>,
+    },
+    Search {
+        current_search_term: String,
+        is_inverted: bool,
+        by_relevance: bool,
+    },
+    Trash,
+}
+
+impl CurrentAppState {
+    pub fn from_view(view: View) -> Self {
+        match view {
+            View::QuoteCategories => Self::QuoteCategories,
+            View::QuoteEntry => Self::QuoteEntry {
+                current_text: String::default(),
+                current_author: String::default(),
+                current_source: String::default(),
+                current_tags: String::default(),
+                editing: None,
+                validation: None,
+            },
+            View::Search => Self::Search {
+                current_search_term: String::default(),
+                is_inverted: false,
+                by_relevance: false,
+            },
+            View::Trash => Self::Trash,
+        }
+    }
+
+    pub const fn view(&self) -> View {
+        match self {
+            Self::QuoteCategories => View::QuoteCategories,
+            Self::QuoteEntry { .. } => View::QuoteEntry,
+            Self::Search { .. } => View::Search,
+            Self::Trash => View::Trash,
+        }
+    }
+}
+
+///A short message shown in the corner of the window - the text, when it was first shown, and how serious it is
+type Toast = (String, Instant, Level);
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+///Logs `msg` and queues it up to be shown as a toast.
+///
+///Prefer [`EnglishQuotesApp::toast`] - this is for places where `self` is already partly borrowed.
+fn push_toast(toasts: &mut Vec<Toast>, level: Level, msg: impl Into<String>) {
+    let msg = msg.into();
+    match level {
+        Level::ERROR => error!("{msg}"),
+        Level::WARN => warn!("{msg}"),
+        _ => info!("{msg}"),
+    }
+    toasts.push((msg, Instant::now(), level));
+}
+
+///Puts the quotes at `indices` on the clipboard, laid out with `template`
+fn copy_quotes(
+    ui: &egui::Ui,
+    toasts: &mut Vec<Toast>,
+    db: &[Quote],
+    indices: impl IntoIterator<Item = usize>,
+    template: &QuoteTemplate,
+) {
+    let copied: Vec<String> = indices
+        .into_iter()
+        .filter_map(|i| db.get(i))
+        .map(|quote| format_quote(quote, template))
+        .collect();
+    ui.output().copied_text = copied.join("\n\n");
+    push_toast(
+        toasts,
+        Level::INFO,
+        if copied.len() == 1 {
+            "Copied 1 quote".to_string()
+        } else {
+            format!("Copied {} quotes", copied.len())
+        },
+    );
+}
+
+///Checkboxes for the categories never to export
+fn show_export_exclusions(
+    ui: &mut egui::Ui,
+    exclusions: &mut ExportExclusions,
+    categories: &[String],
+) {
+    egui::CollapsingHeader::new(if exclusions.is_empty() {
+        "Never export quotes in…".to_string()
+    } else {
+        format!(
+            "Never export quotes in: {}",
+            exclusions.categories.join(", ")
+        )
+    })
+    .id_source("export_exclusions")
+    .show(ui, |ui| {
+        for cat in categories {
+            let mut excluded = exclusions.categories.contains(cat);
+            if ui.checkbox(&mut excluded, cat).changed() {
+                if excluded {
+                    exclusions.categories.push(cat.clone());
+                } else {
+                    exclusions.categories.retain(|existing| existing != cat);
+                }
+            }
+        }
+    });
+}
+
+///Sorts `db` in memory, logging what happened and returning whether anything moved - this can't actually fail, but [`sort_list_by`] can when it's given the DB file
+fn sort_quotes(db: &mut Vec<Quote>, key: SortKey) -> bool {
+    match sort_list_by(Some(db), key) {
+        Ok(outcome) => {
+            debug!("Sorting quotes by {key:?}: {outcome:?}");
+            outcome == SortOutcome::Sorted
+        }
+        Err(err) => {
+            warn!("Unable to sort quotes: {err}");
+            false
+        }
+    }
+}
+
+///The window title for a database with `count` quotes in it
+fn window_title(count: usize) -> String {
+    format!("English Quotes ({count})")
+}
+
+///Every keyboard shortcut and what it does, for the `?` overlay - add to this when adding a shortcut
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("/", "Search quotes"),
+    ("Ctrl+F", "Search quotes, even while typing"),
+    ("Ctrl+K", "Open the command palette"),
+    ("?", "Show or hide this list"),
+    ("Esc", "Close this list"),
+];
+
+type LoadResult = Result<(Vec<Quote>, Vec<QuoteIssues>), Error>;
+///The quotes from a feed, or why they couldn't be fetched
+#[cfg(feature = "feed")]
+type FeedResult = Result<Vec<Quote>, String>;
+
+enum DbState {
+    Loading(Receiver<LoadResult>),
+    Loaded,
+    ///Nothing is saved in this state, so a database that couldn't be read doesn't get overwritten
+    Failed(String),
+}
+
+enum QuitChoice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+///How many rows of a CSV file are shown while its columns are being picked
+const CSV_PREVIEW_ROWS: usize = 5;
+const CSV_PREVIEW_LENGTH: usize = 60;
+///How many quotes a category is limited to when it's first given a limit
+const DEFAULT_CATEGORY_CAP: usize = 30;
+///Where exports are wrapped when "Wrap lines at column" is first ticked
+const DEFAULT_WRAP_WIDTH: usize = 72;
+///How many quotes are kept in "Recently viewed", and how much of each is shown
+const RECENTLY_VIEWED: usize = 10;
+const RECENTLY_VIEWED_LENGTH: usize = 30;
+
+///A dropped CSV file, waiting on its columns to be matched up with quote fields
+struct PendingCsv {
+    source: String,
+    contents: String,
+    first_row: Vec<String>,
+    mapping: CsvMapping,
+}
+
+///Describes where an import is coming from - worth pointing out when the extension said otherwise, in case it was read wrongly
+fn import_source(path: &Path, format: ImportFormat) -> String {
+    match ImportFormat::from_path(path) {
+        Some(expected) if expected != format => format!(
+            "{} (read as {format}, despite looking like {expected})",
+            path.display()
+        ),
+        _ => format!("{} (read as {format})", path.display()),
+    }
+}
+
+///Reads the database on another thread, so the window can open straight away
+fn load_db_in_background(settings: &AppSettings) -> Receiver<LoadResult> {
+    let known_categories = settings
+        .strict_load
+        .then(|| all_categories(&settings.custom_categories));
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        let result = known_categories.map_or_else(
+            || read_db().map(|db| (db, vec![])),
+            |known_categories| read_db_strict(&known_categories),
+        );
+        //the app has already gone if this fails, so there's no one to tell
+        let _ = sender.send(result);
+    });
+    receiver
+}
+
+#[allow(clippy::struct_excessive_bools)]
+pub struct EnglishQuotesApp {
+    current_state: CurrentAppState,
+    settings: AppSettings,
+    ///`current_db` is empty until this is [`DbState::Loaded`]
+    db_state: DbState,
+    current_db: Vec<Quote>,
+    ///Set by [`Self::in_memory`] - nothing gets saved
+    in_memory: bool,
+    ///Names of the checked categories, so they stay checked if the list of categories changes
+    current_checked: HashSet<String>,
+    new_category: String,
+    ///Narrows down the category checkboxes
+    category_filter: String,
+    ///Unused custom categories waiting on confirmation to be removed
+    pending_category_cleanup: Option<Vec<String>>,
+    ///Pairs of similar quotes still to be reviewed
+    near_duplicates: Option<Vec<(Quote, Quote, f32)>>,
+    ///How many pairs the similarity slider's current threshold would find, once it's been moved
+    similarity_preview: Option<usize>,
+    ///Texts with more than one author, from [`find_attribution_conflicts`]
+    attribution_conflicts: Option<Vec<(String, Vec<String>)>>,
+    quote_settings: Option<Quote>,
+    ///The [`Quote::dedup_key`]s of the last few quotes opened, most recent first
+    recently_viewed: Vec<(String, Option<String>)>,
+    ///Picked from the quote settings window - the next quote clicked is merged with it
+    merge_from: Option<Quote>,
+    ///Quotes read from a dropped file, waiting on confirmation to be added
+    ///Where the quotes are coming from, what importing them would do, and the quotes that would be added
+    pending_import: Option<(String, ImportPreview, Vec<Quote>)>,
+    pending_csv: Option<PendingCsv>,
+    #[cfg(feature = "feed")]
+    feed_url: String,
+    ///The URL being fetched, and where its quotes will turn up
+    #[cfg(feature = "feed")]
+    feed_fetch: Option<(String, Receiver<FeedResult>)>,
+    ///Indices of quotes waiting on confirmation to be deleted
+    pending_bulk_delete: Option<Vec<usize>>,
+    ///Indices of quotes to tag, and the tags being typed for them
+    pending_bulk_tag: Option<(Vec<usize>, String)>,
+    sort_key: SortKey,
+    ///Built lazily from `current_db` - anything that adds, removes, edits or reorders quotes has to reset it to `None`
+    category_index: Option<CategoryIndex>,
+    ///The collection name being typed in the quote settings
+    new_collection: String,
+    ///The category picked to get a new limit in the settings
+    new_cap_category: String,
+    ///Whether the lists have checkboxes for picking out quotes to act on together
+    select_mode: bool,
+    ///Indices of the quotes picked in select mode - cleared along with `category_index`, as the indices go stale at the same time
+    selected: BTreeSet<usize>,
+    ///The quote that was just added, to scroll to and highlight once it's listed
+    flash: Option<Flash>,
+    ///Quotes have been added since the list was last sorted
+    is_unsorted: bool,
+    ///`current_db` has changed since it was loaded or last saved - see [`Self::is_dirty`]
+    dirty: bool,
+    ///Vertical scroll offset of each view's list, so it can be restored when coming back to that view
+    scroll_offsets: HashMap<Discriminant<CurrentAppState>, f32>,
+    last_view: Option<Discriminant<CurrentAppState>>,
+    show_export_dialog: bool,
+    show_about: bool,
+    show_shortcuts: bool,
+    palette: CommandPalette,
+    ///What the window title was last set to, so it's only changed when the quote count does
+    window_title: String,
+    export_format: ExportFormat,
+    export_options: ExportOptions,
+    export_fields: FieldSet,
+    ///Export only what the current view is showing, in the order it's showing it
+    export_shown_only: bool,
+    ///Set by "Export selected…" - only these quotes are exported, until the dialog's closed
+    export_selected: Option<Vec<usize>>,
+    ///Folder that "Export All Formats" writes to
+    export_all_dir: String,
+    ///Export only quotes added between `export_from` and `export_to`
+    export_dated: bool,
+    export_from: String,
+    export_to: String,
+    export_include_undated: bool,
+    ///Indices of the quotes the current view showed last frame, in order - `None` for views without a list
+    visible: Option<Vec<usize>>,
+    ///Shared by the category and search views, so they stay put when switching between them
+    filters: ActiveFilters,
+    ///The path that an export failed to write to, and the alternative location the user is picking
+    export_fallback: Option<(PathBuf, String)>,
+    exit_save_error: Option<String>,
+    exit_handled: bool,
+    ///The window was asked to close with unsaved changes, so "Save before quitting?" is showing
+    quit_prompt: bool,
+    ///Set by the `/` and Ctrl+F shortcuts, so the search box grabs focus once it's drawn
+    focus_search: bool,
+    toasts: Vec<Toast>,
+    ///Problems found by strict loading, kept up to date while the review window is open
+    issues: Vec<QuoteIssues>,
+    show_issues: bool,
+    ///Deleted quotes, oldest first - `None` if trash.json couldn't be read, in which case deletes are permanent so it isn't overwritten
+    trash: Option<Vec<TrashedQuote>>,
+    ///The trash has changed since it was last written
+    trash_dirty: bool,
+    ///The quote shown on startup, and whether "Don't show again today" is ticked
+    startup_quote: Option<(Quote, bool)>,
+    ///Random quotes already shown, oldest first, for [`next_random`] to avoid
+    shown_recently: Vec<Quote>,
+}
+
+impl Default for EnglishQuotesApp {
+    fn default() -> Self {
+        let settings = load_settings();
+        let db_state = DbState::Loading(load_db_in_background(&settings));
+        let mut app = Self::new(settings, db_state, vec![], false);
+        match read_trash() {
+            Ok(mut trash) => {
+                app.trash_dirty = purge_trash(&mut trash, app.settings.trash_days) > 0;
+                app.trash = Some(trash);
+            }
+            Err(err) => {
+                warn!("Unable to read {}: {err}", FileType::Trash.get_location());
+                app.trash = None;
+            }
+        }
+        app
+    }
+}
+
+impl EnglishQuotesApp {
+    ///An app working on `quotes` that never reads or writes the database, settings or backups - changes are thrown away on exit
+    pub fn in_memory(quotes: Vec<Quote>) -> Self {
+        Self::new(AppSettings::default(), DbState::Loaded, quotes, true)
+    }
+
+    fn new(
+        settings: AppSettings,
+        db_state: DbState,
+        current_db: Vec<Quote>,
+        in_memory: bool,
+    ) -> Self {
+        let mut app = Self {
+            current_state: CurrentAppState::from_view(settings.last_view),
+            db_state,
+            settings,
+            current_db,
+            in_memory,
+            issues: vec![],
+            show_issues: false,
+            current_checked: HashSet::new(),
+            new_category: String::default(),
+            category_filter: String::new(),
+            pending_category_cleanup: None,
+            near_duplicates: None,
+            similarity_preview: None,
+            attribution_conflicts: None,
+            quote_settings: None,
+            recently_viewed: vec![],
+            merge_from: None,
+            pending_import: None,
+            pending_csv: None,
+            #[cfg(feature = "feed")]
+            feed_url: String::new(),
+            #[cfg(feature = "feed")]
+            feed_fetch: None,
+            pending_bulk_delete: None,
+            pending_bulk_tag: None,
+            sort_key: SortKey::default(),
+            category_index: None,
+            new_collection: String::new(),
+            new_cap_category: String::new(),
+            select_mode: false,
+            selected: BTreeSet::new(),
+            flash: None,
+            is_unsorted: false,
+            dirty: false,
+            scroll_offsets: HashMap::new(),
+            last_view: None,
+            show_export_dialog: false,
+            show_about: false,
+            show_shortcuts: false,
+            palette: CommandPalette::default(),
+            window_title: String::new(),
+            export_format: ExportFormat::default(),
+            export_options: ExportOptions::default(),
+            export_fields: FieldSet::default(),
+            export_shown_only: false,
+            export_selected: None,
+            export_all_dir: String::from("."),
+            export_dated: false,
+            export_from: String::new(),
+            export_to: String::new(),
+            export_include_undated: false,
+            visible: None,
+            filters: ActiveFilters::default(),
+            export_fallback: None,
+            exit_save_error: None,
+            quit_prompt: false,
+            exit_handled: false,
+            focus_search: false,
+            toasts: vec![],
+            trash: Some(vec![]),
+            trash_dirty: false,
+            startup_quote: None,
+            shown_recently: vec![],
+        };
+        if app.current_state.view() == View::QuoteEntry {
+            app.check_default_categories();
+        }
+        app
+    }
+
+    fn toast(&mut self, level: Level, msg: impl Into<String>) {
+        push_toast(&mut self.toasts, level, msg);
+    }
+
+    fn show_toasts(&mut self, ctx: &egui::Context) {
+        self.toasts
+            .retain(|(_, shown, _)| shown.elapsed() < TOAST_DURATION);
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        egui::Area::new("toasts")
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .show(ctx, |ui| {
+                for (msg, _, level) in &self.toasts {
+                    let colour = match *level {
+                        Level::ERROR => egui::Color32::RED,
+                        Level::WARN => egui::Color32::YELLOW,
+                        _ => ui.visuals().text_color(),
+                    };
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.colored_label(colour, msg);
+                    });
+                }
+            });
+        //keep repainting so they go away on time, even if nothing else is happening
+        ctx.request_repaint();
+    }
+
+    ///Pre-checks the default categories, if any have been set
+    fn check_default_categories(&mut self) {
+        if !self.settings.default_categories.is_empty() {
+            self.current_checked = reverse_chosen_types(
+                self.settings.default_categories.clone(),
+                &all_categories(&self.settings.custom_categories),
+            );
+        }
+    }
+
+    fn save_db(&mut self) -> Result<(), Error> {
+        if !matches!(self.db_state, DbState::Loaded) || self.in_memory {
+            return Ok(());
+        }
+        self.category_index = None;
+        self.selected.clear();
+        self.dirty |= sort_quotes(&mut self.current_db, self.sort_key);
+        self.is_unsorted = false;
+        if let (true, Some(trash)) = (self.trash_dirty, &self.trash) {
+            write_trash(trash)?;
+            self.trash_dirty = false;
+        }
+        if !self.is_dirty() {
+            debug!("No changes to save");
+            return Ok(());
+        }
+        self.write_leniently(FileType::Database.get_location())?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    ///Whether there are changes to `current_db` that haven't been written to the DB file yet
+    #[must_use]
+    pub const fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    ///Writes the database with [`write_db_lenient`], logging any quotes that had to be left out
+    fn write_leniently(&self, location: &str) -> Result<(), Error> {
+        let skipped = write_db_lenient(&self.current_db, location)?;
+        if skipped.is_empty() {
+            return Ok(());
+        }
+        for &i in &skipped {
+            error!(
+                "Unable to serialise quote {i}, left it out of {location}: {:?}",
+                self.current_db[i]
+            );
+        }
+        Err(Error::QuotesLeftOut(skipped.len()))
+    }
+
+    ///Called after quotes are added - either re-sorts straight away or marks the list as unsorted, depending on the settings
+    fn sort_after_add(&mut self) {
+        if self.settings.auto_sort {
+            self.dirty |= sort_quotes(&mut self.current_db, self.sort_key);
+        } else {
+            self.is_unsorted = true;
+        }
+    }
+
+    ///Removes `quote`, putting it in the trash unless that's been turned off (or couldn't be read)
+    fn delete_quote(&mut self, quote: &Quote) -> Result<(), Error> {
+        self.category_index = None;
+        self.selected.clear();
+        match (&mut self.trash, self.settings.soft_delete) {
+            (Some(trash), true) => {
+                trash_quote(quote, &mut self.current_db, trash)?;
+                self.trash_dirty = true;
+            }
+            _ => remove_quote(quote, Some(&mut self.current_db))?,
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    ///Like [`Self::delete_quote`], for every quote at `indices` - returns how many were removed
+    fn delete_quotes(&mut self, indices: &[usize]) -> usize {
+        self.category_index = None;
+        self.selected.clear();
+        let removed = match (&mut self.trash, self.settings.soft_delete) {
+            (Some(trash), true) => {
+                let removed = trash_quotes(&mut self.current_db, indices, trash);
+                self.trash_dirty |= removed > 0;
+                removed
+            }
+            _ => remove_quotes(&mut self.current_db, indices),
+        };
+        self.dirty |= removed > 0;
+        removed
+    }
+
+    ///Applies [`AppSettings::category_caps`], trashing the quotes that go if the trash is on
+    fn enforce_category_caps(&mut self) {
+        let mut removed = 0;
+        let mut untagged = 0;
+        for (category, &max) in &self.settings.category_caps {
+            let evictions = enforce_category_cap(&mut self.current_db, category, max);
+            if evictions.is_empty() {
+                continue;
+            }
+            removed += evictions.removed.len();
+            untagged += evictions.untagged.len();
+            if let (Some(trash), true) = (&mut self.trash, self.settings.soft_delete) {
+                let deleted = now_timestamp();
+                trash.extend(
+                    evictions
+                        .removed
+                        .into_iter()
+                        .map(|quote| TrashedQuote { quote, deleted }),
+                );
+                self.trash_dirty = true;
+            }
+        }
+        if removed + untagged == 0 {
+            return;
+        }
+
+        self.category_index = None;
+        self.selected.clear();
+        self.dirty = true;
+        let mut message = vec![];
+        if removed > 0 {
+            message.push(format!("removed {removed} old quotes"));
+        }
+        if untagged > 0 {
+            message.push(format!("took {untagged} out of capped categories"));
+        }
+        self.toast(
+            Level::INFO,
+            format!("Category limits: {}", message.join(", ")),
+        );
+    }
+
+    fn show_category_caps(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Category limits", |ui| {
+            ui.weak(
+                "Only the newest quotes are kept in these, eg. for a rolling \"daily\" category.",
+            );
+            let mut remove = None;
+            for (category, max) in &mut self.settings.category_caps {
+                ui.horizontal(|ui| {
+                    ui.label(category.as_str());
+                    ui.add(
+                        egui::DragValue::new(max)
+                            .clamp_range(1..=10_000)
+                            .suffix(" quotes"),
+                    );
+                    if ui
+                        .small_button("×")
+                        .on_hover_text("Remove the limit")
+                        .clicked()
+                    {
+                        remove = Some(category.clone());
+                    }
+                });
+            }
+            if let Some(category) = remove {
+                self.settings.category_caps.remove(&category);
+            }
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("new_category_cap")
+                    .selected_text(self.new_cap_category.as_str())
+                    .show_ui(ui, |ui| {
+                        for category in all_categories(&self.settings.custom_categories) {
+                            if !self.settings.category_caps.contains_key(&category) {
+                                ui.selectable_value(
+                                    &mut self.new_cap_category,
+                                    category.clone(),
+                                    category,
+                                );
+                            }
+                        }
+                    });
+                if ui
+                    .add_enabled(
+                        !self.new_cap_category.is_empty(),
+                        egui::Button::new("Add limit"),
+                    )
+                    .clicked()
+                {
+                    self.settings.category_caps.insert(
+                        std::mem::take(&mut self.new_cap_category),
+                        DEFAULT_CATEGORY_CAP,
+                    );
+                }
+            });
+            if !self.settings.category_caps.is_empty()
+                && ui
+                    .button("Apply now")
+                    .on_hover_text("Limits are otherwise applied whenever quotes are added")
+                    .clicked()
+            {
+                self.enforce_category_caps();
+            }
+        });
+    }
+
+    ///The deleted quotes, newest first, to be restored or deleted for good
+    fn show_trash(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Trash");
+        let Some(trash) = &mut self.trash else {
+            ui.label(format!(
+                "{} couldn't be read, so deleted quotes aren't being kept.",
+                FileType::Trash.get_location()
+            ));
+            return;
+        };
+        if !self.settings.soft_delete {
+            ui.weak("Deleted quotes are gone straight away while the trash is turned off.");
+        }
+        if trash.is_empty() {
+            ui.weak("Nothing's been deleted.");
+            return;
+        }
+
+        let mut restore = None;
+        let mut purge = None;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Quotes are deleted for good after {} days.",
+                self.settings.trash_days
+            ));
+            if ui.button("Empty trash").clicked() {
+                trash.clear();
+                self.trash_dirty = true;
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (i, trashed) in trash.iter().enumerate().rev() {
+                ui.label(trashed.quote.to_string());
+                ui.horizontal(|ui| {
+                    ui.weak(format!(
+                        "deleted {}",
+                        describe_age(now_timestamp().saturating_sub(trashed.deleted))
+                    ));
+                    if ui.small_button("Restore").clicked() {
+                        restore = Some(i);
+                    }
+                    if ui.small_button("Delete forever").clicked() {
+                        purge = Some(i);
+                    }
+                });
+                ui.separator();
+            }
+        });
+
+        if let Some(i) = purge {
+            trash.remove(i);
+            self.trash_dirty = true;
+        }
+        if let Some(i) = restore {
+            self.category_index = None;
+            self.selected.clear();
+            match restore_quote(trash, i, &mut self.current_db) {
+                Ok(()) => {
+                    self.trash_dirty = true;
+                    self.dirty = true;
+                    self.sort_after_add();
+                    self.toast(Level::INFO, "Quote restored");
+                }
+                Err(err) => self.toast(Level::ERROR, format!("Unable to restore quote: {err}")),
+            }
+        }
+    }
+
+    fn go_to(&mut self, view: View) {
+        self.current_state = CurrentAppState::from_view(view);
+        if view == View::QuoteEntry {
+            self.check_default_categories();
+        }
+    }
+
+    ///Switches to the category view showing `category`, either on its own or alongside the categories already checked
+    fn filter_by_category(&mut self, category: &str, add: bool) {
+        let categories = all_categories(&self.settings.custom_categories);
+        let Some(category) = categories
+            .into_iter()
+            .find(|known| category_key(known) == category_key(category))
+        else {
+            self.toast(
+                Level::INFO,
+                format!("{category} isn't one of your categories"),
+            );
+            return;
+        };
+
+        if !add {
+            self.current_checked.clear();
+        }
+        self.current_checked.insert(category);
+        if self.current_state.view() != View::QuoteCategories {
+            self.go_to(View::QuoteCategories);
+        }
+    }
+
+    fn set_sort_key(&mut self, key: SortKey) {
+        self.sort_key = key;
+        self.is_unsorted = false;
+        self.category_index = None;
+        self.selected.clear();
+        self.dirty |= sort_quotes(&mut self.current_db, key);
+    }
+
+    fn find_similar_quotes(&mut self) {
+        self.near_duplicates = Some(
+            find_near_duplicates(&self.current_db, self.settings.similarity_threshold)
+                .into_iter()
+                .map(|(a, b, score)| {
+                    (
+                        self.current_db[a].clone(),
+                        self.current_db[b].clone(),
+                        score,
+                    )
+                })
+                .collect(),
+        );
+        self.similarity_preview = None;
+    }
+
+    ///Writes every quote with `exporter` into `export_all_dir`
+    fn export_backup(&mut self, exporter: &dyn Exporter) {
+        match export_with(
+            exporter,
+            &self
+                .settings
+                .export_exclusions
+                .apply(self.current_db.clone()),
+            &self.export_all_dir,
+            now_timestamp(),
+        ) {
+            Ok(path) => self.toast(Level::INFO, format!("Exported to {}", path.display())),
+            Err(err) => self.toast(Level::ERROR, format!("Unable to export: {err}")),
+        }
+    }
+
+    fn export_all_formats(&mut self) {
+        let list = self
+            .settings
+            .export_exclusions
+            .apply(self.current_db.clone());
+        match export_all(&list, &self.export_all_dir, now_timestamp()) {
+            Ok(written) => self.toast(
+                Level::INFO,
+                format!("Wrote {} files to {}", written.len(), self.export_all_dir),
+            ),
+            Err(err) => self.toast(Level::ERROR, format!("Unable to export: {err}")),
+        }
+    }
+
+    ///Everything the command palette offers, built fresh each time as some names depend on the current settings
+    fn commands(&self) -> Vec<Command> {
+        let mut commands = vec![
+            Command::new("Go to All Quotes", |app| app.go_to(View::QuoteCategories)),
+            Command::new("Go to Quote Entry", |app| app.go_to(View::QuoteEntry)),
+            Command::new("Go to Trash", |app| app.go_to(View::Trash)),
+            Command::new("Go to Search", |app| {
+                app.go_to(View::Search);
+                app.focus_search = true;
+            }),
+            Command::new("Export...", Self::open_export_dialog),
+        ];
+        let registry = ExporterRegistry::with_builtins(
+            &self.export_options_with_template(),
+            self.export_fields,
+        );
+        for exporter in registry.iter() {
+            let extension = exporter.extension().to_string();
+            commands.push(Command::new(
+                format!("Export as {}", exporter.name()),
+                move |app| {
+                    let registry = ExporterRegistry::with_builtins(
+                        &app.export_options_with_template(),
+                        app.export_fields,
+                    );
+                    if let Some(exporter) = registry.get(&extension) {
+                        app.export_backup(exporter);
+                    }
+                },
+            ));
+        }
+        commands.push(Command::new("Export all formats", Self::export_all_formats));
+        for key in SortKey::ALL {
+            commands.push(Command::new(format!("Sort by {key}"), move |app| {
+                app.set_sort_key(key);
+            }));
+        }
+        commands.extend([
+            Command::new(
+                if self.settings.dark_mode {
+                    "Switch to light theme"
+                } else {
+                    "Switch to dark theme"
+                },
+                |app| app.settings.dark_mode = !app.settings.dark_mode,
+            ),
+            Command::new("Find similar quotes", Self::find_similar_quotes),
+            Command::new("Find attribution conflicts", |app| {
+                app.attribution_conflicts = Some(find_attribution_conflicts(&app.current_db));
+            }),
+            Command::new("Show keyboard shortcuts", |app| app.show_shortcuts = true),
+            Command::new("About", |app| app.show_about = true),
+        ]);
+        commands
+    }
+
+    ///For exporting from the menus - anything "Export selected…" was used on before is forgotten
+    fn open_export_dialog(&mut self) {
+        self.export_selected = None;
+        self.show_export_dialog = true;
+    }
+
+    ///The quotes the export dialog's options pick out of `current_db` - never the DB file, which might be behind or (in [`Self::in_memory`] mode) not ours at all
+    fn export_list(&self) -> Result<Vec<Quote>, Error> {
+        let range = if self.export_dated {
+            Some(
+                DateRange::from_dates(
+                    &self.export_from,
+                    &self.export_to,
+                    self.export_include_undated,
+                )
+                .ok_or_else(|| {
+                    Error::InvalidDateRange(self.export_from.clone(), self.export_to.clone())
+                })?,
+            )
+        } else {
+            None
+        };
+
+        let list: Vec<Quote> = match (&self.export_selected, self.export_shown_only, &self.visible)
+        {
+            (Some(indices), _, _) | (None, true, Some(indices)) => indices
+                .iter()
+                .filter_map(|&i| self.current_db.get(i).cloned())
+                .collect(),
+            _ => self.current_db.clone(),
+        };
+        let mut list = self.settings.export_exclusions.apply(list);
+        if let Some(range) = range {
+            list.retain(|quote| range.contains(quote));
+        }
+        Ok(list)
+    }
+
+    fn run_export(&self, path: &str) -> Result<(), Error> {
+        export_quotes_to(
+            path,
+            &self.export_list()?,
+            self.export_format,
+            self.export_options_with_template(),
+            self.export_fields,
+        )
+    }
+
+    ///The export dialog's options, laying plain text out with the quote template from the settings
+    fn export_options_with_template(&self) -> ExportOptions {
+        ExportOptions {
+            template: QuoteTemplate::parse_or_default(&self.settings.quote_template),
+            ..self.export_options.clone()
+        }
+    }
+
+    fn handle_export_result(&mut self, result: Result<(), Error>, path: &str) {
+        match result {
+            Ok(()) => {
+                self.export_fallback = None;
+                self.toast(Level::INFO, format!("Exported to {path}"));
+            }
+            Err(Error::Export(ExportError::NotWritable { path })) => {
+                self.toast(
+                    Level::WARN,
+                    format!("Unable to write export to {}", path.display()),
+                );
+                self.export_fallback = Some((
+                    path,
+                    fallback_export_location(self.export_format)
+                        .to_string_lossy()
+                        .to_string(),
+                ));
+            }
+            Err(err) => self.toast(Level::ERROR, format!("Unable to export: {err}")),
+        }
+    }
+
+    ///Moves `quote` to the front of [`Self::recently_viewed`], dropping the oldest past [`RECENTLY_VIEWED`]
+    fn note_viewed(&mut self, quote: &Quote) {
+        let key = quote.dedup_key();
+        if self.recently_viewed.first() == Some(&key) {
+            return;
+        }
+        self.recently_viewed.retain(|viewed| viewed != &key);
+        self.recently_viewed.insert(0, key);
+        self.recently_viewed.truncate(RECENTLY_VIEWED);
+    }
+
+    ///Quick links back to the recently viewed quotes that are still in the database
+    fn show_recently_viewed(&mut self, ui: &mut egui::Ui) {
+        if self.recently_viewed.is_empty() {
+            return;
+        }
+        let mut open = None;
+        egui::CollapsingHeader::new("Recently viewed").show(ui, |ui| {
+            //one pass over the database, rather than one per recent quote
+            let mut found: Vec<Option<&Quote>> = vec![None; self.recently_viewed.len()];
+            for quote in &self.current_db {
+                let key = quote.dedup_key();
+                if let Some(position) = self
+                    .recently_viewed
+                    .iter()
+                    .position(|viewed| viewed == &key)
+                {
+                    found[position].get_or_insert(quote);
+                }
+            }
+
+            for quote in found.into_iter().flatten() {
+                let label = truncate_graphemes(&quote.0, RECENTLY_VIEWED_LENGTH);
+                if ui.small_button(label).on_hover_text(&quote.0).clicked() {
+                    open = Some(quote.clone());
+                }
+            }
+        });
+        if open.is_some() {
+            self.quote_settings = open;
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn show_side_panel(&mut self, ctx: &egui::Context) {
+        let mut panel = egui::SidePanel::new(Side::Left, "tab_menu");
+        if let Some(width) = self.settings.side_panel_width {
+            panel = panel.default_width(width);
+        }
+
+        let response = panel.show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Menus");
+                if ui.small_button("«").on_hover_text("Hide menus").clicked() {
+                    self.settings.side_panel_collapsed = true;
+                }
+            });
+
+            if ui.button("All Quotes").clicked() {
+                self.go_to(View::QuoteCategories);
+            }
+            if ui.button("Quote Entry").clicked() {
+                self.go_to(View::QuoteEntry);
+            }
+            if ui.button("Search Quotes").clicked() {
+                self.go_to(View::Search);
+            }
+            let trashed = self.trash.as_ref().map_or(0, Vec::len);
+            if ui.button(format!("Trash ({trashed})")).clicked() {
+                self.go_to(View::Trash);
+            }
+            self.show_recently_viewed(ui);
+            if ui.button("Export").clicked() {
+                self.open_export_dialog();
+            }
+            if ui
+                .add_enabled(self.visible.is_some(), egui::Button::new("Copy all shown"))
+                .clicked()
+            {
+                copy_quotes(
+                    ui,
+                    &mut self.toasts,
+                    &self.current_db,
+                    self.visible.iter().flatten().copied(),
+                    &QuoteTemplate::parse_or_default(&self.settings.quote_template),
+                );
+            }
+            if ui.button("About").clicked() {
+                self.show_about = !self.show_about;
+            }
+            if ui
+                .button("Keyboard shortcuts")
+                .on_hover_text("Or press ?")
+                .clicked()
+            {
+                self.show_shortcuts = !self.show_shortcuts;
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.selectable_value(
+                    &mut self.settings.density,
+                    ListDensity::Comfortable,
+                    "Comfortable",
+                );
+                ui.selectable_value(&mut self.settings.density, ListDensity::Compact, "Compact");
+            });
+            let old_sort_key = self.sort_key;
+            egui::ComboBox::from_label("Sort by")
+                .selected_text(self.sort_key.to_string())
+                .show_ui(ui, |ui| {
+                    for key in SortKey::ALL {
+                        ui.selectable_value(&mut self.sort_key, key, key.to_string());
+                    }
+                });
+            ui.checkbox(&mut self.settings.auto_sort, "Sort after adding");
+            if self.is_unsorted {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, "List is unsorted");
+                    if ui.button("Sort").clicked() {
+                        self.set_sort_key(self.sort_key);
+                    }
+                });
+            }
+            if self.sort_key != old_sort_key {
+                self.set_sort_key(self.sort_key);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.new_category);
+                if ui.button("Add Category").clicked() {
+                    let new_category = self.new_category.trim().to_string();
+                    if !new_category.is_empty()
+                        && !all_categories(&self.settings.custom_categories).contains(&new_category)
+                    {
+                        self.settings.custom_categories.push(new_category);
+                    }
+                    self.new_category.clear();
+                }
+            });
+            ui.separator();
+            ui.label("Quote format");
+            ui.text_edit_singleline(&mut self.settings.quote_template)
+                .on_hover_text(
+                    "Placeholders: {text} {author} {categories} {tags} {source} {rating}",
+                );
+            if let Err(err) = self.settings.quote_template.parse::<QuoteTemplate>() {
+                ui.colored_label(egui::Color32::RED, format!("{err}, using the default"));
+            }
+            ui.add(
+                egui::Slider::new(&mut self.settings.quote_font_size, 8.0..=40.0)
+                    .text("Quote size"),
+            );
+            ui.add(egui::Slider::new(&mut self.settings.ui_scale, 0.5..=3.0).text("UI scale"));
+            ui.checkbox(&mut self.settings.dark_mode, "Dark theme");
+            egui::ComboBox::from_label("on startup")
+                .selected_text(self.settings.startup_quote.to_string())
+                .show_ui(ui, |ui| {
+                    for choice in StartupQuote::ALL {
+                        ui.selectable_value(
+                            &mut self.settings.startup_quote,
+                            choice,
+                            choice.to_string(),
+                        );
+                    }
+                });
+            if self.settings.startup_quote == StartupQuote::Random {
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.no_repeat_window)
+                        .clamp_range(0..=100)
+                        .prefix("Don't repeat the last ")
+                        .suffix(" quotes"),
+                );
+            }
+            ui.checkbox(&mut self.settings.category_grid, "Categories in columns");
+            ui.checkbox(
+                &mut self.settings.preview_pane,
+                "Show quotes in a side pane",
+            );
+            ui.checkbox(&mut self.settings.normalize_on_add, "Tidy text when adding");
+            ui.checkbox(
+                &mut self.settings.soft_delete,
+                "Keep deleted quotes in the trash",
+            );
+            ui.add_enabled(
+                self.settings.soft_delete,
+                egui::DragValue::new(&mut self.settings.trash_days)
+                    .clamp_range(1..=3650)
+                    .suffix(" days in the trash"),
+            );
+            ui.checkbox(
+                &mut self.settings.punctuation.capitalize,
+                "Capitalise the first letter",
+            );
+            ui.checkbox(
+                &mut self.settings.punctuation.terminal_period,
+                "End with a full stop",
+            );
+            if ui
+                .checkbox(&mut self.settings.strict_load, "Check quotes on load")
+                .changed()
+                && self.settings.strict_load
+            {
+                self.issues = db_issues(
+                    &self.current_db,
+                    &all_categories(&self.settings.custom_categories),
+                );
+            }
+            #[cfg(feature = "spellcheck")]
+            ui.checkbox(&mut self.settings.spellcheck, "Spellcheck new quotes");
+            if ui
+                .button("Normalize all")
+                .on_hover_text("Straighten smart quotes and tidy whitespace in every quote")
+                .clicked()
+            {
+                self.category_index = None;
+                self.selected.clear();
+                let changed = normalize_all(&mut self.current_db);
+                self.dirty |= changed > 0;
+                push_toast(
+                    &mut self.toasts,
+                    Level::INFO,
+                    format!("Normalized {changed} quotes"),
+                );
+            }
+            if ui
+                .button("Find attribution conflicts")
+                .on_hover_text("The same quote attributed to different people")
+                .clicked()
+            {
+                self.attribution_conflicts = Some(find_attribution_conflicts(&self.current_db));
+            }
+            if ui.button("Find similar quotes").clicked() {
+                self.find_similar_quotes();
+            }
+            if ui.button("Clean up categories").clicked() {
+                self.pending_category_cleanup = Some(prune_unused_categories(
+                    &self.settings.custom_categories,
+                    &self.current_db,
+                ));
+            }
+            self.show_category_caps(ui);
+            #[cfg(feature = "feed")]
+            self.show_feed_import(ui);
+        });
+        self.settings.side_panel_width = Some(response.response.rect.width());
+    }
+
+    ///The "quotes have issues" banner, and the window listing them
+    fn show_issue_review(&mut self, ctx: &egui::Context) {
+        if self.show_issues {
+            self.issues = db_issues(
+                &self.current_db,
+                &all_categories(&self.settings.custom_categories),
+            );
+        }
+        if !self.settings.strict_load || self.issues.is_empty() {
+            return;
+        }
+
+        egui::TopBottomPanel::top("issue_banner").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let count = self.issues.len();
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    if count == 1 {
+                        "1 quote has issues".to_string()
+                    } else {
+                        format!("{count} quotes have issues")
+                    },
+                );
+                if ui.button("Review").clicked() {
+                    self.show_issues = true;
+                }
+            });
+        });
+
+        let issues: HashMap<usize, String> = self
+            .issues
+            .iter()
+            .map(|(i, issues)| {
+                let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+                (*i, format!("({})", issues.join(", ")))
+            })
+            .collect();
+        let quotes: Vec<(usize, Quote)> = self
+            .issues
+            .iter()
+            .filter_map(|(i, _)| Some((*i, self.current_db.get(*i)?.clone())))
+            .collect();
+
+        let total = self.current_db.len();
+        egui::Window::new("Quotes With Issues")
+            .open(&mut self.show_issues)
+            .show(ctx, |ui| {
+                ui.label("Click a quote to edit or delete it.");
+                counts_header(ui, quotes.len(), total, "");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    display_quotes_list(
+                        quotes.iter().cloned(),
+                        ui,
+                        self.settings.density,
+                        self.settings.quote_font_size,
+                        &QuoteTemplate::parse_or_default(&self.settings.quote_template),
+                        |quote| {
+                            quotes
+                                .iter()
+                                .find(|(_, q)| q == quote)
+                                .and_then(|(i, _)| issues.get(i).cloned())
+                        },
+                        Some(|quote| self.quote_settings = Some(quote)),
+                        None::<fn(String, bool)>,
+                        None,
+                    );
+                });
+            });
+    }
+
+    ///The chosen quote's text and controls, for either the popup or the preview pane - returns whether to close it
+    fn show_quote_details(&mut self, ui: &mut egui::Ui, quote: &Quote) -> bool {
+        let mut close = false;
+        let mut new_rating = None;
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                ui.heading(&quote.0);
+            });
+        if let Some(author) = quote.author() {
+            ui.label(format!("- {author}"));
+        }
+        if !quote.1.is_empty() {
+            ui.label(format!("Categories: {}", quote.1.join(", ")));
+        }
+        if !quote.tags().is_empty() {
+            ui.label(format!("Tags: {}", quote.tags().join(", ")));
+        }
+        self.show_collections(ui, quote);
+        if let Some(source) = quote.source() {
+            ui.hyperlink(source);
+        }
+        let now = now_timestamp();
+        if let Some(added) = quote.2.added {
+            ui.weak(format!("Added {}", describe_age(now.saturating_sub(added))));
+        }
+        if let Some(modified) = quote.modified() {
+            ui.weak(format!(
+                "Edited {}",
+                describe_age(now.saturating_sub(modified))
+            ));
+        }
+        ui.horizontal(|ui| {
+            for star in 1..=MAX_RATING {
+                let lit = quote.rating().is_some_and(|rating| star <= rating);
+                if ui
+                    .selectable_label(lit, if lit { "★" } else { "☆" })
+                    .clicked()
+                {
+                    new_rating = Some(Some(star));
+                }
+            }
+            if quote.rating().is_some() && ui.small_button("Clear").clicked() {
+                new_rating = Some(None);
+            }
+        });
+        if let Some(rating) = new_rating {
+            self.rate(quote, rating);
+        }
+        self.show_attribution(ui, quote);
+        if ui.button("Copy Quote").clicked() {
+            ui.output().copied_text = format_quote(
+                quote,
+                &QuoteTemplate::parse_or_default(&self.settings.quote_template),
+            );
+            self.toast(Level::INFO, "Copied quote");
+        }
+        if ui.button("Delete Quote").clicked() {
+            match self.delete_quote(quote) {
+                Ok(()) => {
+                    let message = if self.settings.soft_delete && self.trash.is_some() {
+                        "Quote moved to the trash"
+                    } else {
+                        "Quote deleted"
+                    };
+                    self.toast(Level::INFO, message);
+                }
+                Err(err) => self.toast(Level::ERROR, format!("Unable to remove quote: {err}")),
+            }
+            close = true;
+        }
+        if ui.button("Merge with…").clicked() {
+            self.merge_from = Some(quote.clone());
+            self.toast(Level::INFO, "Click the quote to merge this one with");
+            close = true;
+        }
+        if ui.button("Edit Quote").clicked() {
+            let quote = quote.clone();
+
+            self.current_state = CurrentAppState::QuoteEntry {
+                current_text: quote.0.clone(),
+                current_author: quote.2.author.clone().unwrap_or_default(),
+                current_source: quote.2.source.clone().unwrap_or_default(),
+                current_tags: quote.2.tags.join(", "),
+                editing: Some(Box::new(quote.clone())),
+                validation: None,
+            };
+            self.current_checked =
+                reverse_chosen_types(quote.1, &all_categories(&self.settings.custom_categories));
+
+            close = true;
+        }
+        if ui
+            .button(if self.settings.preview_pane {
+                "Close"
+            } else {
+                "Cancel"
+            })
+            .clicked()
+        {
+            close = true;
+        }
+        close
+    }
+
+    ///The quote's collections, with buttons to take it out of them or put it in others
+    fn show_collections(&mut self, ui: &mut egui::Ui, quote: &Quote) {
+        let mut removed = None;
+        let mut added = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Collections:");
+            for collection in quote.collections() {
+                if ui
+                    .small_button(format!("{collection} ✖"))
+                    .on_hover_text("Take it out of this collection")
+                    .clicked()
+                {
+                    removed = Some(collection.clone());
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let others: Vec<String> = all_collections(&self.current_db)
+                .into_iter()
+                .filter(|collection| !quote.in_collection(collection))
+                .collect();
+            if !others.is_empty() {
+                egui::ComboBox::from_id_source("add_to_collection")
+                    .selected_text("Add to…")
+                    .show_ui(ui, |ui| {
+                        for collection in others {
+                            if ui.selectable_label(false, &collection).clicked() {
+                                added = Some(collection);
+                            }
+                        }
+                    });
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_collection)
+                    .hint_text("New collection")
+                    .desired_width(120.0),
+            );
+            if ui
+                .add_enabled(
+                    !self.new_collection.trim().is_empty(),
+                    egui::Button::new("Add"),
+                )
+                .clicked()
+            {
+                added = Some(std::mem::take(&mut self.new_collection));
+            }
+        });
+
+        if removed.is_none() && added.is_none() {
+            return;
+        }
+        if let Some(index) = self.current_db.iter().position(|q| q == quote) {
+            match update_quote(&mut self.current_db, index, |q| {
+                if let Some(collection) = &removed {
+                    q.remove_from_collection(collection);
+                }
+                if let Some(collection) = &added {
+                    q.add_to_collection(collection);
+                }
+            }) {
+                Ok(()) => self.dirty = true,
+                Err(err) => warn!("Unable to change collections: {err}"),
+            }
+            self.quote_settings = Some(self.current_db[index].clone());
+        }
+    }
+
+    fn rate(&mut self, quote: &Quote, rating: Option<u8>) {
+        if let Some(index) = self.current_db.iter().position(|q| q == quote) {
+            match update_quote(&mut self.current_db, index, |q| q.set_rating(rating)) {
+                Ok(()) => self.dirty = true,
+                Err(err) => warn!("Unable to rate quote: {err}"),
+            }
+            self.quote_settings = Some(self.current_db[index].clone());
+        }
+        if self.sort_key == SortKey::Rating {
+            self.is_unsorted = true;
+        }
+    }
+
+    ///A dropdown for how sure it is that the author really said `quote`
+    fn show_attribution(&mut self, ui: &mut egui::Ui, quote: &Quote) {
+        let mut attribution = quote.attribution();
+        egui::ComboBox::from_id_source("quote_attribution")
+            .selected_text(attribution.map_or_else(
+                || "Attribution not checked".to_string(),
+                |a| format!("{} {a}", a.icon()),
+            ))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut attribution, None, "Not checked");
+                for option in Attribution::ALL {
+                    ui.selectable_value(
+                        &mut attribution,
+                        Some(option),
+                        format!("{} {option}", option.icon()),
+                    );
+                }
+            });
+        if attribution == quote.attribution() {
+            return;
+        }
+        if let Some(index) = self.current_db.iter().position(|q| q == quote) {
+            match update_quote(&mut self.current_db, index, |q| {
+                q.set_attribution(attribution);
+            }) {
+                Ok(()) => self.dirty = true,
+                Err(err) => warn!("Unable to change the attribution: {err}"),
+            }
+            self.quote_settings = Some(self.current_db[index].clone());
+        }
+    }
+
+    ///The two quotes picked for "Merge selected…", to go through [`Self::show_merge_dialog`] like "Merge with…" does
+    fn merge_pair(db: &[Quote], selected: &BTreeSet<usize>) -> (Option<Quote>, Option<Quote>) {
+        match *selected.iter().collect::<Vec<_>>() {
+            [&first, &second] => (db.get(first).cloned(), db.get(second).cloned()),
+            _ => (None, None),
+        }
+    }
+
+    ///Once a second quote has been clicked after "Merge with…" (or two have been selected), asks which text to keep
+    fn show_merge_dialog(&mut self, ctx: &egui::Context) {
+        let (Some(from), Some(into)) = (&self.merge_from, &self.quote_settings) else {
+            return;
+        };
+        if from == into {
+            return;
+        }
+
+        let mut keep = None;
+        let mut cancelled = false;
+        egui::Window::new("Merge Quotes")
+            .collapsible(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label("Which text should be kept? Categories, tags and other details from both are combined.");
+                ui.columns(2, |columns| {
+                    columns[0].label(from.to_string());
+                    columns[1].label(into.to_string());
+                    if columns[0].button("Keep this").clicked() {
+                        keep = Some((from.clone(), into.clone()));
+                    }
+                    if columns[1].button("Keep this").clicked() {
+                        keep = Some((into.clone(), from.clone()));
+                    }
+                });
+                if ui.button("Cancel").clicked() {
+                    cancelled = true;
+                }
+            });
+
+        let merged = keep.is_some();
+        if let Some((kept, dropped)) = keep {
+            self.merge(&kept, &dropped);
+        }
+        if merged || cancelled {
+            self.merge_from = None;
+            self.quote_settings = None;
+        }
+    }
+
+    ///Gives back the merged quote, if it worked
+    fn merge(&mut self, kept: &Quote, dropped: &Quote) -> Option<Quote> {
+        let kept_index = self.current_db.iter().position(|q| q == kept);
+        let dropped_index = self.current_db.iter().position(|q| q == dropped);
+        let result = match (kept_index, dropped_index) {
+            (Some(kept_index), Some(dropped_index)) => {
+                self.category_index = None;
+                self.selected.clear();
+                merge_quotes(&mut self.current_db, kept_index, dropped_index)
+            }
+            _ => Err(Error::QuoteNotFoundInDB(Box::new(dropped.clone()))),
+        };
+        match result {
+            Ok(()) => {
+                self.dirty = true;
+                self.toast(Level::INFO, "Quotes merged");
+                let kept_index = kept_index? - usize::from(dropped_index? < kept_index?);
+                self.current_db.get(kept_index).cloned()
+            }
+            Err(err) => {
+                self.toast(Level::ERROR, format!("Unable to merge quotes: {err}"));
+                None
+            }
+        }
+    }
+
+    ///Swaps in the database once it's loaded, and until then shows a loading or error screen instead of the app.
+    ///
+    ///Returns whether the rest of the app should be drawn.
+    fn show_db_state(&mut self, ctx: &egui::Context) -> bool {
+        if let DbState::Loading(receiver) = &self.db_state {
+            match receiver.try_recv() {
+                Ok(Ok((db, issues))) => {
+                    self.current_db = db;
+                    self.issues = issues;
+                    self.category_index = None;
+                    self.selected.clear();
+                    self.db_state = DbState::Loaded;
+                    self.startup_quote = self.pick_startup_quote().map(|quote| (quote, false));
+                }
+                Ok(Err(err)) => {
+                    error!("Unable to read database for EQ App: {err:?}");
+                    self.db_state = DbState::Failed(err.to_string());
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.db_state =
+                        DbState::Failed("The database loader stopped unexpectedly".into());
+                }
+            }
+        }
+
+        match &self.db_state {
+            DbState::Loaded => true,
+            DbState::Loading(_) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Loading…");
+                    });
+                });
+                ctx.request_repaint();
+                false
+            }
+            DbState::Failed(err) => {
+                let err = err.clone();
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.heading("Unable to load your quotes");
+                    ui.label(err);
+                    ui.label(format!(
+                        "Nothing will be saved over {} until it loads.",
+                        FileType::Database.get_location()
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Retry").clicked() {
+                            self.db_state = DbState::Loading(load_db_in_background(&self.settings));
+                        }
+                        if ui
+                            .button("Start with no quotes")
+                            .on_hover_text("Saving will replace the database that couldn't be read")
+                            .clicked()
+                        {
+                            self.current_db = vec![];
+                            self.db_state = DbState::Loaded;
+                        }
+                    });
+                });
+                false
+            }
+        }
+    }
+
+    ///A URL box for pulling in quotes from an RSS/Atom feed - they're put in the default categories
+    #[cfg(feature = "feed")]
+    fn show_feed_import(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        ui.add(egui::TextEdit::singleline(&mut self.feed_url).hint_text("Feed URL"));
+        if self.feed_fetch.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Fetching…");
+            });
+        } else if ui
+            .add_enabled(
+                !self.feed_url.trim().is_empty(),
+                egui::Button::new("Import from feed"),
+            )
+            .clicked()
+        {
+            let url = self.feed_url.trim().to_string();
+            let categories = self.settings.default_categories.clone();
+            let (sender, receiver) = channel();
+            let fetch_url = url.clone();
+            std::thread::spawn(move || {
+                let result = fetch_feed(&fetch_url, &categories).map_err(|err| err.to_string());
+                //the app has already gone if this fails, so there's no one to tell
+                let _ = sender.send(result);
+            });
+            self.feed_fetch = Some((url, receiver));
+        }
+    }
+
+    ///Once a feed has been fetched, shows the usual import preview for it
+    #[cfg(feature = "feed")]
+    fn poll_feed_import(&mut self, ctx: &egui::Context) {
+        let Some((url, receiver)) = &self.feed_fetch else {
+            return;
+        };
+        let url = url.clone();
+        match receiver.try_recv() {
+            Ok(Ok(quotes)) => {
+                let (preview, new_quotes) = preview_import(&self.current_db, quotes);
+                self.pending_import = Some((url, preview, new_quotes));
+            }
+            Ok(Err(err)) => self.toast(Level::ERROR, format!("Unable to import from {url}: {err}")),
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint();
+                return;
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.toast(Level::ERROR, format!("Fetching {url} stopped unexpectedly"));
+            }
+        }
+        self.feed_fetch = None;
+    }
+
+    ///Lets the columns of a dropped CSV file be matched up with quote fields, with a few rows to check them against, before the usual import preview
+    fn show_csv_mapping(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &mut self.pending_csv else {
+            return;
+        };
+        let mut confirmed = None;
+        egui::Window::new("Import CSV")
+            .collapsible(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(format!("Importing from {}", pending.source));
+                ui.checkbox(&mut pending.mapping.has_header, "The first row is a header");
+                ui.horizontal(|ui| {
+                    ui.label("Categories are separated by");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut pending.mapping.category_delimiter)
+                            .desired_width(30.0),
+                    );
+                });
+
+                egui::Grid::new("csv_columns").striped(true).show(ui, |ui| {
+                    for (i, field) in pending.mapping.columns.iter_mut().enumerate() {
+                        if pending.mapping.has_header {
+                            ui.label(&pending.first_row[i]);
+                        } else {
+                            ui.label(format!("Column {}", i + 1));
+                        }
+                        egui::ComboBox::from_id_source(("csv_column", i))
+                            .selected_text(field.to_string())
+                            .show_ui(ui, |ui| {
+                                for option in CsvField::ALL {
+                                    ui.selectable_value(field, option, option.to_string());
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.label("Preview:");
+                match import_csv_with(&pending.contents, &pending.mapping, CSV_PREVIEW_ROWS) {
+                    Ok(quotes) if quotes.is_empty() => {
+                        ui.weak("No quotes - is a column set to Text?");
+                    }
+                    Ok(quotes) => {
+                        egui::Grid::new("csv_preview").striped(true).show(ui, |ui| {
+                            ui.strong("Text");
+                            ui.strong("Author");
+                            ui.strong("Categories");
+                            ui.end_row();
+                            for quote in quotes {
+                                ui.label(truncate_graphemes(&quote.0, CSV_PREVIEW_LENGTH));
+                                ui.label(quote.author().unwrap_or_default());
+                                ui.label(quote.1.join(", "));
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        ui.colored_label(egui::Color32::RED, err.to_string());
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(pending.mapping.has_text(), egui::Button::new("Continue"))
+                        .clicked()
+                    {
+                        confirmed = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        confirmed = Some(false);
+                    }
+                });
+            });
+
+        if confirmed == Some(true) {
+            if let Some(pending) = self.pending_csv.take() {
+                match import_csv_with(&pending.contents, &pending.mapping, usize::MAX) {
+                    Ok(quotes) => {
+                        let (preview, new_quotes) = preview_import(&self.current_db, quotes);
+                        self.pending_import = Some((pending.source, preview, new_quotes));
+                    }
+                    Err(err) => self.toast(
+                        Level::ERROR,
+                        format!("Import failed for {}: {err}", pending.source),
+                    ),
+                }
+            }
+        } else if confirmed == Some(false) {
+            self.pending_csv = None;
+        }
+    }
+
+    ///The quote to greet the user with, going by [`AppSettings::startup_quote`] - `None` if it's been hidden for today
+    fn pick_startup_quote(&self) -> Option<Quote> {
+        if self.settings.startup_quote_hidden_on == Some(today()) {
+            return None;
+        }
+        match self.settings.startup_quote {
+            StartupQuote::Off => None,
+            StartupQuote::Random => random_quote(&self.current_db),
+            StartupQuote::OfTheDay => quote_of_the_day(&self.current_db, today()),
+        }
+        .cloned()
+    }
+
+    fn show_startup_quote(&mut self, ctx: &egui::Context) {
+        let Some((quote, hide_today)) = &mut self.startup_quote else {
+            return;
+        };
+        let title = if self.settings.startup_quote == StartupQuote::OfTheDay {
+            "Quote of the Day"
+        } else {
+            "A Quote for You"
+        };
+
+        let random = self.settings.startup_quote == StartupQuote::Random;
+        let mut open = true;
+        let mut close = false;
+        let mut another = false;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.set_max_width(400.0);
+                ui.heading(&quote.0);
+                if let Some(author) = quote.author() {
+                    ui.label(format!("- {author}"));
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.checkbox(hide_today, "Don't show again today");
+                    close = ui.button("Close").clicked();
+                    another = random && ui.button("Another").clicked();
+                });
+            });
+
+        if another {
+            self.shown_recently.push(quote.clone());
+            let excess = self
+                .shown_recently
+                .len()
+                .saturating_sub(self.settings.no_repeat_window);
+            self.shown_recently.drain(..excess);
+            if let Some(next) = next_random(
+                &self.current_db,
+                &self.shown_recently,
+                self.settings.no_repeat_window,
+            ) {
+                *quote = next.clone();
+            }
+        }
+
+        if !open || close {
+            if *hide_today {
+                self.settings.startup_quote_hidden_on = Some(today());
+            }
+            self.startup_quote = None;
+        }
+    }
+
+    ///Whether quitting now would lose anything, ie. there's a loaded database on disk with changes to it or the trash
+    const fn has_unsaved_changes(&self) -> bool {
+        !self.in_memory
+            && matches!(self.db_state, DbState::Loaded)
+            && (self.is_dirty() || self.trash_dirty)
+    }
+
+    fn exit_save_failed(&mut self, err: &Error) {
+        warn!("Unable to save db.json: {err}");
+        self.exit_save_error = Some(format!(
+            "Unable to save db.json: {err}\n{}",
+            self.write_recovery()
+        ));
+    }
+
+    fn save_and_quit(&mut self, frame: &mut eframe::Frame) {
+        match self.save_db() {
+            Ok(()) => {
+                self.exit_handled = true;
+                frame.quit();
+            }
+            Err(err) => self.exit_save_failed(&err),
+        }
+    }
+
+    ///"Save before quitting?", after the window is closed with unsaved changes
+    fn show_quit_prompt(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !self.quit_prompt {
+            return;
+        }
+
+        let mut choice = None;
+        egui::Window::new("Save before quitting?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("You have changes that haven't been saved yet.");
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        choice = Some(QuitChoice::Save);
+                    }
+                    if ui.button("Discard").clicked() {
+                        choice = Some(QuitChoice::Discard);
+                    }
+                    if ui.button("Cancel").clicked() || ui.input().key_pressed(Key::Escape) {
+                        choice = Some(QuitChoice::Cancel);
+                    }
+                });
+            });
+
+        if let Some(choice) = choice {
+            self.quit_prompt = false;
+            match choice {
+                QuitChoice::Save => self.save_and_quit(frame),
+                QuitChoice::Discard => {
+                    info!("Quitting without saving changes");
+                    self.exit_handled = true;
+                    frame.quit();
+                }
+                QuitChoice::Cancel => {}
+            }
+        }
+    }
+
+    ///The list of [`SHORTCUTS`], which goes away on a click anywhere else
+    fn show_shortcuts_overlay(&mut self, ctx: &egui::Context) {
+        if !self.show_shortcuts {
+            return;
+        }
+        let response = egui::Window::new("Keyboard Shortcuts")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .open(&mut self.show_shortcuts)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for (keys, action) in SHORTCUTS {
+                        ui.monospace(*keys);
+                        ui.label(*action);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if let Some(response) = response {
+            let input = ctx.input();
+            let clicked_outside = input.pointer.any_click()
+                && input
+                    .pointer
+                    .interact_pos()
+                    .is_some_and(|pos| !response.response.rect.contains(pos));
+            drop(input);
+            if clicked_outside {
+                self.show_shortcuts = false;
+            }
+        }
+    }
+
+    ///Writes the database to the recovery file, returning a message saying where it ended up
+    fn write_recovery(&self) -> String {
+        let location = FileType::Recovery.get_location();
+        match self.write_leniently(location) {
+            Ok(()) => {
+                warn!("Wrote unsaved quotes to {location}");
+                format!("A copy of your quotes has been written to {location}.")
+            }
+            Err(err) => {
+                error!("Unable to write recovery file: {err}");
+                format!("Unable to write a recovery copy to {location} either: {err}")
+            }
+        }
+    }
+}
+
+impl eframe::App for EnglishQuotesApp {
+    #[allow(clippy::too_many_lines)]
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !self.show_db_state(ctx) {
+            return;
+        }
+        #[cfg(feature = "feed")]
+        self.poll_feed_import(ctx);
+
+        //changing the scale mid-drag moves the slider out from under the pointer
+        let pixels_per_point =
+            frame.info().native_pixels_per_point.unwrap_or(1.0) * self.settings.ui_scale;
+        if !ctx.is_using_pointer() && (ctx.pixels_per_point() - pixels_per_point).abs() > 0.001 {
+            ctx.set_pixels_per_point(pixels_per_point);
+        }
+
+        if let Some(err) = self.exit_save_error.clone() {
+            egui::Window::new("Unable to Save")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(err);
+                    ui.horizontal(|ui| {
+                        if ui.button("Retry").clicked() {
+                            self.exit_save_error = None;
+                            self.save_and_quit(frame);
+                        }
+                        if ui.button("Quit anyway (lose changes)").clicked() {
+                            self.exit_save_error = None;
+                            self.exit_handled = true;
+                            frame.quit();
+                        }
+                        if ui.button("Keep Editing").clicked() {
+                            self.exit_save_error = None;
+                        }
+                    });
+                });
+        }
+
+        self.show_quit_prompt(ctx, frame);
+
+        if ctx.style().visuals.dark_mode != self.settings.dark_mode {
+            ctx.set_visuals(if self.settings.dark_mode {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+
+        let title = window_title(self.current_db.len());
+        if title != self.window_title {
+            frame.set_window_title(&title);
+            self.window_title = title;
+        }
+
+        //a `/` typed into a focused text field is just text, so only look for the shortcut when nothing has focus
+        let typing = ctx.wants_keyboard_input();
+        let (search_shortcut, palette_shortcut, shortcuts_shortcut, escape) = {
+            let input = ctx.input();
+            let typed = |wanted: &str| {
+                !typing
+                    && input
+                        .events
+                        .iter()
+                        .any(|event| matches!(event, Event::Text(text) if text == wanted))
+            };
+            (
+                (input.modifiers.command && input.key_pressed(Key::F)) || typed("/"),
+                input.modifiers.command && input.key_pressed(Key::K),
+                typed("?"),
+                input.key_pressed(Key::Escape),
+            )
+        };
+        if shortcuts_shortcut {
+            self.show_shortcuts = !self.show_shortcuts;
+        } else if escape {
+            self.show_shortcuts = false;
+        }
+        self.show_shortcuts_overlay(ctx);
+        self.show_startup_quote(ctx);
+        if search_shortcut {
+            if self.current_state.view() != View::Search {
+                self.go_to(View::Search);
+            }
+            self.focus_search = true;
+        }
+        if palette_shortcut {
+            self.palette.toggle();
+        }
+        if self.palette.is_open() {
+            let mut commands = self.commands();
+            if let Some(picked) = self.palette.show(ctx, &commands) {
+                (commands[picked].run)(self);
+            }
+        }
+
+        if self.settings.side_panel_collapsed {
+            egui::SidePanel::new(Side::Left, "tab_menu_collapsed")
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if ui.button("»").on_hover_text("Show menus").clicked() {
+                        self.settings.side_panel_collapsed = false;
+                    }
+                });
+        } else {
+            self.show_side_panel(ctx);
+        }
+
+        self.show_merge_dialog(ctx);
+
+        {
+            let mut new_qs = false;
+            //the merge dialog takes over while a second quote is being picked
+            let merging = self.merge_from.is_some() && self.merge_from != self.quote_settings;
+            if let (Some(quote), false) = (self.quote_settings.clone(), merging) {
+                self.note_viewed(&quote);
+                if self.settings.preview_pane {
+                    egui::SidePanel::right("quote_preview").show(ctx, |ui| {
+                        new_qs = self.show_quote_details(ui, &quote);
+                    });
+                } else {
+                    egui::Window::new("Quote Settings")
+                        .collapsible(false)
+                        .resizable(true)
+                        .show(ctx, |ui| {
+                            new_qs = self.show_quote_details(ui, &quote);
+                        });
+                }
+            }
+
+            if new_qs {
+                self.quote_settings = None;
+            }
+        }
+
+        if self.show_about {
+            egui::Window::new("About")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut self.show_about)
+                .show(ctx, |ui| {
+                    ui.label(format!("English Quotes v{}", env!("CARGO_PKG_VERSION")));
+                    if self.in_memory {
+                        ui.label("Database: in memory, changes won't be saved");
+                    } else {
+                        let db = FileType::Database.get_location();
+                        let db = std::fs::canonicalize(db)
+                            .map_or_else(|_| db.to_string(), |path| path.display().to_string());
+                        ui.label(format!("Database: {db}"));
+                    }
+                    ui.label(format!("Quotes: {}", self.current_db.len()));
+                });
+        }
+
+        if self.show_export_dialog {
+            let mut result = None;
+            egui::Window::new("Export")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::ComboBox::from_label("Format")
+                        .selected_text(self.export_format.to_string())
+                        .show_ui(ui, |ui| {
+                            for format in ExportFormat::ALL {
+                                if ui
+                                    .selectable_label(
+                                        self.export_format.same_kind(format),
+                                        format.to_string(),
+                                    )
+                                    .clicked()
+                                    && !self.export_format.same_kind(format)
+                                {
+                                    self.export_format = format;
+                                }
+                            }
+                        });
+
+                    if let ExportFormat::PlainText(separator) = &mut self.export_format {
+                        egui::ComboBox::from_label("Separator")
+                            .selected_text(separator.to_string())
+                            .show_ui(ui, |ui| {
+                                for option in Separator::ALL {
+                                    ui.selectable_value(separator, option, option.to_string());
+                                }
+                            });
+                    }
+
+                    let groups = matches!(
+                        self.export_format,
+                        ExportFormat::Markdown | ExportFormat::HtmlApp
+                    );
+                    ui.add_enabled_ui(groups, |ui| {
+                        egui::ComboBox::from_label("Group")
+                            .selected_text(self.export_options.grouping.to_string())
+                            .show_ui(ui, |ui| {
+                                for grouping in ExportGrouping::ALL {
+                                    ui.selectable_value(
+                                        &mut self.export_options.grouping,
+                                        grouping,
+                                        grouping.to_string(),
+                                    );
+                                }
+                            });
+                    });
+
+                    ui.label("Fields to include:");
+                    ui.checkbox(&mut self.export_fields.text, "Text");
+                    ui.checkbox(&mut self.export_fields.author, "Author");
+                    ui.checkbox(&mut self.export_fields.categories, "Categories");
+                    ui.checkbox(&mut self.export_fields.attribution, "Attribution");
+                    ui.checkbox(&mut self.export_fields.timestamps, "Dates added/edited");
+                    ui.checkbox(&mut self.export_fields.tags, "Tags");
+                    ui.checkbox(&mut self.export_fields.source, "Source");
+                    ui.checkbox(&mut self.export_fields.rating, "Rating");
+                    ui.checkbox(&mut self.export_fields.collections, "Collections");
+
+                    let wraps = matches!(
+                        self.export_format,
+                        ExportFormat::Markdown | ExportFormat::PlainText(_)
+                    );
+                    ui.add_enabled_ui(wraps, |ui| {
+                        ui.horizontal(|ui| {
+                            let mut wrap = self.export_options.wrap_width.is_some();
+                            if ui.checkbox(&mut wrap, "Wrap lines at column").changed() {
+                                self.export_options.wrap_width = wrap.then_some(DEFAULT_WRAP_WIDTH);
+                            }
+                            if let Some(width) = &mut self.export_options.wrap_width {
+                                ui.add(egui::DragValue::new(width).clamp_range(20..=200));
+                            }
+                        });
+                    });
+
+                    if let Some(selected) = self.export_selected.as_ref().map(Vec::len) {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Only the {selected} selected quotes"));
+                            if ui.button("Export all instead").clicked() {
+                                self.export_selected = None;
+                            }
+                        });
+                    } else {
+                        ui.add_enabled(
+                            self.visible.is_some(),
+                            egui::Checkbox::new(
+                                &mut self.export_shown_only,
+                                "Only the quotes currently shown",
+                            ),
+                        );
+                    }
+                    show_export_exclusions(
+                        ui,
+                        &mut self.settings.export_exclusions,
+                        &all_categories(&self.settings.custom_categories),
+                    );
+                    let withheld =
+                        match (&self.export_selected, self.export_shown_only, &self.visible) {
+                            (Some(indices), _, _) | (None, true, Some(indices)) => self
+                                .settings
+                                .export_exclusions
+                                .withheld(indices.iter().filter_map(|&i| self.current_db.get(i))),
+                            _ => self.settings.export_exclusions.withheld(&self.current_db),
+                        };
+                    if withheld > 0 {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("{withheld} quotes in excluded categories will be left out"),
+                        );
+                    }
 
-#[derive(Clone, Debug, PartialEq)]
-pub enum CurrentAppState {
-    QuoteCategories {
-        current_quote_filter: QuoteSelectionFilter,
-    },
-    QuoteEntry {
-        current_text: String,
-    },
-    Search {
-        current_search_term: String,
-        is_inverted: bool,
-    },
-}
+                    ui.checkbox(&mut self.export_dated, "Only quotes added between");
+                    ui.add_enabled_ui(self.export_dated, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.export_from)
+                                    .hint_text("YYYY-MM-DD")
+                                    .desired_width(90.0),
+                            );
+                            ui.label("and");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.export_to)
+                                    .hint_text("YYYY-MM-DD")
+                                    .desired_width(90.0),
+                            );
+                        });
+                        ui.checkbox(
+                            &mut self.export_include_undated,
+                            "Include quotes with no date",
+                        );
+                    });
+                    let range_ok = !self.export_dated
+                        || DateRange::from_dates(&self.export_from, &self.export_to, false)
+                            .is_some();
+                    if !range_ok {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            "Enter two dates as YYYY-MM-DD, the first no later than the second",
+                        );
+                    }
 
-pub struct EnglishQuotesApp {
-    current_state: CurrentAppState,
-    current_db: Vec<Quote>,
-    current_checked: Vec<bool>,
-    quote_settings: Option<Quote>,
-}
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(range_ok, egui::Button::new("Export"))
+                            .clicked()
+                        {
+                            result = Some(
+                                self.run_export(self.export_format.file_type().get_location()),
+                            );
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_export_dialog = false;
+                        }
+                    });
 
-impl Default for EnglishQuotesApp {
-    fn default() -> Self {
-        Self {
-            current_state: CurrentAppState::QuoteCategories {
-                current_quote_filter: QuoteSelectionFilter::default(),
-            },
-            current_db: read_db().unwrap_or_else(|error| {
-                warn!("Unable to read database for EQ App: {error:?}");
-                vec![]
-            }),
-            current_checked: vec![false; ALL_PERMS.len()],
-            quote_settings: None,
+                    ui.separator();
+                    let registry = ExporterRegistry::with_builtins(
+                        &self.export_options_with_template(),
+                        self.export_fields,
+                    );
+                    ui.label("Back up everything, in one format or all of them:");
+                    ui.horizontal(|ui| {
+                        ui.label("Folder");
+                        ui.text_edit_singleline(&mut self.export_all_dir);
+                    });
+                    ui.horizontal_wrapped(|ui| {
+                        for exporter in registry.iter() {
+                            if ui.button(exporter.name()).clicked() {
+                                self.export_backup(exporter);
+                            }
+                        }
+                    });
+                    if ui.button("Export All Formats").clicked() {
+                        self.export_all_formats();
+                    }
+                });
+
+            if let Some(result) = result {
+                self.show_export_dialog = false;
+                self.handle_export_result(result, self.export_format.file_type().get_location());
+            }
         }
-    }
-}
 
-impl eframe::App for EnglishQuotesApp {
-    #[allow(clippy::too_many_lines)]
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::SidePanel::new(Side::Left, "tab_menu").show(ctx, |ui| {
-            ui.heading("Menus");
+        if let Some((failed_path, new_path)) = &mut self.export_fallback {
+            let mut export_here = None;
+            let mut cancelled = false;
+            egui::Window::new("Export Failed")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Unable to write to {}, as it isn't writable. Pick somewhere else to export to:",
+                        failed_path.display()
+                    ));
+                    ui.text_edit_singleline(new_path);
+                    ui.horizontal(|ui| {
+                        if ui.button("Export Here").clicked() {
+                            export_here = Some(new_path.clone());
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
 
-            if ui.button("All Quotes").clicked() {
-                self.current_state = CurrentAppState::QuoteCategories {
-                    current_quote_filter: QuoteSelectionFilter::default(),
-                };
+            if let Some(path) = export_here {
+                let result = self.run_export(&path);
+                self.handle_export_result(result, &path);
+            } else if cancelled {
+                self.export_fallback = None;
             }
-            if ui.button("Quote Entry").clicked() {
-                self.current_state = CurrentAppState::QuoteEntry {
-                    current_text: String::default(),
-                };
+        }
+
+        let dropped_files = ctx.input().raw.dropped_files.clone();
+        for path in dropped_files.into_iter().filter_map(|file| file.path) {
+            let result = read_import(&path).and_then(|(format, contents)| {
+                let source = import_source(&path, format);
+                //CSV columns could be anything, so they're checked over before getting this far
+                if format == ImportFormat::Csv {
+                    let first_row = csv_first_row(&contents)?;
+                    self.pending_csv = Some(PendingCsv {
+                        source,
+                        contents,
+                        mapping: CsvMapping::guess(&first_row),
+                        first_row,
+                    });
+                } else {
+                    let quotes = parse_import(format, &contents)?;
+                    let (preview, new_quotes) = preview_import(&self.current_db, quotes);
+                    self.pending_import = Some((source, preview, new_quotes));
+                }
+                Ok(())
+            });
+            if let Err(err) = result {
+                self.toast(
+                    Level::ERROR,
+                    format!("Import failed for {}: {err}", path.display()),
+                );
             }
-            if ui.button("Search Quotes").clicked() {
-                self.current_state = CurrentAppState::Search {
-                    current_search_term: String::default(),
-                    is_inverted: false,
-                };
+        }
+        self.show_csv_mapping(ctx);
+
+        if let Some((path, preview, _)) = &self.pending_import {
+            let mut confirmed = None;
+            egui::Window::new("Import Quotes")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("Importing from {path}:"));
+                    ui.label(format!("{} new quotes", preview.new));
+                    ui.label(format!(
+                        "{} duplicates of quotes already in the database",
+                        preview.duplicates
+                    ));
+                    ui.label(format!(
+                        "{} quotes already in the database with different categories",
+                        preview.category_conflicts
+                    ));
+                    if preview.duplicates + preview.category_conflicts > 0 {
+                        ui.label("Duplicates and conflicts will be skipped.");
+                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(
+                                preview.new > 0,
+                                egui::Button::new(format!("Import {} new", preview.new)),
+                            )
+                            .clicked()
+                        {
+                            confirmed = Some(true);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            confirmed = Some(false);
+                        }
+                    });
+                });
+
+            if let Some(confirmed) = confirmed {
+                if let Some((_, _, quotes)) = self.pending_import.take() {
+                    if confirmed {
+                        self.category_index = None;
+                        self.selected.clear();
+                        let result = add_quotes_batch(
+                            quotes,
+                            &mut self.current_db,
+                            self.settings.normalize_on_add,
+                            self.settings.punctuation,
+                        );
+                        self.dirty |= result.added + result.merged > 0;
+                        if result.added + result.merged > 0 {
+                            self.enforce_category_caps();
+                        }
+                        self.sort_after_add();
+                        let mut message = vec![format!("Imported {} quotes", result.added)];
+                        if result.merged > 0 {
+                            message.push(format!("merged {} into existing ones", result.merged));
+                        }
+                        if result.skipped > 0 {
+                            message.push(format!("skipped {}", result.skipped));
+                        }
+                        self.toast(Level::INFO, message.join(", "));
+                    }
+                }
             }
-            if ui.button("Export").clicked() {
-                export().unwrap_or_else(|err| warn!("Unable to export: {err}"));
+        }
+
+        if let Some(conflicts) = &self.attribution_conflicts {
+            let mut finished = false;
+            let mut jump_to = None;
+            egui::Window::new("Attribution Conflicts")
+                .collapsible(false)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    if conflicts.is_empty() {
+                        ui.label("Every quote has just the one author.");
+                    }
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
+                        .show(ui, |ui| {
+                            for (text, authors) in conflicts {
+                                ui.label(text);
+                                ui.horizontal_wrapped(|ui| {
+                                    for author in authors {
+                                        if ui
+                                            .button(author)
+                                            .on_hover_text("Show this quote")
+                                            .clicked()
+                                        {
+                                            jump_to = Some((text.clone(), author.clone()));
+                                        }
+                                    }
+                                });
+                                ui.separator();
+                            }
+                        });
+                    if ui.button("Done").clicked() {
+                        finished = true;
+                    }
+                });
+
+            if let Some((text, author)) = jump_to {
+                let text = normalize_text(&text);
+                let author = author.to_lowercase();
+                self.quote_settings = self
+                    .current_db
+                    .iter()
+                    .find(|quote| {
+                        normalize_text(&quote.0) == text
+                            && quote.author().map(|a| a.trim().to_lowercase())
+                                == Some(author.clone())
+                    })
+                    .cloned();
             }
-        });
+            if finished {
+                self.attribution_conflicts = None;
+            }
+        }
 
-        {
-            let mut new_qs = false;
-            if let Some(quote) = &self.quote_settings {
-                egui::Window::new("Quote Settings")
-                    .collapsible(false)
-                    .resizable(true)
-                    .show(ctx, |ui| {
-                        ui.heading(&quote.0);
-                        if ui.button("Delete Quote").clicked() {
-                            remove_quote(quote, Some(&mut self.current_db))
-                                .unwrap_or_else(|err| warn!("Unable to remove quote: {err}"));
-                            new_qs = true;
-                        }
-                        if ui.button("Edit Quote").clicked() {
-                            remove_quote(quote, Some(&mut self.current_db))
-                                .unwrap_or_else(|err| warn!("Unable to remove quote: {err}"));
-
-                            let quote = quote.clone();
-
-                            self.current_state = CurrentAppState::QuoteEntry {
-                                current_text: quote.0,
-                            };
-                            self.current_checked = reverse_chosen_types(quote.1);
+        if let Some(pairs) = &self.near_duplicates {
+            let mut finished = false;
+            //which pair was resolved, and the quote to remove (if any)
+            let mut resolved: Option<(usize, Option<Quote>)> = None;
+            //which pair was merged, the quote that's kept and the one folded into it
+            let mut merged: Option<(usize, Quote, Quote)> = None;
+            let mut rescan = false;
+            egui::Window::new("Similar Quotes")
+                .collapsible(false)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add(
+                                egui::Slider::new(
+                                    &mut self.settings.similarity_threshold,
+                                    0.0..=1.0,
+                                )
+                                .step_by(0.05)
+                                .text("Similarity"),
+                            )
+                            .on_hover_text("Lower finds more pairs, that are less alike")
+                            .changed()
+                        {
+                            self.similarity_preview = Some(
+                                find_near_duplicates(
+                                    &self.current_db,
+                                    self.settings.similarity_threshold,
+                                )
+                                .len(),
+                            );
+                        }
+                        if let Some(count) = self.similarity_preview {
+                            rescan = ui.button(format!("Show these {count} pairs")).clicked();
+                        }
+                    });
+                    ui.separator();
+
+                    if pairs.is_empty() {
+                        ui.label("No similar quotes left.");
+                    }
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
+                        .show(ui, |ui| {
+                            for (i, (left, right, score)) in pairs.iter().enumerate() {
+                                ui.label(format!("{:.0}% similar", score * 100.0));
+                                ui.columns(2, |columns| {
+                                    columns[0].label(left.to_string());
+                                    columns[1].label(right.to_string());
+                                });
+                                ui.horizontal(|ui| {
+                                    if ui.button("Keep left").clicked() {
+                                        resolved = Some((i, Some(right.clone())));
+                                    }
+                                    if ui.button("Keep right").clicked() {
+                                        resolved = Some((i, Some(left.clone())));
+                                    }
+                                    if ui.button("Keep both").clicked() {
+                                        resolved = Some((i, None));
+                                    }
+                                    if ui.button("Merge into left").clicked() {
+                                        merged = Some((i, left.clone(), right.clone()));
+                                    }
+                                    if ui.button("Merge into right").clicked() {
+                                        merged = Some((i, right.clone(), left.clone()));
+                                    }
+                                });
+                                ui.separator();
+                            }
+                        });
+                    if ui.button("Done").clicked() {
+                        finished = true;
+                    }
+                });
+
+            if let Some((i, kept, dropped)) = merged {
+                let merged = self.merge(&kept, &dropped);
+                if let (Some(pairs), Some(merged)) = (&mut self.near_duplicates, merged) {
+                    pairs.remove(i);
+                    pairs.retain(|(a, b, _)| a != &dropped && b != &dropped);
+                    //the kept quote may have gained categories, so update any other pairs it's in
+                    for (a, b, _) in pairs.iter_mut() {
+                        if a == &kept {
+                            a.clone_from(&merged);
+                        }
+                        if b == &kept {
+                            b.clone_from(&merged);
+                        }
+                    }
+                }
+            }
+
+            if finished {
+                self.near_duplicates = None;
+                self.similarity_preview = None;
+            } else if rescan {
+                self.find_similar_quotes();
+            } else if let Some((i, removed)) = resolved {
+                if let Some(pairs) = &mut self.near_duplicates {
+                    pairs.remove(i);
+                    //anything else paired with the removed quote is moot now
+                    if let Some(removed) = &removed {
+                        pairs.retain(|(a, b, _)| a != removed && b != removed);
+                    }
+                }
+                if let Some(removed) = removed {
+                    if let Err(err) = self.delete_quote(&removed) {
+                        warn!("Unable to remove quote: {err}");
+                    }
+                }
+            }
+        }
+
+        if let Some(unused) = self.pending_category_cleanup.clone() {
+            let mut finished = false;
+            egui::Window::new("Clean up Categories")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if unused.is_empty() {
+                        ui.label("Every custom category is in use.");
+                        if ui.button("Ok").clicked() {
+                            finished = true;
+                        }
+                    } else {
+                        ui.label("These custom categories aren't used by any quotes:");
+                        for cat in &unused {
+                            ui.label(format!(" - {cat}"));
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Remove").clicked() {
+                                self.settings
+                                    .custom_categories
+                                    .retain(|cat| !unused.contains(cat));
+                                self.current_checked.retain(|cat| !unused.contains(cat));
+                                finished = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                finished = true;
+                            }
+                        });
+                    }
+                });
+
+            if finished {
+                self.pending_category_cleanup = None;
+            }
+        }
 
-                            new_qs = true;
+        if let Some(indices) = self.pending_bulk_delete.clone() {
+            let mut finished = false;
+            egui::Window::new("Delete Quotes")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if self.in_memory {
+                        ui.label(format!(
+                            "Are you sure you want to delete {} quotes?",
+                            indices.len()
+                        ));
+                    } else {
+                        ui.label(format!(
+                            "Are you sure you want to delete {} quotes? A backup will be written to {} first.",
+                            indices.len(),
+                            FileType::Backup.get_location()
+                        ));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            let backup = if self.in_memory {
+                                Ok(())
+                            } else {
+                                write_db(&self.current_db, FileType::Backup.get_location())
+                            };
+                            match backup {
+                                Ok(()) => {
+                                    let removed = self.delete_quotes(&indices);
+                                    push_toast(
+                                        &mut self.toasts,
+                                        Level::INFO,
+                                        format!("Removed {removed} quotes"),
+                                    );
+                                }
+                                Err(err) => push_toast(
+                                    &mut self.toasts,
+                                    Level::ERROR,
+                                    format!("Unable to back up database, not deleting: {err}"),
+                                ),
+                            }
+                            finished = true;
                         }
                         if ui.button("Cancel").clicked() {
-                            new_qs = true;
+                            finished = true;
                         }
                     });
+                });
+
+            if finished {
+                self.pending_bulk_delete = None;
             }
+        }
 
-            if new_qs {
-                self.quote_settings = None;
+        if let Some((indices, tags)) = &mut self.pending_bulk_tag {
+            let mut apply = false;
+            let mut cancelled = false;
+            egui::Window::new("Tag Quotes")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("Tags to add to {} quotes:", indices.len()));
+                    ui.add(egui::TextEdit::singleline(tags).hint_text("comma, separated"));
+                    ui.horizontal(|ui| {
+                        apply = ui
+                            .add_enabled(!parse_tags(tags).is_empty(), egui::Button::new("Add"))
+                            .clicked();
+                        cancelled = ui.button("Cancel").clicked();
+                    });
+                });
+
+            if apply {
+                let tagged = tag_quotes(
+                    &mut self.current_db,
+                    indices.iter().copied(),
+                    &parse_tags(tags),
+                );
+                if tagged > 0 {
+                    self.dirty = true;
+                    self.category_index = None;
+                    self.selected.clear();
+                }
+                push_toast(
+                    &mut self.toasts,
+                    Level::INFO,
+                    format!("Tagged {tagged} quotes"),
+                );
+            }
+            if apply || cancelled {
+                self.pending_bulk_tag = None;
             }
         }
 
-        egui::CentralPanel::default().show(ctx, |ui| match &mut self.current_state {
-            CurrentAppState::QuoteCategories {
-                current_quote_filter,
-            } => {
+        let view = std::mem::discriminant(&self.current_state);
+        let saved_offset = (self.last_view != Some(view))
+            .then(|| self.scroll_offsets.get(&view).copied().unwrap_or_default());
+        self.last_view = Some(view);
 
+        let categories = all_categories(&self.settings.custom_categories);
+        let template = QuoteTemplate::parse_or_default(&self.settings.quote_template);
+        let longest = self
+            .current_db
+            .iter()
+            .map(|quote| quote.0.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        self.show_issue_review(ctx);
+
+        //a category chip clicked in the list, and whether to add it to the filter rather than replace it
+        let mut chip_clicked: Option<(String, bool)> = None;
+        //category limits can't be applied while the entry form is borrowed
+        let mut quote_added = false;
+        egui::CentralPanel::default().show(ctx, |ui| match &mut self.current_state {
+            CurrentAppState::QuoteCategories => {
                 ui.horizontal(|ui| {
                     ui.vertical(|ui| {
                         ui.heading("All Quotes");
                         ui.label("---");
 
                         ui.radio_value(
-                            current_quote_filter,
+                            &mut self.filters.mode,
                             QuoteSelectionFilter::Or,
                             "One of selected",
                         );
                         ui.radio_value(
-                            current_quote_filter,
+                            &mut self.filters.mode,
                             QuoteSelectionFilter::And,
                             "All of selected",
                         );
 
                         ui.label("---"); //separator messes up the horizontal
-                        vertical_category_checkbox(ui, &mut self.current_checked);
+                        vertical_category_checkbox(
+                            ui,
+                            &mut self.current_checked,
+                            &categories,
+                            &self.current_db,
+                            &mut self.category_filter,
+                            self.settings.category_grid,
+                        );
+
+                        let collections = all_collections(&self.current_db);
+                        if self.filters.collection.as_ref().is_some_and(|filter| {
+                            !collections.iter().any(|collection| collection == filter)
+                        }) {
+                            self.filters.collection = None;
+                        }
+                        if !collections.is_empty() {
+                            ui.label("---");
+                            ui.label("Collections");
+                            ui.radio_value(&mut self.filters.collection, None, "Any");
+                            for collection in collections {
+                                ui.radio_value(
+                                    &mut self.filters.collection,
+                                    Some(collection.clone()),
+                                    collection,
+                                );
+                            }
+                        }
                     });
 
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        ui.vertical(|ui| {
-                            let chosen_types: Vec<String> =
-                                get_chosen_types(self.current_checked.clone());
-
-                            let chosen_quotes =
-                                self.current_db.clone().into_iter().filter(|quote| {
-                                    match *current_quote_filter {
-                                        QuoteSelectionFilter::And => {
-                                            let mut works = true;
-
-                                            for t in &chosen_types {
-                                                if !quote.1.contains(t) {
-                                                    works = false;
-                                                    break;
-                                                }
-                                            }
-
-                                            works
-                                        }
-                                        QuoteSelectionFilter::Or => {
-                                            let mut works = false;
+                    ui.vertical(|ui| {
+                        self.filters.show_bar(
+                            ui,
+                            Some(&mut self.current_checked),
+                            &categories,
+                            None,
+                        );
+                        self.filters.quick.show_chips(ui);
+                        self.filters.length.show(ui, longest);
 
-                                            for t in &chosen_types {
-                                                if quote.1.contains(t) {
-                                                    works = true;
-                                                    break;
-                                                }
-                                            }
+                        let mut scroll_area = egui::ScrollArea::vertical();
+                        if let Some(offset) = saved_offset {
+                            scroll_area = scroll_area.vertical_scroll_offset(offset);
+                        }
 
-                                            works
-                                        }
-                                    }
-                                });
+                        let output = scroll_area.show(ui, |ui| {
+                            ui.vertical(|ui| {
+                                let chosen_types: Vec<String> =
+                                    get_chosen_types(&self.current_checked, &categories);
 
-                            // info!("Quotes: {:?}", &chosen_quotes);
+                                let total_no = self.current_db.len();
+                                //with nothing checked the quick filters work across every quote
+                                let filtered = self.filters.narrows();
+                                let narrow = |quote: &Quote| self.filters.matches(quote);
+                                let index = self
+                                    .category_index
+                                    .get_or_insert_with(|| build_category_index(&self.current_db));
+                                let shown = filter_quotes(
+                                    &self.current_db,
+                                    index,
+                                    &chosen_types,
+                                    self.filters.mode,
+                                    filtered.then_some(&narrow as &dyn Fn(&Quote) -> bool),
+                                );
 
-                            display_quotes_list(
-                                chosen_quotes,
-                                ui,
-                                Some(|quote| self.quote_settings = Some(quote)),
-                            );
-                        })
+                                self.visible = Some(shown.clone());
+                                counts_header(
+                                    ui,
+                                    shown.len(),
+                                    total_no,
+                                    &self.filters.length.describe(),
+                                );
+
+                                if !shown.is_empty() && ui.button("Delete all shown").clicked() {
+                                    self.pending_bulk_delete = Some(shown.clone());
+                                }
+                                match selection_toolbar(
+                                    ui,
+                                    &mut self.select_mode,
+                                    &mut self.selected,
+                                    &shown,
+                                ) {
+                                    Some(SelectionAction::Delete) => {
+                                        self.pending_bulk_delete =
+                                            Some(self.selected.iter().copied().collect());
+                                    }
+                                    Some(SelectionAction::Copy) => copy_quotes(
+                                        ui,
+                                        &mut self.toasts,
+                                        &self.current_db,
+                                        self.selected.iter().copied(),
+                                        &template,
+                                    ),
+                                    Some(SelectionAction::Export) => {
+                                        self.export_selected =
+                                            Some(self.selected.iter().copied().collect());
+                                        self.show_export_dialog = true;
+                                    }
+                                    Some(SelectionAction::Merge) => {
+                                        (self.merge_from, self.quote_settings) =
+                                            Self::merge_pair(&self.current_db, &self.selected);
+                                    }
+                                    Some(SelectionAction::Tag) => {
+                                        self.pending_bulk_tag = Some((
+                                            self.selected.iter().copied().collect(),
+                                            String::new(),
+                                        ));
+                                    }
+                                    None => {}
+                                }
+                                if shown.is_empty() {
+                                    render_empty_state(
+                                        ui,
+                                        if total_no == 0 {
+                                            EmptyState::EmptyDb
+                                        } else if filtered {
+                                            EmptyState::FilteredOut
+                                        } else if chosen_types.is_empty() {
+                                            EmptyState::NothingChecked
+                                        } else {
+                                            EmptyState::NoCategoryMatches
+                                        },
+                                    );
+                                }
+
+                                let rows = display_quotes_list(
+                                    shown.iter().map(|&i| (i, self.current_db[i].clone())),
+                                    ui,
+                                    self.settings.density,
+                                    self.settings.quote_font_size,
+                                    &template,
+                                    |_| None,
+                                    Some(|quote| self.quote_settings = Some(quote)),
+                                    Some(|category, add| chip_clicked = Some((category, add))),
+                                    self.select_mode.then_some(&mut self.selected),
+                                );
+                                show_flash(ui, &mut self.flash, &self.current_db, &rows);
+                            })
+                        });
+                        self.scroll_offsets.insert(view, output.state.offset.y);
                     });
                 });
             }
-            CurrentAppState::QuoteEntry { current_text } => {
-                ui.heading("Quote Entry");
+            CurrentAppState::QuoteEntry {
+                current_text,
+                current_author,
+                current_source,
+                current_tags,
+                editing,
+                validation,
+            } => {
+                self.visible = None;
+                //`None` if the quote being edited has gone, eg. deleted from the search view
+                let editing_index = editing
+                    .as_ref()
+                    .and_then(|original| self.current_db.iter().position(|q| q == &**original));
+                if editing.is_some() && editing_index.is_none() {
+                    *editing = None;
+                }
+                ui.heading(if editing.is_some() {
+                    "Edit Quote"
+                } else {
+                    "Quote Entry"
+                });
+
+                if validation.is_some() {
+                    let text = if self.settings.normalize_on_add {
+                        normalize_text(current_text)
+                    } else {
+                        current_text.clone()
+                    };
+                    let candidate = Quote::builder(text).author(current_author.as_str()).build();
+                    let result = editing_index.map_or_else(
+                        || validate_quote(&candidate, &self.current_db),
+                        |index| validate_edit(&candidate, &self.current_db, index),
+                    );
+                    if result.is_ok() {
+                        *validation = None;
+                    }
+                }
 
                 ui.horizontal(|ui| {
-                    vertical_category_checkbox(ui, &mut self.current_checked);
+                    vertical_category_checkbox(
+                        ui,
+                        &mut self.current_checked,
+                        &categories,
+                        &self.current_db,
+                        &mut self.category_filter,
+                        self.settings.category_grid,
+                    );
                     ui.vertical(|ui| {
-                        ui.text_edit_singleline(current_text);
+                        ui.scope(|ui| {
+                            if validation.is_some() {
+                                let stroke = egui::Stroke::new(1.5, egui::Color32::RED);
+                                let visuals = ui.visuals_mut();
+                                visuals.widgets.inactive.bg_stroke = stroke;
+                                visuals.widgets.hovered.bg_stroke = stroke;
+                                visuals.selection.stroke = stroke;
+                            }
+                            spellchecked_text_edit(ui, current_text, self.settings.spellcheck);
+                        });
+                        if let Some(err) = validation {
+                            ui.colored_label(egui::Color32::RED, err.to_string());
+                        }
+                        if let Some(original) = editing.as_deref() {
+                            let spans = word_diff(&original.0, current_text);
+                            if spans.iter().any(|span| !matches!(span, DiffSpan::Same(_))) {
+                                ui.label("Changes:");
+                                egui::ScrollArea::vertical()
+                                    .id_source("edit_diff")
+                                    .max_height(150.0)
+                                    .show(ui, |ui| show_word_diff(ui, &spans));
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Author: ");
+                            ui.text_edit_singleline(current_author);
+                        });
+                        let source_ok =
+                            current_source.trim().is_empty() || is_valid_source(current_source);
+                        ui.horizontal(|ui| {
+                            ui.label("Source URL: ");
+                            ui.text_edit_singleline(current_source);
+                            if !source_ok {
+                                ui.colored_label(egui::Color32::RED, "Not a URL");
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Tags: ");
+                            ui.add(
+                                egui::TextEdit::singleline(current_tags)
+                                    .hint_text("comma separated, eg. motivation, 1999"),
+                            );
+                        });
+
+                        let chosen_ts = get_chosen_types(&self.current_checked, &categories);
 
-                        let chosen_ts = get_chosen_types(self.current_checked.clone());
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Make these the default categories")
+                                .on_hover_text("They'll be checked whenever you start a new quote")
+                                .clicked()
+                            {
+                                self.settings.default_categories.clone_from(&chosen_ts);
+                            }
+                            if !self.settings.default_categories.is_empty()
+                                && ui.button("Clear defaults").clicked()
+                            {
+                                self.settings.default_categories.clear();
+                            }
+                        });
+                        if !self.settings.default_categories.is_empty() {
+                            ui.label(format!(
+                                "Defaults: {}",
+                                self.settings.default_categories.join(", ")
+                            ));
+                        }
+
+                        let mut submitted = false;
+                        ui.horizontal(|ui| {
+                            submitted = ui
+                                .add_enabled(source_ok, egui::Button::new("Submit!"))
+                                .clicked();
+                            if editing.is_some() && ui.button("Cancel Edit").clicked() {
+                                *editing = None;
+                                current_text.clear();
+                                current_author.clear();
+                                current_source.clear();
+                                current_tags.clear();
+                                *validation = None;
+                            }
+                        });
 
-                        if ui.button("Submit!").clicked() {
-                            let new_text = current_text.clone().trim().to_string();
-                            let new_quote = Quote(new_text, chosen_ts.clone());
+                        if let (true, Some(index)) = (submitted, editing_index) {
+                            let mut edited = Quote::builder(current_text.as_str())
+                                .categories(chosen_ts.clone())
+                                .author(current_author.as_str())
+                                .source(current_source.as_str())
+                                .tags(parse_tags(current_tags))
+                                .build();
+                            if self.settings.normalize_on_add {
+                                edited.0 = normalize_text(&edited.0);
+                            }
 
-                            add_quote_to_db(new_quote, Some(&mut self.current_db)).unwrap_or_else(
-                                |err| {
-                                    warn!("Unable to add quote: {err}");
-                                    vec![]
-                                },
-                            );
+                            match validate_edit(&edited, &self.current_db, index) {
+                                Ok(()) => {
+                                    self.category_index = None;
+                                    self.selected.clear();
+                                    update_quote(&mut self.current_db, index, |quote| {
+                                        quote.0 = edited.0;
+                                        quote.1 = edited.1;
+                                        quote.2.author = edited.2.author;
+                                        quote.2.source = edited.2.source;
+                                        quote.2.tags = edited.2.tags;
+                                    })
+                                    .map_or_else(
+                                        |err| warn!("Unable to update quote: {err}"),
+                                        |()| self.dirty = true,
+                                    );
+                                    push_toast(&mut self.toasts, Level::INFO, "Quote updated");
+                                    *editing = None;
+                                    current_text.clear();
+                                    current_author.clear();
+                                    current_source.clear();
+                                    current_tags.clear();
+                                    if self.settings.auto_sort {
+                                        self.dirty |=
+                                            sort_quotes(&mut self.current_db, self.sort_key);
+                                    } else {
+                                        self.is_unsorted = true;
+                                    }
+                                }
+                                Err(err) => *validation = Some(err),
+                            }
+                        } else if submitted {
+                            let new_quote = Quote::builder(current_text.as_str())
+                                .categories(chosen_ts.clone())
+                                .author(current_author.as_str())
+                                .source(current_source.as_str())
+                                .tags(parse_tags(current_tags))
+                                .build();
 
-                            current_text.clear();
-                            sort_list(Some(&mut self.current_db))
-                                .unwrap_or_else(|err| warn!("Unable to remove quote: {err}"));
+                            self.category_index = None;
+                            self.selected.clear();
+                            match add_quote_to_db(
+                                new_quote,
+                                Some(&mut self.current_db),
+                                self.settings.normalize_on_add,
+                                self.settings.punctuation,
+                            ) {
+                                Ok(_) => {
+                                    self.dirty = true;
+                                    quote_added = true;
+                                    self.flash = self.current_db.last().cloned().map(Flash::new);
+                                    push_toast(&mut self.toasts, Level::INFO, "Quote added");
+                                    current_text.clear();
+                                    current_source.clear();
+                                    current_tags.clear();
+                                    if !self.settings.default_categories.is_empty() {
+                                        self.current_checked = reverse_chosen_types(
+                                            self.settings.default_categories.clone(),
+                                            &categories,
+                                        );
+                                    }
+                                    if self.settings.auto_sort {
+                                        self.dirty |=
+                                            sort_quotes(&mut self.current_db, self.sort_key);
+                                    } else {
+                                        self.is_unsorted = true;
+                                    }
+                                }
+                                Err(Error::Validation(err)) => *validation = Some(err),
+                                Err(err) => push_toast(
+                                    &mut self.toasts,
+                                    Level::ERROR,
+                                    format!("Unable to add quote: {err}"),
+                                ),
+                            }
                         }
 
                         if !chosen_ts.is_empty() {
@@ -216,27 +3164,25 @@ impl eframe::App for EnglishQuotesApp {
                             let db = self.current_db.clone();
                             let db_len = db.len();
 
-                            //TODO: Make this into a function
-                            let mut chosen_len = 0;
                             let chosen_quotes: Vec<_> = db
                                 .into_iter()
-                                .filter(|quote| {
-                                    let mut works = true;
-                                    for t in &chosen_ts {
-                                        if !quote.1.contains(t) {
-                                            works = false;
-                                            break;
-                                        }
-                                    }
-                                    if works {
-                                        chosen_len += 1;
-                                    }
-                                    works
-                                })
+                                .filter(|quote| quote.matches_all(&chosen_ts))
                                 .collect();
+                            let chosen_len = chosen_quotes.len();
 
-                            ui.heading(format!("Existing Quotes ({chosen_len}/{db_len}): "));
+                            ui.heading("Existing Quotes");
+                            counts_header(ui, chosen_len, db_len, "");
 
+                            if chosen_len == 0 {
+                                render_empty_state(
+                                    ui,
+                                    if db_len == 0 {
+                                        EmptyState::EmptyDb
+                                    } else {
+                                        EmptyState::NoCategoryMatches
+                                    },
+                                );
+                            }
                             for quote in chosen_quotes {
                                 ui.label(format!(" - {:?} | {}", quote.1, quote.0));
                             }
@@ -247,70 +3193,323 @@ impl eframe::App for EnglishQuotesApp {
             CurrentAppState::Search {
                 current_search_term,
                 is_inverted,
+                by_relevance,
             } => {
                 let mut scroll = None;
                 ui.heading("Search");
 
                 ui.horizontal(|ui| {
                     ui.label("Search Input: ");
-                    if ui.text_edit_singleline(current_search_term).changed() {
+                    let response = ui
+                        .text_edit_singleline(current_search_term)
+                        .on_hover_text("cat:NAME only searches that category");
+                    if std::mem::take(&mut self.focus_search) {
+                        response.request_focus();
+                    }
+                    if response.changed() {
                         scroll = Some(());
                     }
                     ui.checkbox(is_inverted, "Invert");
+                    ui.add_enabled(
+                        !*is_inverted,
+                        egui::Checkbox::new(by_relevance, "Sort by relevance"),
+                    );
                 });
+                self.filters
+                    .show_bar(ui, None, &categories, Some(current_search_term));
+                self.filters.quick.show_chips(ui);
+                self.filters.length.show(ui, longest);
 
-                let (search_results, total_no, search_no) = {
-                    let full_list_clone = self.current_db.clone();
-                    let total_no = full_list_clone.len();
-
-                    let search_results = full_list_clone.into_iter().filter(|qu| {
-                        let r = qu.0.contains(current_search_term.as_str());
-                        if *is_inverted {
-                            !r
-                        } else {
-                            r
-                        }
-                    });
-                    let search_no = search_results.clone().count();
+                let query = SearchQuery::parse(current_search_term, &categories);
+                for term in query.categories.iter().filter(|term| !term.exact) {
+                    if term.resolved.is_empty() {
+                        ui.weak(format!("No category like \"{}\"", term.typed));
+                    } else {
+                        ui.weak(format!(
+                            "cat:{} matched {}",
+                            term.typed,
+                            term.resolved.join(", ")
+                        ));
+                    }
+                }
 
-                    (search_results, total_no, search_no)
+                let total_no = self.current_db.len();
+                let search_results: Vec<usize> = if *by_relevance && !*is_inverted {
+                    rank_substring_matches(&query.text, &self.current_db)
+                        .into_iter()
+                        .filter(|&i| query.matches_categories(&self.current_db[i]))
+                        .collect()
+                } else {
+                    (0..total_no)
+                        .filter(|&i| {
+                            let quote = &self.current_db[i];
+                            let r = quote_matches(&query.text, quote).any()
+                                && query.matches_categories(quote);
+                            if *is_inverted {
+                                !r
+                            } else {
+                                r
+                            }
+                        })
+                        .collect()
                 };
+                let search_results: Vec<usize> = search_results
+                    .into_iter()
+                    .filter(|&i| self.filters.matches(&self.current_db[i]))
+                    .collect();
+                let search_no = search_results.len();
+                self.visible = Some(search_results.clone());
 
                 ui.separator();
 
-                egui::ScrollArea::vertical()
-                    .max_height(f32::INFINITY)
-                    .show(ui, |ui| {
-                        let r = ui.separator().rect;
-                        ui.heading(format!("Search Results: {search_no}/{total_no}"));
-                        display_quotes_list(
-                            search_results,
+                let mut scroll_area = egui::ScrollArea::vertical().max_height(f32::INFINITY);
+                if let Some(offset) = saved_offset {
+                    scroll_area = scroll_area.vertical_scroll_offset(offset);
+                }
+
+                let output = scroll_area.show(ui, |ui| {
+                    let r = ui.separator().rect;
+                    ui.heading("Search Results");
+                    counts_header(ui, search_no, total_no, &self.filters.length.describe());
+                    if search_no > 0 && ui.button("Delete all shown").clicked() {
+                        self.pending_bulk_delete = Some(search_results.clone());
+                    }
+                    match selection_toolbar(
+                        ui,
+                        &mut self.select_mode,
+                        &mut self.selected,
+                        &search_results,
+                    ) {
+                        Some(SelectionAction::Delete) => {
+                            self.pending_bulk_delete =
+                                Some(self.selected.iter().copied().collect());
+                        }
+                        Some(SelectionAction::Copy) => copy_quotes(
+                            ui,
+                            &mut self.toasts,
+                            &self.current_db,
+                            self.selected.iter().copied(),
+                            &template,
+                        ),
+                        Some(SelectionAction::Export) => {
+                            self.export_selected = Some(self.selected.iter().copied().collect());
+                            self.show_export_dialog = true;
+                        }
+                        Some(SelectionAction::Merge) => {
+                            (self.merge_from, self.quote_settings) =
+                                Self::merge_pair(&self.current_db, &self.selected);
+                        }
+                        Some(SelectionAction::Tag) => {
+                            self.pending_bulk_tag =
+                                Some((self.selected.iter().copied().collect(), String::new()));
+                        }
+                        None => {}
+                    }
+                    if search_no == 0 {
+                        render_empty_state(
                             ui,
-                            Some(|quote| self.quote_settings = Some(quote)),
+                            if total_no == 0 {
+                                EmptyState::EmptyDb
+                            } else if self.filters.narrows() {
+                                EmptyState::FilteredOut
+                            } else {
+                                EmptyState::NoResults(current_search_term)
+                            },
                         );
+                    }
+                    //inverted results didn't match anything, so there's nothing to explain
+                    let inverted = *is_inverted;
+                    let rows = display_quotes_list(
+                        search_results
+                            .iter()
+                            .map(|&i| (i, self.current_db[i].clone())),
+                        ui,
+                        self.settings.density,
+                        self.settings.quote_font_size,
+                        &template,
+                        |quote| {
+                            if inverted {
+                                None
+                            } else {
+                                quote_matches(&query.text, quote).note()
+                            }
+                        },
+                        Some(|quote| self.quote_settings = Some(quote)),
+                        Some(|category, add| chip_clicked = Some((category, add))),
+                        self.select_mode.then_some(&mut self.selected),
+                    );
+                    show_flash(ui, &mut self.flash, &self.current_db, &rows);
 
-                        if std::mem::take(&mut scroll).is_some() {
-                            ui.scroll_to_rect(r, None);
-                            //TODO: need to have a better solution than a separator
-                        }
-                    });
+                    if std::mem::take(&mut scroll).is_some() {
+                        ui.scroll_to_rect(r, None);
+                        //TODO: need to have a better solution than a separator
+                    }
+                });
+                self.scroll_offsets.insert(view, output.state.offset.y);
+            }
+            CurrentAppState::Trash => {
+                self.visible = None;
+                self.show_trash(ui);
             }
         });
+        if let Some((category, add)) = chip_clicked {
+            self.filter_by_category(&category, add);
+        }
+        if quote_added {
+            self.enforce_category_caps();
+        }
+
+        self.show_toasts(ctx);
     }
 
-    fn on_exit(&mut self, _gl: &Context) {
-        sort_list(Some(&mut self.current_db))
-            .unwrap_or_else(|err| warn!("Unable to remove quote: {err}"));
+    fn on_exit_event(&mut self) -> bool {
+        if self.exit_handled {
+            return true;
+        }
+        //the user gets to pick what happens to unsaved changes - see show_quit_prompt
+        if self.has_unsaved_changes() {
+            self.quit_prompt = true;
+            return false;
+        }
 
-        match &serde_json::to_vec(&self.current_db) {
-            Ok(v) => {
-                std::fs::write(FileType::Database.get_location(), v).unwrap_or_else(|err| {
-                    warn!("Unable to save db.json: {err}");
-                });
+        match self.save_db() {
+            Ok(()) => {
+                self.exit_handled = true;
+                true
             }
-            Err(e) => {
-                warn!("Unable to serialise database: {e:?}");
+            Err(err) => {
+                self.exit_save_failed(&err);
+                false
             }
         }
     }
+
+    fn on_exit(&mut self, _gl: &Context) {
+        if self.in_memory {
+            info!("Discarding changes to the in-memory database");
+            return;
+        }
+        self.settings.last_view = self.current_state.view();
+        save_settings(&self.settings).unwrap_or_else(|err| warn!("Unable to save settings: {err}"));
+
+        //on_exit_event isn't called when the window is forcefully closed, so we might still need to save
+        if self.exit_handled {
+            return;
+        }
+
+        if let Err(err) = self.save_db() {
+            warn!("Unable to save db.json: {err}");
+            self.write_recovery();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotes(texts: &[&str]) -> Vec<Quote> {
+        texts
+            .iter()
+            .map(|text| Quote::new(*text, vec!["Stella".to_string()]))
+            .collect()
+    }
+
+    #[test]
+    fn starts_clean() {
+        let app = EnglishQuotesApp::in_memory(quotes(&["a", "b"]));
+        assert!(!app.is_dirty());
+    }
+
+    #[test]
+    fn deleting_marks_the_db_dirty() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["a", "b"]));
+        app.delete_quote(&Quote::new("a", vec!["Stella".to_string()]))
+            .unwrap();
+        assert!(app.is_dirty());
+        assert_eq!(app.current_db.len(), 1);
+    }
+
+    #[test]
+    fn only_sorting_that_moves_something_is_a_change() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["a", "b"]));
+        app.set_sort_key(SortKey::Text);
+        assert!(!app.is_dirty());
+
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["b", "a"]));
+        app.set_sort_key(SortKey::Text);
+        assert!(app.is_dirty());
+    }
+
+    #[test]
+    fn deleting_nothing_is_not_a_change() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["a"]));
+        assert_eq!(app.delete_quotes(&[]), 0);
+        assert!(!app.is_dirty());
+    }
+
+    #[test]
+    fn exports_the_unsaved_quotes_in_memory() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["Gone", "Kept"]));
+        app.delete_quote(&Quote::new("Gone", vec!["Stella".to_string()]))
+            .unwrap();
+        assert_eq!(app.export_list().unwrap(), quotes(&["Kept"]));
+
+        let path =
+            std::env::temp_dir().join(format!("english_quotes_export_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        app.export_format = ExportFormat::PlainText(Separator::Newline);
+        app.run_export(path).unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(written.contains("Kept"));
+        assert!(!written.contains("Gone"));
+    }
+
+    #[test]
+    fn exporting_only_whats_shown() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["a", "b", "c"]));
+        app.visible = Some(vec![2, 0]);
+        assert_eq!(app.export_list().unwrap().len(), 3);
+
+        app.export_shown_only = true;
+        assert_eq!(app.export_list().unwrap(), quotes(&["c", "a"]));
+    }
+
+    #[test]
+    fn merging_two_selected_quotes() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["a", "b", "c"]));
+        app.selected = BTreeSet::from([0]);
+        assert_eq!(
+            EnglishQuotesApp::merge_pair(&app.current_db, &app.selected),
+            (None, None)
+        );
+
+        app.selected = BTreeSet::from([0, 2]);
+        let (Some(from), Some(into)) = EnglishQuotesApp::merge_pair(&app.current_db, &app.selected)
+        else {
+            panic!("two quotes were selected");
+        };
+        assert_eq!((from.0.as_str(), into.0.as_str()), ("a", "c"));
+
+        //what the merge dialog does once a text is picked
+        let merged = app.merge(&into, &from).unwrap();
+        assert_eq!(merged.0, "c");
+        assert_eq!(app.current_db, quotes(&["b", "c"]));
+        assert!(app.selected.is_empty());
+        assert!(app.is_dirty());
+    }
+
+    #[test]
+    fn exporting_only_whats_selected() {
+        let mut app = EnglishQuotesApp::in_memory(quotes(&["a", "b", "c"]));
+        app.visible = Some(vec![2, 0]);
+        app.export_shown_only = true;
+        app.export_selected = Some(vec![1]);
+        assert_eq!(app.export_list().unwrap(), quotes(&["b"]));
+
+        //opening the dialog from the menu goes back to the usual options
+        app.open_export_dialog();
+        assert_eq!(app.export_list().unwrap(), quotes(&["c", "a"]));
+    }
 }
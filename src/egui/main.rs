@@ -4,12 +4,15 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery)]
 
 mod eq_app;
+mod palette;
+mod settings;
 mod utility;
 
 #[macro_use]
 extern crate tracing;
 
 use crate::eq_app::EnglishQuotesApp;
+use english_quotes::db::read_db;
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 
@@ -19,10 +22,22 @@ fn main() {
         .finish();
     tracing::subscriber::set_global_default(sub).expect("Unable to set tracing sub");
 
+    //`--demo` starts with a copy of the database, and throws away any changes on exit
+    let demo = std::env::args().skip(1).any(|arg| arg == "--demo");
+
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "English Quotes",
         options,
-        Box::new(|_cc| Box::new(EnglishQuotesApp::default())),
+        Box::new(move |_cc| {
+            Box::new(if demo {
+                EnglishQuotesApp::in_memory(read_db().unwrap_or_else(|err| {
+                    warn!("Unable to read the database for the demo, starting empty: {err}");
+                    vec![]
+                }))
+            } else {
+                EnglishQuotesApp::default()
+            })
+        }),
     );
 }
@@ -0,0 +1,118 @@
+//!The Ctrl+K command palette, for getting at actions without hunting through the side panel
+
+use crate::eq_app::EnglishQuotesApp;
+use egui::{Align, Align2, Key, TextEdit, Vec2};
+
+///Something the palette can run
+pub struct Command {
+    pub name: String,
+    pub run: Box<dyn FnMut(&mut EnglishQuotesApp)>,
+}
+
+impl Command {
+    pub fn new(name: impl Into<String>, run: impl FnMut(&mut EnglishQuotesApp) + 'static) -> Self {
+        Self {
+            name: name.into(),
+            run: Box::new(run),
+        }
+    }
+}
+
+///Whether every word of `query` turns up somewhere in `name`, ignoring case
+fn matches(name: &str, query: &str) -> bool {
+    let name = name.to_lowercase();
+    query
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| name.contains(word))
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    ///Index into the commands that match `query`
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub const fn is_open(&self) -> bool {
+        self.open
+    }
+
+    ///Draws the palette if it's open, returning the index into `commands` of the one picked.
+    ///
+    ///Up and down move the selection, Enter picks it and Escape closes the palette.
+    pub fn show(&mut self, ctx: &egui::Context, commands: &[Command]) -> Option<usize> {
+        if !self.open {
+            return None;
+        }
+
+        let shown: Vec<usize> = (0..commands.len())
+            .filter(|&i| matches(&commands[i].name, &self.query))
+            .collect();
+        let (up, down, enter, escape) = {
+            let input = ctx.input();
+            (
+                input.key_pressed(Key::ArrowUp),
+                input.key_pressed(Key::ArrowDown),
+                input.key_pressed(Key::Enter),
+                input.key_pressed(Key::Escape),
+            )
+        };
+        if down && self.selected + 1 < shown.len() {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(shown.len().saturating_sub(1));
+
+        let mut picked = enter.then(|| shown.get(self.selected).copied()).flatten();
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, Vec2::new(0.0, 60.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command")
+                        .desired_width(300.0),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (position, &i) in shown.iter().enumerate() {
+                            let label =
+                                ui.selectable_label(position == self.selected, &commands[i].name);
+                            if position == self.selected && (up || down) {
+                                label.scroll_to_me(Some(Align::Center));
+                            }
+                            if label.clicked() {
+                                picked = Some(i);
+                            }
+                        }
+                        if shown.is_empty() {
+                            ui.weak("No matching commands");
+                        }
+                    });
+            });
+
+        if picked.is_some() || escape {
+            self.open = false;
+        }
+        picked
+    }
+}
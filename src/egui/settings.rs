@@ -0,0 +1,227 @@
+use english_quotes::{
+    db::write_atomically,
+    quote::{FileType, PunctuationRules},
+    utils::{exports::ExportExclusions, template::DEFAULT_TEMPLATE, Error},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+///egui's own body text size
+pub const DEFAULT_QUOTE_FONT_SIZE: f32 = 14.0;
+///How similar two quotes have to be for "Find similar quotes" to show them, until changed in its window
+pub const NEAR_DUPLICATE_THRESHOLD: f32 = 0.8;
+///Bumped whenever a setting changes meaning, so [`migrate`] can bring older files up to date
+pub const SETTINGS_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct AppSettings {
+    ///The [`SETTINGS_VERSION`] the file was written with - files from before it was added count as 0
+    #[serde(default)]
+    pub version: u32,
+    pub density: ListDensity,
+    pub last_view: View,
+    ///Categories added from the app, on top of the ones in types.txt
+    pub custom_categories: Vec<String>,
+    ///Whether to re-sort the list straight after adding quotes - when off, new quotes stay at the bottom until sorted manually
+    pub auto_sort: bool,
+    ///Straighten smart quotes and tidy whitespace in new quotes
+    pub normalize_on_add: bool,
+    ///Capitalise and full-stop new quotes - both off by default
+    pub punctuation: PunctuationRules,
+    ///Categories checked when opening the entry form, and again after each submit
+    pub default_categories: Vec<String>,
+    ///Underline likely typos in new quotes - only does anything with the `spellcheck` feature
+    pub spellcheck: bool,
+    ///Width the menu panel was last dragged to - `None` leaves it at egui's default
+    pub side_panel_width: Option<f32>,
+    pub side_panel_collapsed: bool,
+    ///Check every quote when loading the database, and point out any with problems
+    pub strict_load: bool,
+    ///How quotes are laid out in the list and when copied - see [`QuoteTemplate`](english_quotes::utils::template::QuoteTemplate)
+    pub quote_template: String,
+    ///Lay the category checkboxes out in a grid rather than one long column
+    pub category_grid: bool,
+    ///Show the clicked quote in a pane on the right, rather than a popup window
+    pub preview_pane: bool,
+    ///Size of the quote text in lists, in points - separate from the rest of the UI so quotes can be big without the buttons being huge
+    pub quote_font_size: f32,
+    ///Scales everything, on top of the screen's own scaling
+    pub ui_scale: f32,
+    pub dark_mode: bool,
+    ///Left out of every export, including the "Back up everything" ones
+    pub export_exclusions: ExportExclusions,
+    ///The last threshold used to find similar quotes, from 0.0 to 1.0 - see [`similarity`](english_quotes::db::similarity)
+    pub similarity_threshold: f32,
+    ///Deleted quotes go in the trash, rather than being gone for good
+    pub soft_delete: bool,
+    ///How many days quotes are kept in the trash before they're purged on startup
+    pub trash_days: u64,
+    pub startup_quote: StartupQuote,
+    ///The day (see [`today`](english_quotes::db::today)) the startup quote was last hidden until tomorrow
+    pub startup_quote_hidden_on: Option<u64>,
+    ///Categories that only keep their newest quotes, by how many - see [`enforce_category_cap`](english_quotes::db::enforce_category_cap)
+    pub category_caps: BTreeMap<String, usize>,
+    ///How many of the last random quotes "Another" won't show again - see [`next_random`](english_quotes::db::next_random)
+    pub no_repeat_window: usize,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            density: ListDensity::default(),
+            last_view: View::default(),
+            custom_categories: vec![],
+            auto_sort: true,
+            normalize_on_add: false,
+            punctuation: PunctuationRules::default(),
+            default_categories: vec![],
+            spellcheck: true,
+            side_panel_width: None,
+            side_panel_collapsed: false,
+            strict_load: false,
+            quote_template: DEFAULT_TEMPLATE.to_string(),
+            category_grid: false,
+            preview_pane: false,
+            quote_font_size: DEFAULT_QUOTE_FONT_SIZE,
+            ui_scale: 1.0,
+            dark_mode: true,
+            export_exclusions: ExportExclusions::default(),
+            similarity_threshold: NEAR_DUPLICATE_THRESHOLD,
+            soft_delete: true,
+            trash_days: 30,
+            startup_quote: StartupQuote::default(),
+            startup_quote_hidden_on: None,
+            category_caps: BTreeMap::new(),
+            no_repeat_window: 5,
+        }
+    }
+}
+
+///Which tab the app is on, without any of the in-progress text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum View {
+    #[default]
+    QuoteCategories,
+    QuoteEntry,
+    Search,
+    Trash,
+}
+
+///A quote to greet the user with when the app opens, drawn from the favourites if there are any
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StartupQuote {
+    #[default]
+    Off,
+    Random,
+    ///The same quote all day
+    OfTheDay,
+}
+
+impl StartupQuote {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Random, Self::OfTheDay];
+}
+
+impl Display for StartupQuote {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "Nothing"),
+            Self::Random => write!(f, "A random quote"),
+            Self::OfTheDay => write!(f, "The quote of the day"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ListDensity {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+///Brings settings saved by an older version of the app up to [`SETTINGS_VERSION`]
+fn migrate(mut settings: AppSettings) -> AppSettings {
+    if settings.version > SETTINGS_VERSION {
+        warn!(
+            "settings.json is from a newer version ({}), so anything new in it will be lost on save",
+            settings.version
+        );
+    }
+    //version 0 only lacked the version number
+    settings.version = SETTINGS_VERSION;
+    settings
+}
+
+///Parses the contents of `settings.json`, migrating it to [`SETTINGS_VERSION`] - any settings it lacks (or doesn't know) take their defaults
+pub fn parse_settings(contents: &str) -> Result<AppSettings, serde_json::Error> {
+    serde_json::from_str(contents).map(migrate)
+}
+
+///Missing settings just fall back to the defaults.
+///
+///Unparseable ones do too, but are copied to `settings.json.bak` first so they aren't lost when the defaults are saved over them.
+pub fn load_settings() -> AppSettings {
+    let location = FileType::Settings.get_location();
+    let Ok(contents) = std::fs::read_to_string(location) else {
+        return AppSettings::default();
+    };
+    match parse_settings(&contents) {
+        Ok(settings) => settings,
+        Err(err) => {
+            warn!("Unable to parse settings, using defaults: {err}");
+            if let Err(err) = std::fs::write(format!("{location}.bak"), contents) {
+                warn!("Unable to back up the unparseable settings: {err}");
+            }
+            AppSettings::default()
+        }
+    }
+}
+
+pub fn save_settings(settings: &AppSettings) -> Result<(), Error> {
+    write_atomically(
+        FileType::Settings.get_location(),
+        &serde_json::to_vec_pretty(settings)?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_without_a_version_are_migrated() {
+        let settings = parse_settings(r#"{"dark_mode": false}"#).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!(!settings.dark_mode);
+    }
+
+    #[test]
+    fn missing_and_unknown_settings_take_their_defaults() {
+        let settings =
+            parse_settings(r#"{"version": 1, "trash_days": 7, "no_longer_a_setting": true}"#)
+                .unwrap();
+        let defaults = AppSettings::default();
+        assert_eq!(settings.trash_days, 7);
+        assert_eq!(settings.auto_sort, defaults.auto_sort);
+        assert_eq!(settings.quote_template, defaults.quote_template);
+        assert_eq!(settings.no_repeat_window, defaults.no_repeat_window);
+
+        let empty = parse_settings("{}").unwrap();
+        assert_eq!(empty.version, SETTINGS_VERSION);
+        assert_eq!(empty.trash_days, defaults.trash_days);
+    }
+
+    #[test]
+    fn garbage_falls_back_to_the_defaults() {
+        assert!(parse_settings("not json at all").is_err());
+        assert!(parse_settings(r#"{"trash_days": "a week"}"#).is_err());
+        let settings = parse_settings("{").unwrap_or_default();
+        assert_eq!(settings.trash_days, AppSettings::default().trash_days);
+    }
+}
@@ -1,52 +1,1095 @@
-use egui::Ui;
-use english_quotes::quote::{Quote, ALL_PERMS};
+use crate::settings::ListDensity;
+use egui::{
+    color::{linear_f32_from_gamma_u8, Hsva},
+    Align, Button, Color32, Id, Label, Rect, RichText, Sense, Ui, Visuals,
+};
+use english_quotes::{
+    quote::{
+        category_key, format_quote, now_timestamp, truncate_graphemes, Attribution, Quote,
+        ALL_PERMS, MAX_RATING,
+    },
+    utils::{
+        diff::DiffSpan,
+        filter::QuoteSelectionFilter,
+        template::{Placeholder, QuoteTemplate},
+    },
+};
+use std::{
+    collections::{BTreeSet, HashSet},
+    time::{Duration, Instant},
+};
 
-pub fn vertical_category_checkbox(ui: &mut Ui, cc: &mut [bool]) {
+const COMPACT_LENGTH: usize = 80;
+///How much smaller compact text is, going by egui's small (10pt) and body (14pt) styles
+const COMPACT_SCALE: f32 = 10.0 / 14.0;
+//tags get their own colours so they can't be mistaken for categories
+const TAG_TEXT: Color32 = Color32::from_rgb(230, 230, 255);
+const TAG_BACKGROUND: Color32 = Color32::from_rgb(70, 60, 140);
+///The WCAG minimum for things that aren't body text - chips have to stand out this much from both the light and dark themes
+const MIN_CHIP_CONTRAST: f32 = 3.0;
+///How long a just-added quote stays highlighted once it's been scrolled to
+const FLASH_DURATION: Duration = Duration::from_secs(1);
+const FLASH_COLOUR: Color32 = Color32::from_rgba_premultiplied(120, 100, 0, 120);
+
+///All of the categories from types.txt, followed by any custom ones
+pub fn all_categories(custom: &[String]) -> Vec<String> {
+    let mut categories = ALL_PERMS.clone();
+    for cat in custom {
+        if !categories.contains(cat) {
+            categories.push(cat.clone());
+        }
+    }
+    categories
+}
+
+///How tall the category checkboxes can get before they scroll
+const CATEGORY_PANEL_HEIGHT: f32 = 250.0;
+///Roughly how wide each column of the category grid is
+const CATEGORY_COLUMN_WIDTH: f32 = 140.0;
+const MAX_CATEGORY_COLUMNS: usize = 3;
+const CATEGORY_PREVIEW_SAMPLES: usize = 3;
+const CATEGORY_PREVIEW_LENGTH: usize = 60;
+
+///Only the categories containing `filter` are shown, but ones that are checked and hidden still count.
+///
+///With `grid` on, the checkboxes are spread over as many columns as fit, up to [`MAX_CATEGORY_COLUMNS`].
+///
+///Hovering over a checkbox shows how many quotes from `db` are in that category, and a few of them.
+pub fn vertical_category_checkbox(
+    ui: &mut Ui,
+    cc: &mut HashSet<String>,
+    categories: &[String],
+    db: &[Quote],
+    filter: &mut String,
+    grid: bool,
+) {
     ui.vertical(|ui| {
-        for (i, cat) in ALL_PERMS.clone().into_iter().enumerate() {
-            ui.checkbox(cc.get_mut(i).unwrap(), cat);
+        ui.add(egui::TextEdit::singleline(filter).hint_text("Filter categories"));
+        let needle = filter.trim().to_lowercase();
+
+        let (shown, hidden): (Vec<usize>, Vec<usize>) =
+            (0..categories.len()).partition(|&i| categories[i].to_lowercase().contains(&needle));
+        let hidden_checked = hidden
+            .into_iter()
+            .filter(|&i| cc.contains(&categories[i]))
+            .count();
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let columns = if grid {
+            ((ui.available_width() / CATEGORY_COLUMN_WIDTH) as usize).clamp(1, MAX_CATEGORY_COLUMNS)
+        } else {
+            1
+        };
+
+        egui::ScrollArea::vertical()
+            .id_source("category_checkboxes")
+            .max_height(CATEGORY_PANEL_HEIGHT)
+            .show(ui, |ui| {
+                egui::Grid::new("category_checkbox_grid")
+                    .num_columns(columns)
+                    .show(ui, |ui| {
+                        for row in shown.chunks(columns) {
+                            for &i in row {
+                                let mut checked = cc.contains(&categories[i]);
+                                if ui
+                                    .checkbox(&mut checked, &categories[i])
+                                    .on_hover_ui(|ui| category_preview(ui, &categories[i], db))
+                                    .changed()
+                                {
+                                    if checked {
+                                        cc.insert(categories[i].clone());
+                                    } else {
+                                        cc.remove(&categories[i]);
+                                    }
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if hidden_checked > 0 {
+            ui.weak(format!("+{hidden_checked} selected but hidden"));
         }
     });
 }
 
-pub fn get_chosen_types(cc: Vec<bool>) -> Vec<String> {
-    cc.into_iter()
-        .enumerate()
-        .filter_map(|(i, b)| if b { Some(i) } else { None })
-        .map(|i| ALL_PERMS[i].clone())
+///How many quotes are in `category`, and the first few of them
+fn category_preview(ui: &mut Ui, category: &str, db: &[Quote]) {
+    let mut quotes = db.iter().filter(|quote| quote.matches_category(category));
+    let samples: Vec<&Quote> = quotes.by_ref().take(CATEGORY_PREVIEW_SAMPLES).collect();
+    let count = samples.len() + quotes.count();
+    ui.strong(format!(
+        "{count} quote{}",
+        if count == 1 { "" } else { "s" }
+    ));
+    for quote in samples {
+        ui.label(truncate_graphemes(&quote.0, CATEGORY_PREVIEW_LENGTH));
+    }
+}
+
+///The checked categories, in the order they're listed in `categories`
+pub fn get_chosen_types(cc: &HashSet<String>, categories: &[String]) -> Vec<String> {
+    categories
+        .iter()
+        .filter(|cat| cc.contains(*cat))
+        .cloned()
         .collect()
 }
 
-pub fn reverse_chosen_types(cats: Vec<String>) -> Vec<bool> {
-    let mut res = vec![false; ALL_PERMS.len()];
+///Checks `cats`, leaving out any that aren't in `categories`
+pub fn reverse_chosen_types(cats: Vec<String>, categories: &[String]) -> HashSet<String> {
     cats.into_iter()
-        .filter_map(|cat| ALL_PERMS.iter().position(|perm| &cat == perm))
-        .for_each(|index| res[index] = true);
-    res
+        .filter(|cat| categories.contains(cat))
+        .collect()
+}
+
+///One quote's row, which with [`ListDensity::Compact`] gets cut short with a "show more" button
+fn quote_button(
+    ui: &mut Ui,
+    label: String,
+    txt: &str,
+    density: ListDensity,
+    font_size: f32,
+) -> egui::Response {
+    match density {
+        ListDensity::Compact => {
+            let short = truncate_graphemes(&label, COMPACT_LENGTH);
+            if short == label {
+                ui.add(Button::new(RichText::new(label).size(font_size * COMPACT_SCALE)).small())
+            } else {
+                let id = Id::new(("show_more", txt));
+                let expanded = ui.data().get_temp::<bool>(id).unwrap_or_default();
+
+                let response = if expanded {
+                    ui.add(
+                        Button::new(RichText::new(label).size(font_size * COMPACT_SCALE))
+                            .small()
+                            .wrap(true),
+                    )
+                } else {
+                    ui.add(
+                        Button::new(RichText::new(short).size(font_size * COMPACT_SCALE)).small(),
+                    )
+                    .on_hover_text(label)
+                };
+
+                if ui
+                    .small_button(if expanded { "show less" } else { "show more" })
+                    .clicked()
+                {
+                    ui.data().insert_temp(id, !expanded);
+                }
+                response
+            }
+        }
+        ListDensity::Comfortable => {
+            ui.add(Button::new(RichText::new(label).size(font_size)).wrap(true))
+        }
+    }
+}
+
+fn toggle_selected(selection: &mut BTreeSet<usize>, index: usize) {
+    if !selection.remove(&index) {
+        selection.insert(index);
+    }
 }
 
+///`font_size` is for comfortable lists - compact ones scale it down like egui's small text.
+///
+///`note` can add a short bit of text to the end of a quote's row, eg. why it showed up in a search.
+///
+///`v` has each quote's index in the database alongside it. With a `selection`, each row gets a checkbox, and clicking a quote selects it rather than calling `on_click`
+///
+///With `on_category`, category chips can be clicked - it's given the category and whether shift was held.
+#[allow(clippy::too_many_arguments)]
 pub fn display_quotes_list(
-    v: impl Iterator<Item = Quote>,
+    v: impl Iterator<Item = (usize, Quote)>,
     ui: &mut Ui,
+    density: ListDensity,
+    font_size: f32,
+    template: &QuoteTemplate,
+    note: impl Fn(&Quote) -> Option<String>,
     mut on_click: Option<impl FnMut(Quote)>,
-) {
-    for quote in v {
-        let Quote(txt, cats) = quote.clone();
-        if ui.small_button(format!("{cats:?} | {txt}")).clicked() {
-            if let Some(on_click) = &mut on_click {
+    mut on_category: Option<impl FnMut(String, bool)>,
+    mut selection: Option<&mut BTreeSet<usize>>,
+) -> Vec<(usize, Rect)> {
+    let mut rows = vec![];
+    for (index, quote) in v {
+        let Quote(txt, cats, meta) = quote.clone();
+        let source = meta.source;
+        let tags = meta.tags;
+        let mut label = format_quote(&quote, template);
+        let show_chips = !template.contains(Placeholder::Categories);
+        if let (Some(attribution), false) = (
+            meta.attribution,
+            template.contains(Placeholder::Attribution),
+        ) {
+            label = format!("{} {label}", attribution.icon());
+        }
+        if let (Some(rating), false) = (meta.rating, template.contains(Placeholder::Rating)) {
+            label = format!("{label} {}", stars(rating));
+        }
+        if let Some(modified) = meta
+            .modified
+            .filter(|&modified| Some(modified) != meta.added)
+        {
+            label = format!(
+                "{label} (edited {})",
+                describe_age(now_timestamp().saturating_sub(modified))
+            );
+        }
+        if let Some(note) = note(&quote) {
+            label = format!("{label} {note}");
+        }
+
+        let response = if let Some(selection) = &mut selection {
+            ui.horizontal(|ui| {
+                let mut checked = selection.contains(&index);
+                if ui.checkbox(&mut checked, "").changed() {
+                    toggle_selected(selection, index);
+                }
+                quote_button(ui, label, &txt, density, font_size)
+            })
+            .inner
+        } else {
+            quote_button(ui, label, &txt, density, font_size)
+        };
+
+        if (show_chips && !cats.is_empty()) || !tags.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for cat in cats.iter().filter(|_| show_chips) {
+                    let background = category_color(cat);
+                    let chip = RichText::new(cat)
+                        .small()
+                        .color(category_text_color(background))
+                        .background_color(background);
+                    if let Some(on_category) = &mut on_category {
+                        let response = ui
+                            .add(Label::new(chip).sense(Sense::click()))
+                            .on_hover_text(
+                                "Show quotes in this category, or shift-click to add it",
+                            );
+                        if response.clicked() {
+                            on_category(cat.clone(), ui.input().modifiers.shift);
+                        }
+                    } else {
+                        ui.label(chip);
+                    }
+                }
+                for tag in &tags {
+                    ui.label(
+                        RichText::new(format!("#{tag}"))
+                            .small()
+                            .color(TAG_TEXT)
+                            .background_color(TAG_BACKGROUND),
+                    );
+                }
+            });
+        }
+        if let Some(source) = source {
+            ui.hyperlink_to(RichText::new("🔗 source").small(), &source)
+                .on_hover_text(&source);
+        }
+        if density == ListDensity::Comfortable {
+            ui.add_space(4.0);
+        }
+
+        rows.push((index, response.rect));
+        if response.clicked() {
+            if let Some(selection) = &mut selection {
+                toggle_selected(selection, index);
+            } else if let Some(on_click) = &mut on_click {
                 on_click(quote);
             }
         }
     }
+    rows
+}
+
+///A just-added quote, to be scrolled to and highlighted the next time it's listed
+pub struct Flash {
+    quote: Quote,
+    started: Option<Instant>,
+}
+
+impl Flash {
+    pub const fn new(quote: Quote) -> Self {
+        Self {
+            quote,
+            started: None,
+        }
+    }
+}
+
+///Scrolls to the flashing quote if it's one of the `rows` from [`display_quotes_list`], and fades its highlight out over [`FLASH_DURATION`]
+pub fn show_flash(ui: &Ui, flash: &mut Option<Flash>, db: &[Quote], rows: &[(usize, Rect)]) {
+    let Some(current) = flash else {
+        return;
+    };
+    let Some(&(_, rect)) = rows
+        .iter()
+        .find(|&&(index, _)| db.get(index) == Some(&current.quote))
+    else {
+        //it isn't in this list, so it shouldn't flash out of nowhere later on either
+        *flash = None;
+        return;
+    };
+
+    let started = *current.started.get_or_insert_with(|| {
+        ui.scroll_to_rect(rect, Some(Align::Center));
+        Instant::now()
+    });
+    let progress = started.elapsed().as_secs_f32() / FLASH_DURATION.as_secs_f32();
+    if progress >= 1.0 {
+        *flash = None;
+        return;
+    }
+    ui.painter().rect_filled(
+        rect.expand(2.0),
+        4.0,
+        FLASH_COLOUR.linear_multiply(1.0 - progress),
+    );
+    ui.ctx().request_repaint();
+}
+
+///Something to do to every selected quote, picked from the [`selection_toolbar`]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum SelectionAction {
+    Delete,
+    Copy,
+    ///Only offered with exactly two selected
+    Merge,
+    Export,
+    Tag,
+}
+
+///The "Select" toggle, and while it's on, buttons for picking quotes and acting on them - `shown` is what "Select all shown" adds
+pub fn selection_toolbar(
+    ui: &mut Ui,
+    select_mode: &mut bool,
+    selected: &mut BTreeSet<usize>,
+    shown: &[usize],
+) -> Option<SelectionAction> {
+    let mut action = None;
+    ui.horizontal_wrapped(|ui| {
+        if ui.selectable_label(*select_mode, "Select").clicked() {
+            *select_mode = !*select_mode;
+            selected.clear();
+        }
+        if !*select_mode {
+            return;
+        }
+        if ui.button("Select all shown").clicked() {
+            selected.extend(shown);
+        }
+        if ui.button("Clear selection").clicked() {
+            selected.clear();
+        }
+        ui.label(format!("{} selected", selected.len()));
+        ui.add_enabled_ui(!selected.is_empty(), |ui| {
+            if ui.button("Copy selected").clicked() {
+                action = Some(SelectionAction::Copy);
+            }
+            if ui.button("Tag selected…").clicked() {
+                action = Some(SelectionAction::Tag);
+            }
+            if ui.button("Export selected…").clicked() {
+                action = Some(SelectionAction::Export);
+            }
+            if ui
+                .add_enabled(selected.len() == 2, egui::Button::new("Merge selected…"))
+                .on_disabled_hover_text("Pick exactly two quotes to merge")
+                .clicked()
+            {
+                action = Some(SelectionAction::Merge);
+            }
+            if ui.button("Delete selected").clicked() {
+                action = Some(SelectionAction::Delete);
+            }
+        });
+    });
+    action
+}
+
+///An edit's changes inline - removed words struck through in red, added ones in green
+pub fn show_word_diff(ui: &mut Ui, spans: &[DiffSpan]) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 4.0;
+        for span in spans {
+            let text = RichText::new(span.text());
+            ui.label(match span {
+                DiffSpan::Same(_) => text,
+                DiffSpan::Added(_) => text.color(Color32::LIGHT_GREEN).underline(),
+                DiffSpan::Removed(_) => text.color(Color32::LIGHT_RED).strikethrough(),
+            });
+        }
+    });
+}
+
+///The "Showing X of Y" line above every list of quotes - `qualifier` goes on the end, eg. to mention a length range
+pub fn counts_header(ui: &mut Ui, shown: usize, total: usize, qualifier: &str) {
+    ui.label(format!("Showing {shown} of {total}{qualifier}"));
+}
+
+///Why a list came out empty, so it can say something more useful than nothing at all
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EmptyState<'a> {
+    ///There aren't any quotes yet
+    EmptyDb,
+    ///The category view with no categories checked
+    NothingChecked,
+    ///The checked categories don't share any quotes
+    NoCategoryMatches,
+    ///The quick filters or length range rule out everything
+    FilteredOut,
+    ///A search for this term found nothing
+    NoResults(&'a str),
+}
+
+impl EmptyState<'_> {
+    const fn icon(self) -> &'static str {
+        match self {
+            Self::EmptyDb => "📭",
+            Self::NothingChecked => "☑",
+            Self::NoCategoryMatches | Self::FilteredOut => "🗐",
+            Self::NoResults(_) => "🔍",
+        }
+    }
+
+    fn message(self) -> String {
+        match self {
+            Self::EmptyDb => "There aren't any quotes yet - add some from Quote Entry".to_string(),
+            Self::NothingChecked => "Check some categories to see their quotes".to_string(),
+            Self::NoCategoryMatches => {
+                "No quotes match these categories - try unchecking some".to_string()
+            }
+            Self::FilteredOut => {
+                "No quotes get past these filters - try turning some off".to_string()
+            }
+            Self::NoResults(term) => format!("No results for '{term}'"),
+        }
+    }
+}
+
+///Shown in place of a list of quotes when there's nothing in it
+pub fn render_empty_state(ui: &mut Ui, context: EmptyState) {
+    ui.vertical_centered(|ui| {
+        ui.add_space(20.0);
+        ui.label(RichText::new(context.icon()).size(48.0).weak());
+        ui.label(RichText::new(context.message()).weak());
+    });
+}
+
+///eg. "2 days ago" for a span of `seconds`
+#[must_use]
+pub fn describe_age(seconds: u64) -> String {
+    let (count, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3_599 => (seconds / 60, "minute"),
+        3_600..=86_399 => (seconds / 3_600, "hour"),
+        86_400..=2_591_999 => (seconds / 86_400, "day"),
+        2_592_000..=31_535_999 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    if count == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{count} {unit}s ago")
+    }
+}
+
+///WCAG relative luminance, from 0 for black to 1 for white
+fn relative_luminance(colour: Color32) -> f32 {
+    0.0722f32.mul_add(
+        linear_f32_from_gamma_u8(colour.b()),
+        0.2126f32.mul_add(
+            linear_f32_from_gamma_u8(colour.r()),
+            0.7152 * linear_f32_from_gamma_u8(colour.g()),
+        ),
+    )
+}
+
+///WCAG contrast ratio, from 1 for identical colours up to 21 for black on white
+fn contrast_ratio(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+///A background colour for `category`'s chip - the hue comes from the name, so a category always gets the same colour (see [`chip_color`])
+#[must_use]
+pub fn category_color(category: &str) -> Color32 {
+    //FNV-1a, as `DefaultHasher` isn't guaranteed to give the same hue between builds
+    let hash = category_key(category)
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        });
+    chip_color(f32::from(u16::try_from(hash % 360).unwrap_or_default()) / 360.0)
+}
+
+///A chip colour with `hue` (0 to 1), bright enough to stand out by at least [`MIN_CHIP_CONTRAST`] against both the light and dark themes
+fn chip_color(hue: f32) -> Color32 {
+    let backgrounds = [
+        Visuals::dark().window_fill(),
+        Visuals::light().window_fill(),
+    ];
+    //brighter always helps against the dark theme and hurts against the light one, so look for the best balance
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..16 {
+        let value = f32::midpoint(low, high);
+        let colour = Color32::from(Hsva::new(hue, 0.6, value, 1.0));
+        if contrast_ratio(colour, backgrounds[0]) < contrast_ratio(colour, backgrounds[1]) {
+            low = value;
+        } else {
+            high = value;
+        }
+    }
+    let colour = Color32::from(Hsva::new(hue, 0.6, low, 1.0));
+    //the balance point for a saturation of 0.6 is around 4:1 for every hue
+    debug_assert!(backgrounds
+        .iter()
+        .all(|&background| contrast_ratio(colour, background) >= MIN_CHIP_CONTRAST));
+    colour
+}
+
+///Black or white, whichever is easier to read on `background`
+#[must_use]
+pub fn category_text_color(background: Color32) -> Color32 {
+    if contrast_ratio(Color32::BLACK, background) >= contrast_ratio(Color32::WHITE, background) {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+///eg. ★★★☆☆ for 3
+#[must_use]
+pub fn stars(rating: u8) -> String {
+    (1..=MAX_RATING)
+        .map(|star| if star <= rating { '★' } else { '☆' })
+        .collect()
+}
+
+#[must_use]
+pub fn has_no_author(quote: &Quote) -> bool {
+    quote.author().is_none_or(|author| author.trim().is_empty())
+}
+
+#[must_use]
+pub const fn has_no_categories(quote: &Quote) -> bool {
+    quote.1.is_empty()
+}
+
+#[must_use]
+pub fn is_longer_than(quote: &Quote, length: usize) -> bool {
+    quote.0.chars().count() > length
+}
+
+///Whether `quote` is between `min` and `max` characters long, inclusive
+#[must_use]
+pub fn length_in_range(quote: &Quote, min: usize, max: usize) -> bool {
+    (min..=max).contains(&quote.0.chars().count())
+}
+
+///Only shows quotes of a certain length, eg. to find a short epigraph - works alongside the other filters in both the category and search views
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct LengthRange {
+    pub enabled: bool,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl LengthRange {
+    #[must_use]
+    pub fn matches(&self, quote: &Quote) -> bool {
+        !self.enabled || length_in_range(quote, self.min, self.max)
+    }
+
+    ///eg. " between 20 and 120 characters", or nothing if the range is off
+    #[must_use]
+    pub fn describe(&self) -> String {
+        if self.enabled {
+            format!(" between {} and {} characters", self.min, self.max)
+        } else {
+            String::new()
+        }
+    }
+
+    ///`longest` is the length of the longest quote, which bounds the sliders
+    pub fn show(&mut self, ui: &mut Ui, longest: usize) {
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.enabled, "Length").changed() && self.enabled && self.max == 0 {
+                self.max = longest;
+            }
+            ui.add_enabled_ui(self.enabled, |ui| {
+                ui.add(egui::Slider::new(&mut self.min, 0..=longest).text("min"));
+                ui.add(egui::Slider::new(&mut self.max, 0..=longest).text("max"));
+            });
+            self.max = self.max.max(self.min);
+        });
+    }
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
-pub enum QuoteSelectionFilter {
-    And,
-    Or,
+///Data-quality filters that can be toggled on top of the category filter
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct QuickFilters {
+    pub no_author: bool,
+    pub no_categories: bool,
+    pub too_long: bool,
+    pub max_length: usize,
+    ///Only rated quotes with at least `min_rating` stars
+    pub rated: bool,
+    pub min_rating: u8,
+    ///Only quotes with this attribution - quotes that haven't been given one count as [`Attribution::Attributed`]
+    pub attribution: Option<Attribution>,
 }
-impl Default for QuoteSelectionFilter {
+
+impl Default for QuickFilters {
     fn default() -> Self {
-        Self::Or
+        Self {
+            no_author: false,
+            no_categories: false,
+            too_long: false,
+            max_length: 200,
+            rated: false,
+            min_rating: 4,
+            attribution: None,
+        }
+    }
+}
+
+impl QuickFilters {
+    #[must_use]
+    pub const fn any_active(&self) -> bool {
+        self.no_author
+            || self.no_categories
+            || self.too_long
+            || self.rated
+            || self.attribution.is_some()
+    }
+
+    #[must_use]
+    pub fn matches(&self, quote: &Quote) -> bool {
+        (!self.no_author || has_no_author(quote))
+            && (!self.no_categories || has_no_categories(quote))
+            && (!self.too_long || is_longer_than(quote, self.max_length))
+            && (!self.rated
+                || quote
+                    .rating()
+                    .is_some_and(|rating| rating >= self.min_rating))
+            && self.attribution.is_none_or(|attribution| {
+                quote.attribution().unwrap_or(Attribution::Attributed) == attribution
+            })
+    }
+
+    pub fn show_chips(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.selectable_label(self.no_author, "No author").clicked() {
+                self.no_author = !self.no_author;
+            }
+            if ui
+                .selectable_label(self.no_categories, "No categories")
+                .clicked()
+            {
+                self.no_categories = !self.no_categories;
+            }
+            if ui
+                .selectable_label(
+                    self.too_long,
+                    format!("Over {} characters", self.max_length),
+                )
+                .clicked()
+            {
+                self.too_long = !self.too_long;
+            }
+            if self.too_long {
+                ui.add(egui::DragValue::new(&mut self.max_length).speed(5));
+            }
+            if ui
+                .selectable_label(self.rated, format!("Rating ≥ {}", self.min_rating))
+                .clicked()
+            {
+                self.rated = !self.rated;
+            }
+            if self.rated {
+                ui.add(egui::Slider::new(&mut self.min_rating, 1..=MAX_RATING));
+            }
+            egui::ComboBox::from_id_source("attribution_filter")
+                .selected_text(
+                    self.attribution
+                        .map_or_else(|| "Any attribution".to_string(), |a| a.to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.attribution, None, "Any attribution");
+                    for attribution in Attribution::ALL {
+                        ui.selectable_value(
+                            &mut self.attribution,
+                            Some(attribution),
+                            format!("{} {attribution}", attribution.icon()),
+                        );
+                    }
+                });
+        });
+    }
+}
+
+///A single line text edit which, with the `spellcheck` feature, underlines likely typos (with suggestions on hover).
+///Without the feature, or with `spellcheck` off, it's a plain text edit.
+pub fn spellchecked_text_edit(ui: &mut Ui, text: &mut String, spellcheck: bool) -> egui::Response {
+    #[cfg(feature = "spellcheck")]
+    if spellcheck {
+        return spellcheck::text_edit(ui, text);
+    }
+    #[cfg(not(feature = "spellcheck"))]
+    let _ = spellcheck;
+
+    ui.text_edit_singleline(text)
+}
+
+#[cfg(feature = "spellcheck")]
+mod spellcheck {
+    use egui::{epaint::text::cursor::CCursor, Color32, Id, Pos2, Shape, Stroke, TextEdit, Ui};
+    use english_quotes::utils::spellcheck::{suggest_corrections, Misspelling};
+
+    const WAVE_LENGTH: f32 = 3.0;
+    const WAVE_HEIGHT: f32 = 1.5;
+
+    pub fn text_edit(ui: &mut Ui, text: &mut String) -> egui::Response {
+        let output = TextEdit::singleline(text).show(ui);
+
+        //finding suggestions means scanning the whole word list, so only redo it when the text changes
+        let id = Id::new("spellcheck").with(output.response.id);
+        let misspellings = match ui.data().get_temp::<(String, Vec<Misspelling>)>(id) {
+            Some((checked, misspellings)) if &checked == text => misspellings,
+            _ => {
+                let misspellings = suggest_corrections(text);
+                ui.data()
+                    .insert_temp(id, (text.clone(), misspellings.clone()));
+                misspellings
+            }
+        };
+
+        let stroke = Stroke::new(1.0, Color32::RED);
+        let mut hover = vec![];
+        for misspelling in &misspellings {
+            let start = output
+                .galley
+                .pos_from_cursor(&output.galley.from_ccursor(CCursor::new(misspelling.start)));
+            let end = output
+                .galley
+                .pos_from_cursor(&output.galley.from_ccursor(CCursor::new(misspelling.end)));
+            let offset = output.text_draw_pos.to_vec2();
+            let (left, right, y) = (
+                start.left() + offset.x,
+                end.left() + offset.x,
+                start.bottom() + offset.y,
+            );
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let waves = ((right - left) / WAVE_LENGTH).ceil().max(0.0) as usize;
+            let mut points: Vec<Pos2> = (0..waves)
+                .map(|i| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let x = (i as f32).mul_add(WAVE_LENGTH, left);
+                    Pos2::new(x, if i % 2 == 0 { y } else { y - WAVE_HEIGHT })
+                })
+                .collect();
+            points.push(Pos2::new(right, y));
+            ui.painter()
+                .with_clip_rect(output.text_clip_rect)
+                .add(Shape::line(points, stroke));
+
+            hover.push(if misspelling.suggestions.is_empty() {
+                format!("{}: not in the dictionary", misspelling.word)
+            } else {
+                format!(
+                    "{}: did you mean {}?",
+                    misspelling.word,
+                    misspelling.suggestions.join(", ")
+                )
+            });
+        }
+
+        if hover.is_empty() {
+            output.response
+        } else {
+            output.response.on_hover_text(hover.join("\n"))
+        }
+    }
+}
+
+///Everything narrowing down the list besides the checked categories (which the entry form shares) and the search term
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ActiveFilters {
+    ///How the checked categories combine, in the category view
+    pub mode: QuoteSelectionFilter,
+    pub quick: QuickFilters,
+    pub length: LengthRange,
+    ///Only show quotes in this collection
+    pub collection: Option<String>,
+}
+
+impl ActiveFilters {
+    ///Whether anything here narrows the list, going by [`Self::matches`]
+    #[must_use]
+    pub const fn narrows(&self) -> bool {
+        self.quick.any_active() || self.length.enabled || self.collection.is_some()
+    }
+
+    #[must_use]
+    pub fn matches(&self, quote: &Quote) -> bool {
+        self.quick.matches(quote)
+            && self.length.matches(quote)
+            && self
+                .collection
+                .as_ref()
+                .is_none_or(|collection| quote.in_collection(collection))
+    }
+
+    ///Each active filter as a pill that removes it when clicked, then "Clear all" - draws nothing if there aren't any.
+    ///
+    ///`checked` is left out where the categories don't apply (ie. searching), as is `search` outside of search.
+    pub fn show_bar(
+        &mut self,
+        ui: &mut Ui,
+        mut checked: Option<&mut HashSet<String>>,
+        categories: &[String],
+        mut search: Option<&mut String>,
+    ) {
+        let chosen = checked
+            .as_deref()
+            .map(|checked| get_chosen_types(checked, categories))
+            .unwrap_or_default();
+        let searching = search.as_deref().is_some_and(|term| !term.is_empty());
+        if chosen.is_empty() && !searching && !self.narrows() {
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Filters:");
+            let pill = |ui: &mut Ui, label: String| {
+                ui.small_button(format!("{label} ×"))
+                    .on_hover_text("Remove this filter")
+                    .clicked()
+            };
+
+            if let Some(term) = search.as_deref_mut().filter(|term| !term.is_empty()) {
+                if pill(ui, format!("Search: \"{term}\"")) {
+                    term.clear();
+                }
+            }
+            for category in &chosen {
+                if pill(ui, category.clone()) {
+                    if let Some(checked) = checked.as_deref_mut() {
+                        checked.remove(category);
+                    }
+                }
+            }
+            if chosen.len() > 1
+                && self.mode == QuoteSelectionFilter::And
+                && pill(ui, "All of selected".to_string())
+            {
+                self.mode = QuoteSelectionFilter::Or;
+            }
+            if self.quick.no_author && pill(ui, "No author".to_string()) {
+                self.quick.no_author = false;
+            }
+            if self.quick.no_categories && pill(ui, "No categories".to_string()) {
+                self.quick.no_categories = false;
+            }
+            if self.quick.too_long && pill(ui, format!("Over {} characters", self.quick.max_length))
+            {
+                self.quick.too_long = false;
+            }
+            if self.quick.rated && pill(ui, format!("Rating ≥ {}", self.quick.min_rating)) {
+                self.quick.rated = false;
+            }
+            if let Some(attribution) = self.quick.attribution {
+                if pill(ui, attribution.to_string()) {
+                    self.quick.attribution = None;
+                }
+            }
+            if self.length.enabled
+                && pill(
+                    ui,
+                    format!("{}-{} characters", self.length.min, self.length.max),
+                )
+            {
+                self.length.enabled = false;
+            }
+            if let Some(collection) = &self.collection {
+                if pill(ui, format!("Collection: {collection}")) {
+                    self.collection = None;
+                }
+            }
+
+            if ui.button("Clear all").clicked() {
+                let mode = self.mode;
+                *self = Self {
+                    mode,
+                    quick: QuickFilters {
+                        max_length: self.quick.max_length,
+                        min_rating: self.quick.min_rating,
+                        ..QuickFilters::default()
+                    },
+                    length: LengthRange {
+                        enabled: false,
+                        ..self.length
+                    },
+                    collection: None,
+                };
+                if let Some(checked) = checked {
+                    checked.clear();
+                }
+                if let Some(term) = search {
+                    term.clear();
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn checked_categories_survive_the_list_changing() {
+        let checked = reverse_chosen_types(
+            names(&["Esme", "Arthur", "Gone"]),
+            &names(&["Stella", "Esme", "Arthur"]),
+        );
+        assert_eq!(checked, HashSet::from(["Esme".into(), "Arthur".into()]));
+
+        //a new category at the front and the rest shuffled
+        let reordered = names(&["New", "Arthur", "Stella", "Esme"]);
+        assert_eq!(
+            get_chosen_types(&checked, &reordered),
+            names(&["Arthur", "Esme"])
+        );
+        assert_eq!(
+            get_chosen_types(&checked, &names(&["Stella", "Esme"])),
+            names(&["Esme"])
+        );
+        assert!(get_chosen_types(&HashSet::new(), &reordered).is_empty());
+    }
+
+    fn with_author(text: &str, author: Option<&str>) -> Quote {
+        let mut quote = Quote::new(text, vec![]);
+        quote.2.author = author.map(ToString::to_string);
+        quote
+    }
+
+    #[test]
+    fn missing_authors() {
+        assert!(has_no_author(&with_author("a", None)));
+        assert!(has_no_author(&with_author("a", Some(""))));
+        assert!(has_no_author(&with_author("a", Some(" \t "))));
+        assert!(!has_no_author(&with_author("a", Some("Anne"))));
+    }
+
+    #[test]
+    fn lengths_are_counted_in_characters() {
+        //5 characters, but 10 bytes
+        let quote = Quote::new("\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}", vec![]);
+        assert!(is_longer_than(&quote, 4));
+        assert!(!is_longer_than(&quote, 5));
+        assert!(length_in_range(&quote, 5, 5));
+        assert!(length_in_range(&quote, 0, 10));
+        assert!(!length_in_range(&quote, 6, 10));
+        assert!(!length_in_range(&quote, 0, 4));
+    }
+
+    #[test]
+    fn quick_filters_combine() {
+        let mut disputed = with_author("a", None);
+        disputed.2.attribution = Some(Attribution::Disputed);
+        let mut rated = with_author("a much longer quote", Some("Anne"));
+        rated.2.rating = Some(4);
+
+        let filters = QuickFilters::default();
+        assert!(!filters.any_active());
+        assert!(filters.matches(&disputed) && filters.matches(&rated));
+
+        let no_author = QuickFilters {
+            no_author: true,
+            ..QuickFilters::default()
+        };
+        assert!(no_author.matches(&disputed));
+        assert!(!no_author.matches(&rated));
+
+        let long_and_rated = QuickFilters {
+            too_long: true,
+            max_length: 10,
+            rated: true,
+            min_rating: 4,
+            ..QuickFilters::default()
+        };
+        assert!(long_and_rated.matches(&rated));
+        assert!(!long_and_rated.matches(&disputed));
+
+        //quotes without an attribution count as attributed
+        let attributed = QuickFilters {
+            attribution: Some(Attribution::Attributed),
+            ..QuickFilters::default()
+        };
+        assert!(attributed.any_active());
+        assert!(attributed.matches(&rated));
+        assert!(!attributed.matches(&disputed));
+        assert!(!QuickFilters {
+            no_author: true,
+            ..attributed
+        }
+        .matches(&disputed));
+    }
+
+    #[test]
+    fn length_ranges_only_filter_when_enabled() {
+        let quote = Quote::new("\u{1F600} smile", vec![]);
+        let mut range = LengthRange {
+            enabled: false,
+            min: 10,
+            max: 20,
+        };
+        assert!(range.matches(&quote));
+        assert_eq!(range.describe(), "");
+
+        range.enabled = true;
+        assert!(!range.matches(&quote));
+        assert_eq!(range.describe(), " between 10 and 20 characters");
+        range.min = 7;
+        assert!(range.matches(&quote));
+        range.max = 6;
+        assert!(!range.matches(&quote));
+    }
+
+    #[test]
+    fn chips_stand_out_in_both_themes() {
+        for degrees in 0..360_u16 {
+            let colour = chip_color(f32::from(degrees) / 360.0);
+            for background in [
+                Visuals::dark().window_fill(),
+                Visuals::light().window_fill(),
+            ] {
+                let contrast = contrast_ratio(colour, background);
+                assert!(
+                    contrast >= MIN_CHIP_CONTRAST,
+                    "{degrees} degrees only has {contrast}"
+                );
+            }
+            let text = category_text_color(colour);
+            assert!(contrast_ratio(text, colour) >= MIN_CHIP_CONTRAST);
+        }
+        assert_eq!(category_color("Stella"), category_color(" stella"));
     }
 }
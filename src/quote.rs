@@ -1,34 +1,46 @@
-use lazy_static::lazy_static;
+use crate::utils::{template::QuoteTemplate, Error};
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
+    collections::HashSet,
     fmt::{Debug, Display, Formatter},
+    str::FromStr,
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
 };
+use unicode_segmentation::UnicodeSegmentation;
 
-lazy_static! {
-    pub static ref ALL_PERMS: Vec<String> = {
-        let location = FileType::Types.get_location();
-        std::fs::read_to_string(&location)
-            .unwrap_or_else(|_| panic!("{}", format!("Could not find t.t at {}", location)))
-            .split('\n')
-            .filter(|ty| !ty.contains("//"))
-            .map(|ty| {
-                if ty.contains('\r') {
-                    let len = ty.len();
-                    &ty[..len - 1]
-                } else {
-                    ty
-                }
-                .to_string()
-            })
-            .collect()
-    };
-}
+pub static ALL_PERMS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    let location = FileType::Types.get_location();
+    std::fs::read_to_string(location)
+        .unwrap_or_else(|_| panic!("Could not find t.t at {location}"))
+        .split('\n')
+        .filter(|ty| !ty.contains("//"))
+        .map(|ty| {
+            if ty.contains('\r') {
+                let len = ty.len();
+                &ty[..len - 1]
+            } else {
+                ty
+            }
+            .to_string()
+        })
+        .collect()
+});
 
 pub enum FileType {
     Database,
     Types,
     Export,
+    CsvExport,
+    HtmlApp,
+    PlainTextExport,
+    ///One quote per line, see [`crate::utils::exports::write_json_lines`]
+    JsonLines,
+    Recovery,
+    Backup,
+    Settings,
+    Trash,
 }
 
 impl FileType {
@@ -38,12 +50,657 @@ impl FileType {
             Self::Database => "db.json",
             Self::Types => "types.txt",
             Self::Export => "export.md",
+            Self::CsvExport => "export.csv",
+            Self::HtmlApp => "export.html",
+            Self::PlainTextExport => "export.txt",
+            Self::JsonLines => "export.jsonl",
+            Self::Recovery => "db.recovery.json",
+            Self::Backup => "db.backup.json",
+            Self::Settings => "settings.json",
+            Self::Trash => "trash.json",
         }
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
-pub struct Quote(pub String, pub Vec<String>);
+pub struct Quote(pub String, pub Vec<String>, #[serde(default)] pub QuoteMeta);
+
+///Optional extra information about a quote - everything in here has to have a default so old databases still load
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct QuoteMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    ///Seconds since the unix epoch when the quote was first added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added: Option<u64>,
+    ///Where the quote came from - always a URL, see [`is_valid_source`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    ///Free-form labels, kept apart from the categories in types.txt - stored without the leading `#`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    ///1 to [`MAX_RATING`] stars, `None` if it hasn't been rated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    ///Seconds since the unix epoch when the quote was last changed in place - see [`update_quote`](crate::db::update_quote)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    ///Personal groupings like "Wedding speech", which unlike categories and tags are only for picking quotes out again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<String>,
+    ///How sure it is that the author really said it, `None` if that hasn't been looked into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<Attribution>,
+}
+
+pub const MAX_RATING: u8 = 5;
+
+///How trustworthy a quote's attribution is - plenty of famous quotes are pinned on the wrong person
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Attribution {
+    ///Checked against a primary source
+    Confirmed,
+    ///Commonly credited to the author, but not checked
+    Attributed,
+    ///Thought to be misattributed
+    Disputed,
+}
+
+impl Attribution {
+    pub const ALL: [Self; 3] = [Self::Confirmed, Self::Attributed, Self::Disputed];
+
+    #[must_use]
+    pub const fn icon(self) -> &'static str {
+        match self {
+            Self::Confirmed => "✔",
+            Self::Attributed => "❔",
+            Self::Disputed => "⚠",
+        }
+    }
+}
+
+impl Display for Attribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Confirmed => write!(f, "Confirmed"),
+            Self::Attributed => write!(f, "Attributed"),
+            Self::Disputed => write!(f, "Disputed"),
+        }
+    }
+}
+
+impl FromStr for Attribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "confirmed" => Ok(Self::Confirmed),
+            "attributed" => Ok(Self::Attributed),
+            "disputed" => Ok(Self::Disputed),
+            _ => Err(format!("unknown attribution {s}")),
+        }
+    }
+}
+
+///Splits comma-separated tags, dropping any leading `#`s, blanks and repeats
+#[must_use]
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for tag in input.split(',') {
+        let tag = tag.trim().trim_start_matches('#').trim();
+        if !tag.is_empty() && !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+///Sources have to be an http(s) URL with something after the scheme and no spaces
+#[must_use]
+pub fn is_valid_source(source: &str) -> bool {
+    let source = source.trim();
+    ["http://", "https://"].iter().any(|scheme| {
+        source
+            .strip_prefix(scheme)
+            .is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace))
+    })
+}
+
+///Straightens curly quotes, collapses any run of whitespace (including non-breaking spaces and newlines) into a single space, and trims.
+///
+///Dashes are left alone, as an em-dash usually means something different from a hyphen.
+#[must_use]
+pub fn normalize_text(text: &str) -> String {
+    let straightened: String = text
+        .chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
+            c => c,
+        })
+        .collect();
+    straightened
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+///Optional tidying of how quotes start and end - see [`apply_punctuation_rules`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PunctuationRules {
+    ///Capitalise the first letter
+    pub capitalize: bool,
+    ///Add a full stop if the quote doesn't already end in `.`, `!`, `?` or `…`
+    pub terminal_period: bool,
+}
+
+const CLOSING_QUOTES: [char; 5] = ['"', '\'', '\u{201D}', '\u{2019}', ')'];
+const OPENING_QUOTES: [char; 5] = ['"', '\'', '\u{201C}', '\u{2018}', '('];
+
+///Whether `text` looks like it starts part way through a sentence, eg. `...and so on`
+fn is_fragment_start(text: &str) -> bool {
+    let text = text.trim_start_matches(OPENING_QUOTES);
+    text.starts_with("...") || text.starts_with('\u{2026}')
+}
+
+///Applies `rules` to `text`, leaving anything that looks like a fragment alone.
+///
+///- Capitalisation skips leading quotation marks, and leaves words like `iPhone` (with a capital second letter) and text starting with an ellipsis as they are.
+///- A full stop goes after any closing quotation mark, unless there's already one (or a `!`, `?` or `…`) just inside it. Text ending in `,`, `;`, `:`, a dash or an ellipsis is left as it is.
+#[must_use]
+pub fn apply_punctuation_rules(text: &str, rules: PunctuationRules) -> String {
+    let mut text = text.trim().to_string();
+    if text.is_empty() {
+        return text;
+    }
+
+    if rules.capitalize && !is_fragment_start(&text) {
+        let start = text.len() - text.trim_start_matches(OPENING_QUOTES).len();
+        let mut rest = text[start..].chars();
+        if let (Some(first), second) = (rest.next(), rest.next()) {
+            if first.is_lowercase() && !second.is_some_and(char::is_uppercase) {
+                let capitalized: String = first.to_uppercase().collect();
+                text.replace_range(start..start + first.len_utf8(), &capitalized);
+            }
+        }
+    }
+
+    if rules.terminal_period {
+        let inner = text.trim_end_matches(CLOSING_QUOTES);
+        let ends_sentence = inner.ends_with(['.', '!', '?', '\u{2026}']);
+        let ends_fragment = inner.ends_with([',', ';', ':', '-', '\u{2013}', '\u{2014}']);
+        if !inner.is_empty() && !ends_sentence && !ends_fragment {
+            text.push('.');
+        }
+    }
+
+    text
+}
+
+///What category names are compared on, so `Motivation` and ` motivation ` count as the same category
+#[must_use]
+pub fn category_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+///Whether `a` and `b` hold the same categories, in any order - compared by [`category_key`]
+#[must_use]
+pub fn same_categories(a: &[String], b: &[String]) -> bool {
+    let keys = |categories: &[String]| -> HashSet<String> {
+        categories
+            .iter()
+            .map(|category| category_key(category))
+            .collect()
+    };
+    keys(a) == keys(b)
+}
+
+///Adds each of `from` to `into` unless it's already there, going by [`category_key`]
+fn union_by_key(into: &mut Vec<String>, from: &[String]) {
+    for name in from {
+        let key = category_key(name);
+        if !into.iter().any(|existing| category_key(existing) == key) {
+            into.push(name.clone());
+        }
+    }
+}
+
+///The quote as a single line of plain text, laid out by `template`
+#[must_use]
+pub fn format_quote(quote: &Quote, template: &QuoteTemplate) -> String {
+    template.render(quote)
+}
+
+///The first `max` graphemes of `text` followed by `…`, or all of `text` if it's no longer than that.
+///
+///Cuts between graphemes rather than bytes or chars, so emoji and accents made of combining characters are never split.
+#[must_use]
+pub fn truncate_graphemes(text: &str, max: usize) -> String {
+    match text.grapheme_indices(true).nth(max) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+///Hard-wraps `text` so no line is longer than `width` graphemes, breaking between words.
+///
+///Line breaks already in `text` are kept, along with any indentation at the start of each line, which the lines it wraps onto get too. A word longer than `width` gets a line of its own rather than being split. A `width` of 0 leaves `text` as it is.
+#[must_use]
+pub fn wrap_text(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+
+    let mut wrapped: Vec<String> = vec![];
+    for line in text.lines() {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let indent_width = indent.graphemes(true).count();
+        let mut current = indent.to_string();
+        let mut current_width = indent_width;
+        for word in line.split_whitespace() {
+            let word_width = word.graphemes(true).count();
+            let started = current_width > indent_width;
+            if started && current_width + 1 + word_width > width {
+                wrapped.push(std::mem::replace(&mut current, indent.to_string()));
+                current_width = indent_width;
+            } else if started {
+                current.push(' ');
+                current_width += 1;
+            }
+            current.push_str(word);
+            current_width += word_width;
+        }
+        wrapped.push(if line.trim().is_empty() {
+            String::new()
+        } else {
+            current
+        });
+    }
+    wrapped.join("\n")
+}
+
+///Seconds since the unix epoch, or 0 if the clock is before it
+#[must_use]
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+///Seconds since the unix epoch at the start of a `YYYY-MM-DD` day (UTC), or `None` if it isn't a real date from 1970 on
+#[must_use]
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month_length = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if !(1..=month_length).contains(&day) {
+        return None;
+    }
+
+    //days since 1970-01-01, from http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days).ok().map(|days| days * 86_400)
+}
+
+///The `YYYY-MM-DD` day (UTC) that `timestamp` falls on - the reverse of [`parse_date`]
+#[must_use]
+pub fn format_date(timestamp: u64) -> String {
+    //from http://howardhinnant.github.io/date_algorithms.html#civil_from_days, which only needs unsigned maths from 1970 on
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+impl Quote {
+    #[must_use]
+    pub fn new(text: impl Into<String>, categories: Vec<String>) -> Self {
+        Self(text.into(), categories, QuoteMeta::default())
+    }
+
+    ///Start building a quote - the builder trims everything, drops empty or repeated categories and tags, and stamps the current time
+    pub fn builder(text: impl Into<String>) -> QuoteBuilder {
+        QuoteBuilder {
+            text: text.into(),
+            categories: vec![],
+            author: None,
+            added: None,
+            source: None,
+            tags: vec![],
+            rating: None,
+        }
+    }
+
+    #[must_use]
+    pub fn author(&self) -> Option<&str> {
+        self.2.author.as_deref()
+    }
+
+    ///What two quotes have to share to count as the same quote: their [normalized](normalize_text) text and their author.
+    ///
+    ///The same words attributed to two different people are kept as two quotes.
+    #[must_use]
+    pub fn dedup_key(&self) -> (String, Option<String>) {
+        (
+            normalize_text(&self.0),
+            self.author()
+                .map(|author| normalize_text(author).to_lowercase()),
+        )
+    }
+
+    ///Whether `other` is the same quote, ignoring any differences [`normalize_text`] would tidy away.
+    ///
+    ///Looser than `==`, which already ignores category order but wants the text and category names to match exactly - here categories are compared by [`category_key`] as a set.
+    #[must_use]
+    pub fn same_quote(&self, other: &Self) -> bool {
+        normalize_text(&self.0) == normalize_text(&other.0) && same_categories(&self.1, &other.1)
+    }
+
+    #[must_use]
+    pub fn source(&self) -> Option<&str> {
+        self.2.source.as_deref()
+    }
+
+    ///Whether the quote is in the category `name` - ignoring case and surrounding whitespace, see [`category_key`]
+    #[must_use]
+    pub fn matches_category(&self, name: &str) -> bool {
+        let name = category_key(name);
+        self.1.iter().any(|cat| category_key(cat) == name)
+    }
+
+    ///Whether the quote is in at least one of `names` - never for an empty slice
+    #[must_use]
+    pub fn matches_any<S: AsRef<str>>(&self, names: &[S]) -> bool {
+        names
+            .iter()
+            .any(|name| self.matches_category(name.as_ref()))
+    }
+
+    ///Whether the quote is in every one of `names` - always for an empty slice
+    #[must_use]
+    pub fn matches_all<S: AsRef<str>>(&self, names: &[S]) -> bool {
+        names
+            .iter()
+            .all(|name| self.matches_category(name.as_ref()))
+    }
+
+    #[must_use]
+    pub fn tags(&self) -> &[String] {
+        &self.2.tags
+    }
+
+    #[must_use]
+    pub const fn modified(&self) -> Option<u64> {
+        self.2.modified
+    }
+
+    #[must_use]
+    pub fn collections(&self) -> &[String] {
+        &self.2.collections
+    }
+
+    ///Adds any of `other`'s categories, tags and collections that this quote doesn't have yet (going by [`category_key`]), giving back whether it gained any.
+    ///
+    ///This doesn't stamp it as modified - do that with [`update_quote`](crate::db::update_quote) or [`Quote::touch`].
+    pub fn absorb(&mut self, other: &Self) -> bool {
+        let counts = |quote: &Self| (quote.1.len(), quote.2.tags.len(), quote.2.collections.len());
+        let before = counts(self);
+        union_by_key(&mut self.1, &other.1);
+        union_by_key(&mut self.2.tags, &other.2.tags);
+        union_by_key(&mut self.2.collections, &other.2.collections);
+        counts(self) != before
+    }
+
+    ///Whether the quote is in the collection `name` - ignoring case and surrounding whitespace like categories
+    #[must_use]
+    pub fn in_collection(&self, name: &str) -> bool {
+        let name = category_key(name);
+        self.2
+            .collections
+            .iter()
+            .any(|collection| category_key(collection) == name)
+    }
+
+    ///Returns whether the quote wasn't already in the collection - blank names are ignored
+    pub fn add_to_collection(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        if name.is_empty() || self.in_collection(name) {
+            return false;
+        }
+        self.2.collections.push(name.to_string());
+        true
+    }
+
+    ///Returns whether the quote was in the collection
+    pub fn remove_from_collection(&mut self, name: &str) -> bool {
+        let before = self.2.collections.len();
+        let name = category_key(name);
+        self.2
+            .collections
+            .retain(|collection| category_key(collection) != name);
+        self.2.collections.len() != before
+    }
+
+    ///Stamps the quote as changed just now
+    pub fn touch(&mut self) {
+        self.2.modified = Some(now_timestamp());
+    }
+
+    #[must_use]
+    pub const fn rating(&self) -> Option<u8> {
+        self.2.rating
+    }
+
+    #[must_use]
+    pub const fn attribution(&self) -> Option<Attribution> {
+        self.2.attribution
+    }
+
+    pub const fn set_attribution(&mut self, attribution: Option<Attribution>) {
+        self.2.attribution = attribution;
+    }
+
+    ///Anything over [`MAX_RATING`] is clamped, and 0 clears the rating
+    pub fn set_rating(&mut self, rating: Option<u8>) {
+        self.2.rating = rating
+            .filter(|&rating| rating > 0)
+            .map(|rating| rating.min(MAX_RATING));
+    }
+
+    pub fn categories(&self, strict: bool) -> Result<Vec<Category>, Error> {
+        self.1
+            .iter()
+            .map(|cat| Category::new(cat, strict))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct QuoteBuilder {
+    text: String,
+    categories: Vec<String>,
+    author: Option<String>,
+    added: Option<u64>,
+    source: Option<String>,
+    tags: Vec<String>,
+    rating: Option<u8>,
+}
+
+impl QuoteBuilder {
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    pub fn categories<S: Into<String>>(mut self, categories: impl IntoIterator<Item = S>) -> Self {
+        self.categories
+            .extend(categories.into_iter().map(Into::into));
+        self
+    }
+
+    ///Blank authors are treated as no author
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn maybe_author(mut self, author: Option<impl Into<String>>) -> Self {
+        self.author = author.map(Into::into);
+        self
+    }
+
+    ///Blank sources are treated as no source. This doesn't check [`is_valid_source`], so callers should do that first
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn tags<S: Into<String>>(mut self, tags: impl IntoIterator<Item = S>) -> Self {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    pub const fn rating(mut self, rating: u8) -> Self {
+        self.rating = Some(rating);
+        self
+    }
+
+    pub const fn maybe_rating(mut self, rating: Option<u8>) -> Self {
+        self.rating = rating;
+        self
+    }
+
+    ///Overrides the timestamp, which otherwise defaults to when [`QuoteBuilder::build`] is called
+    pub const fn added(mut self, added: u64) -> Self {
+        self.added = Some(added);
+        self
+    }
+
+    pub fn build(self) -> Quote {
+        let mut categories: Vec<String> = vec![];
+        for cat in self.categories {
+            let cat = cat.trim();
+            if !cat.is_empty() && !categories.iter().any(|existing| existing == cat) {
+                categories.push(cat.to_string());
+            }
+        }
+
+        let author = self
+            .author
+            .map(|author| author.trim().to_string())
+            .filter(|author| !author.is_empty());
+
+        let source = self
+            .source
+            .map(|source| source.trim().to_string())
+            .filter(|source| !source.is_empty());
+
+        let tags = parse_tags(&self.tags.join(","));
+
+        let mut quote = Quote(
+            self.text.trim().to_string(),
+            categories,
+            QuoteMeta {
+                author,
+                added: Some(self.added.unwrap_or_else(now_timestamp)),
+                source,
+                tags,
+                rating: None,
+                modified: None,
+                collections: vec![],
+                attribution: None,
+            },
+        );
+        quote.set_rating(self.rating);
+        quote
+    }
+}
+
+///A category name - in strict mode it has to be one of [`ALL_PERMS`], otherwise anything goes as a custom category
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Category(String);
+
+impl Category {
+    pub fn new(name: &str, strict: bool) -> Result<Self, Error> {
+        let name = name.trim();
+        if strict && !ALL_PERMS.iter().any(|perm| perm == name) {
+            Err(Error::UnknownCategory(name.to_string()))
+        } else {
+            Ok(Self(name.to_string()))
+        }
+    }
+
+    #[must_use]
+    pub fn custom(name: &str) -> Self {
+        Self(name.trim().to_string())
+    }
+
+    #[must_use]
+    pub fn is_known(&self) -> bool {
+        ALL_PERMS.contains(&self.0)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Category {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s, true)
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Category> for String {
+    fn from(cat: Category) -> Self {
+        cat.0
+    }
+}
 
 impl Eq for Quote {}
 
@@ -99,3 +756,264 @@ impl PartialEq<&Self> for Quote {
         self.1 == other.1 && self.0 == other.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_defaults() {
+        let before = now_timestamp();
+        let quote = Quote::builder("Hello").build();
+        assert_eq!(quote.0, "Hello");
+        assert!(quote.1.is_empty());
+        assert_eq!(quote.author(), None);
+        assert_eq!(quote.rating(), None);
+        assert!(quote.tags().is_empty());
+        let added = quote.2.added.unwrap();
+        assert!((before..=now_timestamp()).contains(&added));
+    }
+
+    #[test]
+    fn builder_normalizes_its_inputs() {
+        let quote = Quote::builder("  Hello  ")
+            .category(" Stella ")
+            .category("Stella")
+            .category("   ")
+            .categories(["Esme", "Stella"])
+            .author("  ")
+            .source("  ")
+            .tags(["#fog", " fog", ""])
+            .rating(9)
+            .added(42)
+            .build();
+        assert_eq!(quote.0, "Hello");
+        assert_eq!(quote.1, ["Stella", "Esme"]);
+        assert_eq!(quote.author(), None);
+        assert_eq!(quote.source(), None);
+        assert_eq!(quote.tags(), ["fog"]);
+        assert_eq!(quote.rating(), Some(MAX_RATING));
+        assert_eq!(quote.2.added, Some(42));
+
+        let quote = Quote::builder("Hi").author(" Anne ").rating(0).build();
+        assert_eq!(quote.author(), Some("Anne"));
+        assert_eq!(quote.rating(), None);
+    }
+
+    #[test]
+    fn normalize_text_straightens_curly_quotes() {
+        assert_eq!(
+            normalize_text("\u{201C}It\u{2019}s\u{201D} \u{2018}fine\u{2019}"),
+            "\"It's\" 'fine'"
+        );
+    }
+
+    #[test]
+    fn normalize_text_collapses_whitespace() {
+        assert_eq!(normalize_text("  a\u{A0}b   c\n\td  "), "a b c d");
+        assert_eq!(normalize_text(" \u{A0} "), "");
+    }
+
+    #[test]
+    fn normalize_text_leaves_dashes_alone() {
+        assert_eq!(
+            normalize_text("wait \u{2014} no - yes"),
+            "wait \u{2014} no - yes"
+        );
+    }
+
+    #[test]
+    fn category_matching_ignores_case_and_whitespace() {
+        let quote = Quote::new("Hello", vec![" Stella".to_string(), "ESME".to_string()]);
+        assert!(quote.matches_category("stella "));
+        assert!(quote.matches_category("Esme"));
+        assert!(!quote.matches_category("Arthur"));
+
+        assert!(quote.matches_any(&["Arthur", "esme"]));
+        assert!(!quote.matches_any::<&str>(&[]));
+        assert!(quote.matches_all(&["stella", "esme"]));
+        assert!(!quote.matches_all(&["stella", "Arthur"]));
+        assert!(quote.matches_all::<&str>(&[]));
+    }
+
+    #[test]
+    fn format_date_reverses_parse_date() {
+        for date in [
+            "1970-01-01",
+            "2000-02-29",
+            "2024-01-31",
+            "2024-12-31",
+            "2100-03-01",
+        ] {
+            assert_eq!(format_date(parse_date(date).unwrap()), date);
+        }
+        //any time during the day is the same date
+        assert_eq!(
+            format_date(parse_date("2024-01-31").unwrap() + 86_399),
+            "2024-01-31"
+        );
+    }
+
+    #[test]
+    fn punctuation_rules() {
+        let both = PunctuationRules {
+            capitalize: true,
+            terminal_period: true,
+        };
+        for (text, expected) in [
+            ("hello there", "Hello there."),
+            ("  hello there  ", "Hello there."),
+            (
+                "\u{201C}hello there\u{201D}",
+                "\u{201C}Hello there\u{201D}.",
+            ),
+            ("\"hello there.\"", "\"Hello there.\""),
+            ("what now?", "What now?"),
+            ("iPhone time", "iPhone time."),
+            ("...and so on", "...and so on."),
+            ("\u{2026}and so on", "\u{2026}and so on."),
+            ("and so\u{2026}", "And so\u{2026}"),
+            ("a list:", "A list:"),
+            ("wait -", "Wait -"),
+            ("\u{e9}t\u{e9}", "\u{c9}t\u{e9}."),
+            ("42", "42."),
+            ("", ""),
+            ("   ", ""),
+        ] {
+            assert_eq!(apply_punctuation_rules(text, both), expected, "{text:?}");
+        }
+
+        assert_eq!(
+            apply_punctuation_rules(" hello ", PunctuationRules::default()),
+            "hello"
+        );
+        assert_eq!(
+            apply_punctuation_rules(
+                "hello",
+                PunctuationRules {
+                    capitalize: true,
+                    terminal_period: false,
+                }
+            ),
+            "Hello"
+        );
+        //quote marks alone aren't a sentence to finish
+        assert_eq!(apply_punctuation_rules("\"\"", both), "\"\"");
+    }
+
+    #[test]
+    fn truncating_keeps_graphemes_whole() {
+        assert_eq!(truncate_graphemes("short", 5), "short");
+        assert_eq!(truncate_graphemes("shorter", 5), "short…");
+        assert_eq!(truncate_graphemes("to be or", 6), "to be…");
+        assert_eq!(truncate_graphemes("", 0), "");
+        assert_eq!(truncate_graphemes("a", 0), "…");
+
+        //a family emoji is several chars joined together
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(
+            truncate_graphemes(&format!("{family}{family}"), 1),
+            format!("{family}…")
+        );
+        //an e followed by a combining acute accent
+        assert_eq!(
+            truncate_graphemes("e\u{301}e\u{301}e", 2),
+            "e\u{301}e\u{301}…"
+        );
+        assert_eq!(
+            truncate_graphemes("e\u{301}e\u{301}", 2),
+            "e\u{301}e\u{301}"
+        );
+    }
+
+    #[test]
+    fn same_quote_ignores_order_and_tidying() {
+        let cats =
+            |names: &[&str]| -> Vec<String> { names.iter().map(ToString::to_string).collect() };
+        assert!(same_categories(
+            &cats(&["Stella", "Esme"]),
+            &cats(&[" esme", "STELLA"])
+        ));
+        assert!(same_categories(
+            &cats(&["Stella", "stella"]),
+            &cats(&["Stella"])
+        ));
+        assert!(!same_categories(
+            &cats(&["Stella"]),
+            &cats(&["Stella", "Esme"])
+        ));
+        assert!(same_categories(&[], &[]));
+
+        let quote = Quote::new("\u{201C}Fog\u{201D}  everywhere", cats(&["Stella", "Esme"]));
+        assert!(quote.same_quote(&Quote::new(
+            "\"Fog\" everywhere ",
+            cats(&["esme", "stella"])
+        )));
+        assert!(!quote.same_quote(&Quote::new("\"Fog\" everywhere", cats(&["Stella"]))));
+        assert!(!quote.same_quote(&Quote::new("\"fog\" everywhere", cats(&["Stella", "Esme"]))));
+        //`==` wants the text exactly, but not the categories in order
+        assert_ne!(
+            quote,
+            Quote::new("\"Fog\" everywhere", cats(&["Esme", "Stella"]))
+        );
+        assert_eq!(
+            quote,
+            Quote::new("\u{201C}Fog\u{201D}  everywhere", cats(&["Esme", "Stella"]))
+        );
+    }
+
+    #[test]
+    fn absorbing_unions_by_category_key() {
+        let mut quote = Quote::builder("Fog")
+            .categories(["Stella"])
+            .tag("grey")
+            .build();
+        quote.2.collections = vec!["Openers".to_string()];
+        let mut other = Quote::builder("Other words")
+            .categories(["stella ", "Esme"])
+            .tags(["Grey", "damp"])
+            .author("Anne")
+            .build();
+        other.2.collections = vec![" openers".to_string(), "Weather".to_string()];
+
+        assert!(quote.absorb(&other));
+        assert_eq!(quote.0, "Fog");
+        assert_eq!(quote.1, ["Stella", "Esme"]);
+        assert_eq!(quote.tags(), ["grey", "damp"]);
+        assert_eq!(quote.collections(), ["Openers", "Weather"]);
+        //only the lists are touched
+        assert_eq!(quote.author(), None);
+        assert_eq!(quote.modified(), None);
+
+        assert!(!quote.absorb(&other));
+    }
+
+    #[test]
+    fn wrapping_text() {
+        assert_eq!(
+            wrap_text("the only way to do great work", 11),
+            "the only\nway to do\ngreat work"
+        );
+        assert_eq!(wrap_text("exactly ten", 11), "exactly ten");
+        assert_eq!(wrap_text("a extraordinarily b", 5), "a\nextraordinarily\nb");
+        assert_eq!(wrap_text("left   as it is", 0), "left   as it is");
+        //existing line breaks stay, and blank lines are kept empty
+        assert_eq!(wrap_text("one two\n  \nthree", 3), "one\ntwo\n\nthree");
+        //graphemes, not bytes
+        assert_eq!(
+            wrap_text("\u{e9}\u{e9}\u{e9} \u{e9}\u{e9}\u{e9}", 7),
+            "\u{e9}\u{e9}\u{e9} \u{e9}\u{e9}\u{e9}"
+        );
+    }
+
+    #[test]
+    fn wrapping_keeps_indentation() {
+        assert_eq!(
+            wrap_text("Roses are red\n    violets are blue", 12),
+            "Roses are\nred\n    violets\n    are blue"
+        );
+        assert_eq!(wrap_text("\tindented", 20), "\tindented");
+        //the indent is kept even if it leaves no room
+        assert_eq!(wrap_text("    word word", 5), "    word\n    word");
+    }
+}
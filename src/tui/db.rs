@@ -5,10 +5,7 @@ use english_quotes::{
 };
 use tui::widgets::ListState;
 
-pub fn get_quote(
-    category_state: &mut ListState,
-    item_state: &mut ListState,
-) -> Result<Quote, Error> {
+pub fn get_quote(category_state: &ListState, item_state: &ListState) -> Result<Quote, Error> {
     raw_get_quote(
         category_state.selected().expect("quote type selected"),
         item_state.selected().unwrap_or_default(),
@@ -23,8 +23,11 @@ use crossterm::{
 };
 use english_quotes::{
     db::{add_quote_to_db, get_quote_by_content, read_db, sort_list},
-    quote::{Quote, ALL_PERMS},
-    utils::{exports::export, MenuItem},
+    quote::{PunctuationRules, Quote, ALL_PERMS},
+    utils::{
+        exports::{export, ExportFormat, ExportOptions, FieldSet},
+        MenuItem,
+    },
 };
 use std::{
     sync::mpsc,
@@ -40,7 +43,7 @@ use tui::{
 };
 
 //based off https://blog.logrocket.com/rust-and-tui-building-a-command-line-interface-in-rust/
-///But now much farther along than that project ever was
+//But now much farther along than that project ever was
 
 #[allow(clippy::too_many_lines)]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -75,7 +78,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
     terminal.clear()?;
 
-    let menu_titles = vec!["Home", "Quotes", "Entry", "Find"];
+    let menu_titles = ["Home", "Quotes", "Entry", "Find"];
     let mut active_menu_item = MenuItem::Home;
 
     let mut main_category_state = default_state();
@@ -86,6 +89,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut find_quote_list = vec![];
 
     let mut current_input = String::new();
+    //why the last quote entered couldn't be added, cleared once the input changes
+    let mut entry_error: Option<String> = None;
 
     //region ui stuff that isn't re-allocated
     let chunks = Layout::default()
@@ -162,7 +167,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     rect.render_widget(right, vertical_menu_chunk[1]);
                 }
                 MenuItem::Entry => {
-                    let (types, entry) = render_entry(current_input.as_str());
+                    let (types, entry) =
+                        render_entry(current_input.as_str(), entry_error.as_deref());
                     rect.render_stateful_widget(
                         types,
                         vertical_menu_chunk[0],
@@ -182,11 +188,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
                 MenuItem::QuoteCategory => {
                     let q = ALL_PERMS[main_category_state.selected().expect("quote type selected")]
-                        .to_string();
+                        .clone();
                     let db = read_db().expect("can read db");
                     let qs: Vec<_> = db
                         .into_iter()
-                        .filter(|quote| quote.1.contains(&q))
+                        .filter(|quote| quote.matches_category(&q))
                         .map(|quote| ListItem::new(format!("{:?}    |    {}", quote.1, quote.0)))
                         .collect();
 
@@ -216,18 +222,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     .map(|index| ALL_PERMS[index].clone())
                                     .collect();
 
-                                add_quote_to_db(
-                                    Quote(current_input.trim().to_string(), indices),
+                                //invalid quotes (empty, duplicates etc) are left in the input box to be fixed
+                                match add_quote_to_db(
+                                    Quote::builder(current_input.as_str())
+                                        .categories(indices)
+                                        .build(),
                                     None,
-                                )
-                                .expect("cannot add quote");
-                                current_input.clear();
-                            }
-                            KeyCode::Backspace => {
-                                if !current_input.is_empty() {
-                                    current_input.remove(current_input.len() - 1);
+                                    false,
+                                    PunctuationRules::default(),
+                                ) {
+                                    Ok(_) => {
+                                        current_input.clear();
+                                        entry_error = None;
+                                    }
+                                    Err(err) => entry_error = Some(err.to_string()),
                                 }
                             }
+                            KeyCode::Backspace if !current_input.is_empty() => {
+                                current_input.remove(current_input.len() - 1);
+                                entry_error = None;
+                            }
                             KeyCode::Tab => {
                                 if let Some(highlighted) = entry_category_state.highlighted() {
                                     entry_category_state.select(highlighted);
@@ -258,6 +272,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             }
                             KeyCode::Char(char) => {
                                 current_input.push(char);
+                                entry_error = None;
                             }
                             _ => {}
                         }
@@ -268,11 +283,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         let amt_quotes = {
                             let q = ALL_PERMS
                                 [main_category_state.selected().expect("quote type selected")]
-                            .to_string();
+                            .clone();
                             read_db()
                                 .expect("can read db")
                                 .iter()
-                                .filter(|quote| quote.1.contains(&q))
+                                .filter(|quote| quote.matches_category(&q))
                                 .count()
                         };
                         match event.code {
@@ -285,11 +300,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 active_menu_item = MenuItem::Quotes;
                             }
                             KeyCode::Enter => {
-                                let quote_selected = get_quote(
-                                    &mut main_category_state,
-                                    &mut quote_single_category_state,
-                                )
-                                .unwrap();
+                                let quote_selected =
+                                    get_quote(&main_category_state, &quote_single_category_state)
+                                        .unwrap();
 
                                 entry_category_state.clear();
                                 entry_category_state.select_multiple(&quote_selected.1);
@@ -303,11 +316,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 active_menu_item = MenuItem::Entry;
                             }
                             KeyCode::Char('d') => {
-                                let quote = get_quote(
-                                    &mut main_category_state,
-                                    &mut quote_single_category_state,
-                                )
-                                .unwrap();
+                                let quote =
+                                    get_quote(&main_category_state, &quote_single_category_state)
+                                        .unwrap();
                                 remove_quote_by_quote(&mut quote_single_category_state, &quote)
                                     .expect("cannot remove quote");
                             }
@@ -362,7 +373,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             active_menu_item = MenuItem::Find;
                         }
                         KeyCode::Char('r') => {
-                            let _hello = export();
+                            let _hello = export(
+                                ExportFormat::Markdown,
+                                ExportOptions::default(),
+                                FieldSet::default(),
+                            );
                         }
                         _ => {}
                     },
@@ -376,10 +391,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 find_quote_state.select(Some(0));
                                 current_input.push(char);
                             }
-                            KeyCode::Backspace => {
-                                if !current_input.is_empty() {
-                                    current_input.remove(current_input.len() - 1);
-                                }
+                            KeyCode::Backspace if !current_input.is_empty() => {
+                                current_input.remove(current_input.len() - 1);
                             }
                             KeyCode::Up => up_arrow(&mut find_quote_state, find_quote_list.len()),
                             KeyCode::Down => {
@@ -429,7 +442,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    sort_list(None).unwrap();
+    sort_list(None)?;
 
     Ok(())
 }
@@ -45,7 +45,7 @@ impl MultipleListState {
         self.highlighted = None;
     }
 
-    pub fn highlight(&mut self, index: Option<usize>) {
+    pub const fn highlight(&mut self, index: Option<usize>) {
         self.highlighted = index;
     }
 
@@ -72,7 +72,7 @@ pub struct MultipleList<'a> {
 }
 
 impl<'a> MultipleList<'a> {
-    pub fn new<T>(items: T) -> MultipleList<'a>
+    pub fn new<T>(items: T) -> Self
     where
         T: Into<Vec<MultipleListItem<'a>>>,
     {
@@ -89,59 +89,56 @@ impl<'a> MultipleList<'a> {
     }
 
     #[allow(clippy::missing_const_for_fn)]
-    pub fn block(mut self, block: Block<'a>) -> MultipleList<'a> {
+    pub fn block(mut self, block: Block<'a>) -> Self {
         self.block = Some(block);
         self
     }
 
     #[allow(dead_code)]
-    pub const fn style(mut self, style: Style) -> MultipleList<'a> {
+    pub const fn style(mut self, style: Style) -> Self {
         self.style = style;
         self
     }
 
     #[allow(dead_code)]
-    pub const fn highlight_symbol(mut self, highlight_symbol: &'a str) -> MultipleList<'a> {
+    pub const fn highlight_symbol(mut self, highlight_symbol: &'a str) -> Self {
         self.highlight_symbol = Some(highlight_symbol);
         self
     }
 
-    pub const fn both_style(mut self, style: Style) -> MultipleList<'a> {
+    pub const fn both_style(mut self, style: Style) -> Self {
         self.both_style = style;
         self
     }
 
-    pub const fn non_select_style(mut self, style: Style) -> MultipleList<'a> {
+    pub const fn non_select_style(mut self, style: Style) -> Self {
         self.non_select_style = style;
         self
     }
 
-    pub const fn highlight_style(mut self, style: Style) -> MultipleList<'a> {
+    pub const fn highlight_style(mut self, style: Style) -> Self {
         self.highlight_style = style;
         self
     }
 
     #[allow(dead_code)]
-    pub const fn start_corner(mut self, corner: Corner) -> MultipleList<'a> {
+    pub const fn start_corner(mut self, corner: Corner) -> Self {
         self.start_corner = corner;
         self
     }
 }
 
-impl<'a> StatefulWidget for MultipleList<'a> {
+impl StatefulWidget for MultipleList<'_> {
     type State = MultipleListState;
 
     #[allow(clippy::cast_possible_truncation)]
     fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         buf.set_style(area, self.style);
-        let list_area = match self.block.take() {
-            Some(b) => {
-                let inner_area = b.inner(area);
-                b.render(area, buf);
-                inner_area
-            }
-            None => area,
-        };
+        let list_area = self.block.take().map_or(area, |b| {
+            let inner_area = b.inner(area);
+            b.render(area, buf);
+            inner_area
+        });
 
         if list_area.width < 1 || list_area.height < 1 {
             return;
@@ -214,7 +211,7 @@ impl<'a> StatefulWidget for MultipleList<'a> {
             buf.set_style(area, item_style);
 
             let is_selected = state.selected.contains(&i);
-            let is_highlighted = state.highlighted.map_or(false, |x| x == i);
+            let is_highlighted = state.highlighted == Some(i);
 
             let elem_x = if has_selection {
                 let symbol = if is_selected || is_highlighted {
@@ -251,7 +248,7 @@ pub struct MultipleListItem<'a> {
 }
 
 impl<'a> MultipleListItem<'a> {
-    pub fn new<T>(content: T) -> MultipleListItem<'a>
+    pub fn new<T>(content: T) -> Self
     where
         T: Into<Text<'a>>,
     {
@@ -262,7 +259,7 @@ impl<'a> MultipleListItem<'a> {
     }
 
     #[allow(dead_code)]
-    pub const fn style(mut self, style: Style) -> MultipleListItem<'a> {
+    pub const fn style(mut self, style: Style) -> Self {
         self.style = style;
         self
     }
@@ -45,11 +45,11 @@ pub fn render_quotes<'a>(quotes_list_state: &ListState) -> (List<'a>, Table<'a>)
         let selected_type = ALL_PERMS[quotes_list_state
             .selected()
             .expect("there is always a selected type in the types list")]
-        .to_string();
+        .clone();
 
         let rows: Vec<_> = quotes_list
             .into_iter()
-            .filter(|quote| quote.1.contains(&selected_type))
+            .filter(|quote| quote.matches_category(&selected_type))
             .map(|quote| {
                 Row::new(vec![
                     Span::raw(format!("{:?}", quote.1)),
@@ -79,7 +79,11 @@ pub fn render_quotes<'a>(quotes_list_state: &ListState) -> (List<'a>, Table<'a>)
     (list, quote_detail)
 }
 
-pub fn render_entry(current_input: &str) -> (MultipleList, Paragraph) {
+///`error` is why the last quote couldn't be added, shown under the input with the box outlined in red
+pub fn render_entry<'a>(
+    current_input: &'a str,
+    error: Option<&'a str>,
+) -> (MultipleList<'a>, Paragraph<'a>) {
     let block = default_block().title("Quote Type");
 
     let items: Vec<MultipleListItem> = ALL_PERMS
@@ -112,15 +116,20 @@ pub fn render_entry(current_input: &str) -> (MultipleList, Paragraph) {
         Spans::from(vec![Span::raw("")]),
         Spans::from(vec![Span::raw(current_input)]),
         Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![coloured_span(error.unwrap_or_default(), Color::Red)]),
     ])
     .alignment(Alignment::Center)
-    .block(default_block().title("Quote Entry"))
+    .block(
+        default_block()
+            .title("Quote Entry")
+            .border_style(error.map_or_else(Style::default, |_| Style::default().fg(Color::Red))),
+    )
     .wrap(Wrap { trim: true });
 
     (list, para)
 }
 
-pub fn render_finder(current_input: &str) -> (Paragraph, List, Vec<Quote>) {
+pub fn render_finder(current_input: &str) -> (Paragraph<'_>, List<'_>, Vec<Quote>) {
     let db = read_db().unwrap_or_default();
     let db_len = db.len();
     let items: Vec<Quote> = db
@@ -141,7 +150,7 @@ pub fn render_finder(current_input: &str) -> (Paragraph, List, Vec<Quote>) {
             .map(|quote| ListItem::new(Span::from(format!("{} - {:?}", quote.0, quote.1))))
             .collect::<Vec<ListItem>>(),
     )
-    .block(default_block().title(format!("Search Results ({}/{}):", items_len, db_len)))
+    .block(default_block().title(format!("Search Results ({items_len}/{db_len}):")))
     .highlight_style(default_style());
 
     let para = Paragraph::new(vec![
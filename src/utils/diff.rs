@@ -0,0 +1,180 @@
+//!Word-by-word differences between two versions of a quote, eg. to check an edit before saving it
+
+use std::mem::discriminant;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffSpan {
+    ///Words in both versions
+    Same(String),
+    ///Words only in the new version
+    Added(String),
+    ///Words only in the old version
+    Removed(String),
+}
+
+impl DiffSpan {
+    #[must_use]
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Same(text) | Self::Added(text) | Self::Removed(text) => text,
+        }
+    }
+}
+
+///Adds `span` onto the end of the last one if they're the same kind, so runs of words stay together
+fn push(spans: &mut Vec<DiffSpan>, span: DiffSpan) {
+    if let Some(last) = spans.last_mut() {
+        if discriminant(last) == discriminant(&span) {
+            let (DiffSpan::Same(text) | DiffSpan::Added(text) | DiffSpan::Removed(text)) = last;
+            text.push(' ');
+            text.push_str(span.text());
+            return;
+        }
+    }
+    spans.push(span);
+}
+
+///Lines `old` and `new` up word by word, going by their longest common subsequence.
+///
+///Words are split on whitespace and joined back up with single spaces, so a change that's only whitespace doesn't show. Where words were swapped out, the removed ones come before the added ones.
+#[must_use]
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffSpan> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+
+    //edits are usually small, so leaving the unchanged start and end out keeps the table below small too
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut spans = vec![];
+    for word in &old[..prefix] {
+        push(&mut spans, DiffSpan::Same((*word).to_string()));
+    }
+    diff_middle(&mut spans, old_middle, new_middle);
+    for word in &old[old.len() - suffix..] {
+        push(&mut spans, DiffSpan::Same((*word).to_string()));
+    }
+    spans
+}
+
+fn diff_middle(spans: &mut Vec<DiffSpan>, old: &[&str], new: &[&str]) {
+    //`common[i][j]` is how long the longest common subsequence of `old[i..]` and `new[j..]` is
+    let mut common = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(spans, DiffSpan::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            push(spans, DiffSpan::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            push(spans, DiffSpan::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same(text: &str) -> DiffSpan {
+        DiffSpan::Same(text.to_string())
+    }
+
+    fn added(text: &str) -> DiffSpan {
+        DiffSpan::Added(text.to_string())
+    }
+
+    fn removed(text: &str) -> DiffSpan {
+        DiffSpan::Removed(text.to_string())
+    }
+
+    #[test]
+    fn identical_text_is_all_the_same() {
+        assert_eq!(
+            word_diff("the fog is thick", "the fog is thick"),
+            [same("the fog is thick")]
+        );
+        assert_eq!(word_diff("", ""), []);
+    }
+
+    #[test]
+    fn insertions_and_deletions() {
+        assert_eq!(
+            word_diff("the fog is thick", "the fog is very thick"),
+            [same("the fog is"), added("very"), same("thick")]
+        );
+        assert_eq!(
+            word_diff("the fog is very thick", "the fog is thick"),
+            [same("the fog is"), removed("very"), same("thick")]
+        );
+        assert_eq!(word_diff("", "fog"), [added("fog")]);
+        assert_eq!(word_diff("fog", ""), [removed("fog")]);
+    }
+
+    #[test]
+    fn replaced_words_are_removed_then_added() {
+        assert_eq!(
+            word_diff("the fog is thick", "the mist is thin"),
+            [
+                same("the"),
+                removed("fog"),
+                added("mist"),
+                same("is"),
+                removed("thick"),
+                added("thin"),
+            ]
+        );
+        assert_eq!(
+            word_diff("a b c", "x y z"),
+            [removed("a b c"), added("x y z")]
+        );
+    }
+
+    #[test]
+    fn whitespace_changes_dont_show() {
+        assert_eq!(
+            word_diff("the  fog\nis thick ", " the fog is\tthick"),
+            [same("the fog is thick")]
+        );
+    }
+
+    #[test]
+    fn repeated_words() {
+        assert_eq!(word_diff("a a", "a"), [same("a"), removed("a")]);
+        assert_eq!(word_diff("a", "a a"), [same("a"), added("a")]);
+        assert_eq!(
+            word_diff("a b a", "b a b"),
+            [removed("a"), same("b a"), added("b")]
+        );
+    }
+
+    #[test]
+    fn the_middle_is_diffed_on_its_own() {
+        let mut spans = vec![];
+        diff_middle(&mut spans, &["fog", "is"], &["is", "fog"]);
+        assert_eq!(spans, [removed("fog"), same("is"), added("fog")]);
+    }
+}
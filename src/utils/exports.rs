@@ -1,36 +1,1655 @@
 use crate::{
-    db::read_db,
-    quote::{FileType, ALL_PERMS},
-    utils::Error,
+    db::{all_collections, read_db},
+    quote::{
+        category_key, format_date, format_quote, parse_date, wrap_text, FileType, Quote, ALL_PERMS,
+        MAX_RATING,
+    },
+    utils::{template::QuoteTemplate, Error},
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
 };
-use std::{fs::File, io::Write};
 
-#[allow(clippy::missing_panics_doc)]
-pub fn export() -> Result<(), Error> {
-    let list = read_db()?;
-    let mut f =
-        File::create(FileType::Export.get_location()).expect("need to be able to open the file");
-    writeln!(f, "# Jack's WIB Quotes\n").map(|_| ())?;
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("unable to write the export to {}", path.display())]
+    NotWritable { path: PathBuf },
+    #[error("only wrote {} of {} files, failed to write {}", written.len(), written.len() + failed.len(), failed.iter().map(|(path, err)| format!("{} ({err})", path.display())).collect::<Vec<_>>().join(", "))]
+    Partial {
+        written: Vec<PathBuf>,
+        ///Each file that couldn't be written, and why
+        failed: Vec<(PathBuf, String)>,
+    },
+}
 
-    for perm in ALL_PERMS.iter() {
-        let perm = perm.to_string();
-        writeln!(f, "## {}", perm).map(|_| ())?;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Csv,
+    ///A single HTML page with the quotes embedded and a search box, which works offline
+    HtmlApp,
+    ///One quote per entry, as `text - author (attribution) [categories]`
+    PlainText(Separator),
+    ///Each quote as JSON on its own line, streamed out rather than built up in memory - see [`write_json_lines`]
+    JsonLines,
+}
 
-        let new_list = list
-            .clone()
-            .into_iter()
-            .filter(|quote| quote.1.contains(&perm));
+impl ExportFormat {
+    #[must_use]
+    pub fn exporter(self, options: ExportOptions, fields: FieldSet) -> Box<dyn Exporter> {
+        let ExportOptions {
+            grouping,
+            wrap_width,
+            template,
+        } = options;
+        match self {
+            Self::Markdown => Box::new(MarkdownExporter {
+                grouping,
+                fields,
+                wrap_width,
+            }),
+            Self::Csv => Box::new(CsvExporter { fields }),
+            Self::HtmlApp => Box::new(HtmlAppExporter { grouping, fields }),
+            Self::PlainText(separator) => Box::new(PlainTextExporter {
+                separator,
+                template,
+                fields,
+                wrap_width,
+            }),
+            Self::JsonLines => Box::new(JsonLinesExporter { fields }),
+        }
+    }
+
+    pub const ALL: [Self; 5] = [
+        Self::Markdown,
+        Self::Csv,
+        Self::HtmlApp,
+        Self::PlainText(Separator::Newline),
+        Self::JsonLines,
+    ];
+
+    ///Whether `self` and `other` are the same format, ignoring options like the plain text separator
+    #[must_use]
+    pub fn same_kind(self, other: Self) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+
+    #[must_use]
+    pub const fn file_type(self) -> FileType {
+        match self {
+            Self::Markdown => FileType::Export,
+            Self::Csv => FileType::CsvExport,
+            Self::HtmlApp => FileType::HtmlApp,
+            Self::PlainText(_) => FileType::PlainTextExport,
+            Self::JsonLines => FileType::JsonLines,
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            "html" => Ok(Self::HtmlApp),
+            "text" | "txt" => Ok(Self::PlainText(Separator::default())),
+            "jsonl" | "ndjson" => Ok(Self::JsonLines),
+            _ => Err(format!(
+                "unknown export format {s}, expected markdown, csv, html, text or jsonl"
+            )),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Markdown => write!(f, "Markdown"),
+            Self::Csv => write!(f, "CSV"),
+            Self::HtmlApp => write!(f, "HTML (searchable)"),
+            Self::PlainText(_) => write!(f, "Plain Text"),
+            Self::JsonLines => write!(f, "JSON Lines"),
+        }
+    }
+}
+
+///What goes between quotes in a plain text export
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Separator {
+    #[default]
+    Newline,
+    BlankLine,
+    ///A line with just `---` on it
+    Divider,
+}
+
+impl Separator {
+    pub const ALL: [Self; 3] = [Self::Newline, Self::BlankLine, Self::Divider];
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Newline => "\n",
+            Self::BlankLine => "\n\n",
+            Self::Divider => "\n---\n",
+        }
+    }
+
+    ///Whether quotes split by this can be told apart from line breaks within `entry`
+    fn fits(self, entry: &str) -> bool {
+        match self {
+            Self::Newline => !entry.contains('\n'),
+            Self::BlankLine => !entry.lines().any(|line| line.trim().is_empty()),
+            Self::Divider => !entry.lines().any(|line| line.trim() == "---"),
+        }
+    }
+}
+
+impl FromStr for Separator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "newline" | "line" => Ok(Self::Newline),
+            "blank" | "blank-line" => Ok(Self::BlankLine),
+            "divider" | "---" => Ok(Self::Divider),
+            _ => Err(format!(
+                "unknown separator {s}, expected newline, blank or divider"
+            )),
+        }
+    }
+}
+
+impl Display for Separator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Newline => write!(f, "New Line"),
+            Self::BlankLine => write!(f, "Blank Line"),
+            Self::Divider => write!(f, "---"),
+        }
+    }
+}
+
+///How quotes are sectioned in formats with headings - CSV is always one flat list, and the HTML app only sections by author
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ExportGrouping {
+    #[default]
+    Category,
+    Author,
+    ///A section per collection - quotes in several collections turn up in each, and ones in none are left out
+    Collection,
+}
+
+impl ExportGrouping {
+    pub const ALL: [Self; 3] = [Self::Category, Self::Author, Self::Collection];
+}
+
+impl FromStr for ExportGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "category" => Ok(Self::Category),
+            "author" => Ok(Self::Author),
+            "collection" => Ok(Self::Collection),
+            _ => Err(format!(
+                "unknown grouping {s}, expected category, author or collection"
+            )),
+        }
+    }
+}
+
+impl Display for ExportGrouping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Category => write!(f, "By Category"),
+            Self::Author => write!(f, "By Author"),
+            Self::Collection => write!(f, "By Collection"),
+        }
+    }
+}
+
+///How an export is laid out, whichever [fields](FieldSet) go into it
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ExportOptions {
+    pub grouping: ExportGrouping,
+    ///The column Markdown and plain text exports are hard-wrapped at, if any - see [`wrap_text`]
+    pub wrap_width: Option<usize>,
+    ///How each quote in a plain text export is written out - see [`format_quote`]
+    pub template: QuoteTemplate,
+}
+
+///Which parts of each quote end up in an export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct FieldSet {
+    pub text: bool,
+    pub author: bool,
+    pub categories: bool,
+    ///Whether each quote's [`Attribution`](crate::quote::Attribution) is noted, for the ones that have one
+    pub attribution: bool,
+    ///When each quote was added and last edited, as `YYYY-MM-DD` dates - see [`format_date`]
+    pub timestamps: bool,
+    pub tags: bool,
+    ///Where each quote came from, for the ones that say
+    pub source: bool,
+    ///Out of [`MAX_RATING`], for the rated quotes
+    pub rating: bool,
+    ///Which collections each quote is in - without them, grouping by collection gives one flat list
+    pub collections: bool,
+}
+
+impl Default for FieldSet {
+    fn default() -> Self {
+        Self {
+            text: true,
+            author: true,
+            categories: true,
+            attribution: true,
+            timestamps: true,
+            tags: true,
+            source: true,
+            rating: true,
+            collections: true,
+        }
+    }
+}
+
+pub fn export(format: ExportFormat, options: ExportOptions, fields: FieldSet) -> Result<(), Error> {
+    export_to(format.file_type().get_location(), format, options, fields)
+}
+
+pub fn export_to(
+    path: impl AsRef<Path>,
+    format: ExportFormat,
+    options: ExportOptions,
+    fields: FieldSet,
+) -> Result<(), Error> {
+    export_quotes_to(path, &read_db()?, format, options, fields)
+}
+
+///Like [`export_to`], but exports `list` exactly as given rather than the whole database.
+///
+///CSV keeps the order of `list`. Markdown and the HTML app keep it within each heading, although grouping by author sorts each author's quotes by text.
+pub fn export_quotes_to(
+    path: impl AsRef<Path>,
+    list: &[Quote],
+    format: ExportFormat,
+    options: ExportOptions,
+    fields: FieldSet,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+    let not_writable = |err: std::io::Error| -> Error {
+        match err.kind() {
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem | ErrorKind::NotFound => {
+                ExportError::NotWritable {
+                    path: path.to_path_buf(),
+                }
+                .into()
+            }
+            _ => err.into(),
+        }
+    };
+
+    if format == ExportFormat::JsonLines {
+        return write_json_lines(list, fields, File::create(path).map_err(not_writable)?);
+    }
+
+    let mut contents = vec![];
+    export_to_writer(list, &mut contents, format, options, fields)?;
+    std::fs::write(path, contents).map_err(not_writable)
+}
+
+///Writes `list` out in the given format, without touching the database or export files, and flushes `writer`
+pub fn export_to_writer(
+    list: &[Quote],
+    mut writer: impl Write,
+    format: ExportFormat,
+    options: ExportOptions,
+    fields: FieldSet,
+) -> Result<(), Error> {
+    if format == ExportFormat::JsonLines {
+        return write_json_lines(list, fields, writer);
+    }
+    writer.write_all(&format.exporter(options, fields).serialize(list)?)?;
+    //a `BufWriter` would otherwise only flush when dropped, where any error is lost
+    writer.flush()?;
+    Ok(())
+}
+
+///`quote` with anything left out of `fields` cleared, for formats that write out whole quotes or fill in a [`QuoteTemplate`] - the text is left empty rather than taken out
+fn only_fields(quote: &Quote, fields: FieldSet) -> Quote {
+    let mut quote = quote.clone();
+    if !fields.text {
+        quote.0.clear();
+    }
+    if !fields.author {
+        quote.2.author = None;
+    }
+    if !fields.categories {
+        quote.1.clear();
+    }
+    if !fields.attribution {
+        quote.2.attribution = None;
+    }
+    if !fields.timestamps {
+        quote.2.added = None;
+        quote.2.modified = None;
+    }
+    if !fields.tags {
+        quote.2.tags.clear();
+    }
+    if !fields.source {
+        quote.2.source = None;
+    }
+    if !fields.rating {
+        quote.2.rating = None;
+    }
+    if !fields.collections {
+        quote.2.collections.clear();
+    }
+    quote
+}
+
+///Writes each quote in `list` as JSON on its own line, one at a time, so the whole export never has to be in memory at once.
+///
+///Anything left out of `fields` is cleared first (see [`only_fields`]). Read it back with [`read_json_lines`](crate::utils::imports::read_json_lines).
+pub fn write_json_lines(list: &[Quote], fields: FieldSet, writer: impl Write) -> Result<(), Error> {
+    let mut writer = BufWriter::new(writer);
+    for quote in list {
+        if fields == FieldSet::default() {
+            serde_json::to_writer(&mut writer, quote)?;
+        } else {
+            serde_json::to_writer(&mut writer, &only_fields(quote, fields))?;
+        }
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+///A way of turning quotes into a file - see [`ExporterRegistry`] for the built-in ones
+pub trait Exporter {
+    ///Shown in the UI, eg. "Markdown"
+    fn name(&self) -> &str;
+    ///Without the dot, eg. "md"
+    fn extension(&self) -> &str;
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error>;
+
+    ///What [`export_all`] calls the file, after the timestamp
+    fn file_name(&self) -> String {
+        format!("export.{}", self.extension())
+    }
+}
+
+///The whole database, exactly as it's stored
+pub struct JsonExporter;
+
+impl Exporter for JsonExporter {
+    fn name(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(quotes)?)
+    }
+
+    fn file_name(&self) -> String {
+        FileType::Database.get_location().to_string()
+    }
+}
+
+pub struct JsonLinesExporter {
+    pub fields: FieldSet,
+}
+
+impl Exporter for JsonLinesExporter {
+    fn name(&self) -> &'static str {
+        "JSON Lines"
+    }
+
+    fn extension(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error> {
+        let mut contents = vec![];
+        write_json_lines(quotes, self.fields, &mut contents)?;
+        Ok(contents)
+    }
+}
+
+pub struct MarkdownExporter {
+    pub grouping: ExportGrouping,
+    pub fields: FieldSet,
+    pub wrap_width: Option<usize>,
+}
+
+impl Exporter for MarkdownExporter {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error> {
+        let mut contents = vec![];
+        match self.grouping {
+            ExportGrouping::Category => {
+                write_markdown(quotes, self.fields, self.wrap_width, &mut contents)?;
+            }
+            ExportGrouping::Author => {
+                write_markdown_by_author(quotes, self.fields, self.wrap_width, &mut contents)?;
+            }
+            ExportGrouping::Collection => {
+                write_markdown_by_collection(quotes, self.fields, self.wrap_width, &mut contents)?;
+            }
+        }
+        Ok(contents)
+    }
+}
+
+pub struct CsvExporter {
+    pub fields: FieldSet,
+}
+
+impl Exporter for CsvExporter {
+    fn name(&self) -> &'static str {
+        "CSV"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error> {
+        let mut contents = vec![];
+        write_csv(quotes, self.fields, &mut contents)?;
+        Ok(contents)
+    }
+}
+
+pub struct HtmlAppExporter {
+    ///Only [`ExportGrouping::Author`] splits the page into sections - anything else gives one list
+    pub grouping: ExportGrouping,
+    pub fields: FieldSet,
+}
+
+impl Exporter for HtmlAppExporter {
+    fn name(&self) -> &'static str {
+        "HTML"
+    }
+
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error> {
+        let mut contents = vec![];
+        write_html_app(quotes, self.grouping, self.fields, &mut contents)?;
+        Ok(contents)
+    }
+}
+
+pub struct PlainTextExporter {
+    pub separator: Separator,
+    ///Fields left out of `fields` come out as empty placeholders
+    pub template: QuoteTemplate,
+    pub fields: FieldSet,
+    pub wrap_width: Option<usize>,
+}
+
+impl Exporter for PlainTextExporter {
+    fn name(&self) -> &'static str {
+        "Plain Text"
+    }
+
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn serialize(&self, quotes: &[Quote]) -> Result<Vec<u8>, Error> {
+        let mut contents = vec![];
+        write_plain_text(
+            quotes,
+            &self.template,
+            self.fields,
+            self.separator,
+            self.wrap_width,
+            &mut contents,
+        )?;
+        Ok(contents)
+    }
+}
+
+///Exporters by file extension, so there's only ever one per extension
+#[derive(Default)]
+pub struct ExporterRegistry {
+    exporters: BTreeMap<String, Box<dyn Exporter>>,
+}
+
+impl ExporterRegistry {
+    ///JSON, along with every [`ExportFormat`]
+    #[must_use]
+    pub fn with_builtins(options: &ExportOptions, fields: FieldSet) -> Self {
+        let mut registry = Self::default();
+        registry.register(Box::new(JsonExporter));
+        for format in ExportFormat::ALL {
+            registry.register(format.exporter(options.clone(), fields));
+        }
+        registry
+    }
+
+    ///Adds `exporter`, replacing any existing one with the same extension
+    pub fn register(&mut self, exporter: Box<dyn Exporter>) {
+        self.exporters
+            .insert(exporter.extension().to_string(), exporter);
+    }
+
+    #[must_use]
+    pub fn get(&self, extension: &str) -> Option<&dyn Exporter> {
+        self.exporters.get(extension).map(AsRef::as_ref)
+    }
+
+    ///In order of extension
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Exporter> {
+        self.exporters.values().map(AsRef::as_ref)
+    }
+}
+
+///Categories whose quotes are never exported, eg. `private` ones that shouldn't end up in a shared file
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ExportExclusions {
+    pub categories: Vec<String>,
+}
+
+impl ExportExclusions {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    #[must_use]
+    pub fn excludes(&self, quote: &Quote) -> bool {
+        quote.matches_any(&self.categories)
+    }
+
+    ///How many of `list` would be left out
+    #[must_use]
+    pub fn withheld<'a>(&self, list: impl IntoIterator<Item = &'a Quote>) -> usize {
+        list.into_iter()
+            .filter(|quote| self.excludes(quote))
+            .count()
+    }
+
+    ///`list` without the excluded quotes
+    #[must_use]
+    pub fn apply(&self, mut list: Vec<Quote>) -> Vec<Quote> {
+        list.retain(|quote| !self.excludes(quote));
+        list
+    }
+}
+
+///Quotes added within a span of time, for [`export_range`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateRange {
+    ///Seconds since the unix epoch, inclusive
+    pub from: u64,
+    ///Seconds since the unix epoch, inclusive
+    pub to: u64,
+    ///Whether quotes from before timestamps were recorded count as in range
+    pub include_undated: bool,
+}
+
+impl DateRange {
+    ///Every quote added from the start of `from` to the end of `to`, both `YYYY-MM-DD` - see [`parse_date`]
+    #[must_use]
+    pub fn from_dates(from: &str, to: &str, include_undated: bool) -> Option<Self> {
+        let from = parse_date(from)?;
+        let to = parse_date(to)? + 86_399;
+        (from <= to).then_some(Self {
+            from,
+            to,
+            include_undated,
+        })
+    }
+
+    #[must_use]
+    pub fn contains(&self, quote: &Quote) -> bool {
+        quote.2.added.map_or(self.include_undated, |added| {
+            (self.from..=self.to).contains(&added)
+        })
+    }
+}
+
+///Like [`export_quotes_to`], but only with the quotes in `list` that were added within `range`
+pub fn export_range(
+    path: impl AsRef<Path>,
+    list: &[Quote],
+    range: DateRange,
+    format: ExportFormat,
+    options: ExportOptions,
+    fields: FieldSet,
+) -> Result<(), Error> {
+    let list: Vec<Quote> = list
+        .iter()
+        .filter(|quote| range.contains(quote))
+        .cloned()
+        .collect();
+    export_quotes_to(path, &list, format, options, fields)
+}
+
+///Writes all of `db` to `dir` with `exporter`, with the file name starting with `timestamp`, and gives back the path written
+pub fn export_with(
+    exporter: &dyn Exporter,
+    db: &[Quote],
+    dir: impl AsRef<Path>,
+    timestamp: u64,
+) -> Result<PathBuf, Error> {
+    let path = dir
+        .as_ref()
+        .join(format!("{timestamp}-{}", exporter.file_name()));
+    std::fs::write(&path, exporter.serialize(db)?)?;
+    Ok(path)
+}
 
-        for quote in new_list {
-            let index = quote.1.iter().position(|x| x == &perm);
-            let mut new_list = quote.1;
+///Writes all of `db` to `dir` with every built-in [`Exporter`] (see [`ExporterRegistry::with_builtins`]), with every file name starting with `timestamp`, and gives back the paths written.
+///
+///One format failing doesn't stop the others - if anything fails, the error is [`ExportError::Partial`], saying what did get written.
+pub fn export_all(
+    db: &[Quote],
+    dir: impl AsRef<Path>,
+    timestamp: u64,
+) -> Result<Vec<PathBuf>, Error> {
+    let dir = dir.as_ref();
+    let mut written = vec![];
+    let mut failed = vec![];
 
-            new_list.remove(index.unwrap()); //PANIC: can't panic - boom
+    let registry = ExporterRegistry::with_builtins(&ExportOptions::default(), FieldSet::default());
+    for exporter in registry.iter() {
+        match export_with(exporter, db, dir, timestamp) {
+            Ok(path) => written.push(path),
+            Err(err) => failed.push((
+                dir.join(format!("{timestamp}-{}", exporter.file_name())),
+                err.to_string(),
+            )),
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(written)
+    } else {
+        Err(ExportError::Partial { written, failed }.into())
+    }
+}
+
+///Somewhere we can probably write to if the usual export location is locked down - the home directory, or the temp directory if we can't find that
+#[must_use]
+pub fn fallback_export_location(format: ExportFormat) -> PathBuf {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map_or_else(std::env::temp_dir, PathBuf::from)
+        .join(format.file_type().get_location())
+}
+
+///When `quote` was added, and when it was last edited if that was later, eg. `added 2024-01-31, edited 2024-02-02` - `None` for quotes from before timestamps were recorded
+fn describe_dates(quote: &Quote) -> Option<String> {
+    let added = quote
+        .2
+        .added
+        .map(|added| format!("added {}", format_date(added)));
+    let modified = quote
+        .modified()
+        .filter(|&modified| Some(modified) != quote.2.added)
+        .map(|modified| format!("edited {}", format_date(modified)));
+    let dates: Vec<String> = added.into_iter().chain(modified).collect();
+    (!dates.is_empty()).then(|| dates.join(", "))
+}
+
+fn write_markdown_line(
+    f: &mut impl Write,
+    quote: &Quote,
+    other_categories: &[String],
+    fields: FieldSet,
+    wrap_width: Option<usize>,
+) -> std::io::Result<()> {
+    let mut line = vec![];
+    if fields.text {
+        write!(line, " *{}*", quote.0)?;
+    }
+    if fields.author {
+        if let Some(author) = quote.author() {
+            write!(line, " - {author}")?;
+        }
+    }
+    if let Some(attribution) = quote.attribution().filter(|_| fields.attribution) {
+        write!(line, " ({})", attribution.to_string().to_lowercase())?;
+    }
+    if let Some(source) = quote.source().filter(|_| fields.source) {
+        write!(line, " (from {source})")?;
+    }
+    if let Some(rating) = quote.rating().filter(|_| fields.rating) {
+        write!(line, " ({rating}/{MAX_RATING})")?;
+    }
+    if let Some(dates) = describe_dates(quote).filter(|_| fields.timestamps) {
+        write!(line, " ({dates})")?;
+    }
+    if fields.tags {
+        for tag in quote.tags() {
+            write!(line, " #{tag}")?;
+        }
+    }
+    if fields.categories {
+        write!(line, ", related to **{other_categories:?}**")?;
+    }
+    if fields.collections && !quote.collections().is_empty() {
+        write!(line, ", in {}", quote.collections().join(", "))?;
+    }
+    let line = String::from_utf8_lossy(&line);
+    //without the text there's nothing for the dash or comma to follow
+    let line = if fields.text {
+        line.to_string()
+    } else {
+        format!(" {}", line.trim_start_matches([' ', '-', ',']))
+    };
+
+    match wrap_width {
+        //continuation lines are indented to stay in the list item
+        Some(width) => writeln!(
+            f,
+            " - {}",
+            wrap_text(line.trim_start(), width.saturating_sub(3).max(1)).replace('\n', "\n   ")
+        ),
+        None => writeln!(f, " -{line}"),
+    }
+}
+
+///Every category from types.txt, followed by any others the quotes in `list` have (eg. custom ones), going by [`category_key`]
+fn markdown_categories(list: &[Quote]) -> Vec<String> {
+    let mut categories = ALL_PERMS.clone();
+    let mut custom: Vec<String> = vec![];
+    for category in list.iter().flat_map(|quote| &quote.1) {
+        let key = category_key(category);
+        if !categories
+            .iter()
+            .chain(&custom)
+            .any(|existing| category_key(existing) == key)
+        {
+            custom.push(category.clone());
+        }
+    }
+    custom.sort_by_key(|category| category_key(category));
+    categories.extend(custom);
+    categories
+}
+
+fn write_markdown(
+    list: &[Quote],
+    fields: FieldSet,
+    wrap_width: Option<usize>,
+    f: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(f, "# Jack's WIB Quotes\n")?;
+
+    //the headings would give the categories away, so without them it's just one flat list
+    if !fields.categories {
+        for quote in list {
+            write_markdown_line(f, quote, &[], fields, wrap_width)?;
+        }
+        return Ok(());
+    }
+
+    for perm in markdown_categories(list) {
+        writeln!(f, "## {perm}")?;
+
+        let key = category_key(&perm);
+        for quote in list.iter().filter(|quote| quote.matches_category(&perm)) {
+            let mut others = quote.1.clone();
+            others.retain(|category| category_key(category) != key);
+
+            write_markdown_line(f, quote, &others, fields, wrap_width)?;
+        }
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+///Quotes grouped into author sections (alphabetically, with authorless ones under "Unknown" at the end), sorted by text within each
+fn group_by_author(list: &[Quote]) -> Vec<(&str, Vec<&Quote>)> {
+    let mut groups: Vec<(&str, Vec<&Quote>)> = vec![];
+    for quote in list {
+        let author = quote.author().map_or("", str::trim);
+        match groups.iter_mut().find(|(name, _)| *name == author) {
+            Some((_, quotes)) => quotes.push(quote),
+            None => groups.push((author, vec![quote])),
+        }
+    }
+
+    groups.sort_by(|(a, _), (b, _)| {
+        a.is_empty()
+            .cmp(&b.is_empty())
+            .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+    });
+    for (_, quotes) in &mut groups {
+        quotes.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    groups
+}
+
+fn write_markdown_by_author(
+    list: &[Quote],
+    fields: FieldSet,
+    wrap_width: Option<usize>,
+    f: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(f, "# Jack's WIB Quotes\n")?;
+
+    //same as with categories - the headings would give the authors away
+    if !fields.author {
+        for quote in list {
+            write_markdown_line(f, quote, &quote.1, fields, wrap_width)?;
+        }
+        return Ok(());
+    }
+
+    //the author is already in the heading
+    let line_fields = FieldSet {
+        author: false,
+        ..fields
+    };
+    for (author, quotes) in group_by_author(list) {
+        writeln!(
+            f,
+            "## {}",
+            if author.is_empty() { "Unknown" } else { author }
+        )?;
+        for quote in quotes {
+            write_markdown_line(f, quote, &quote.1, line_fields, wrap_width)?;
+        }
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+fn write_markdown_by_collection(
+    list: &[Quote],
+    fields: FieldSet,
+    wrap_width: Option<usize>,
+    f: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(f, "# Jack's WIB Quotes\n")?;
+
+    //as with the other groupings - the headings would give the collections away
+    if !fields.collections {
+        for quote in list {
+            write_markdown_line(f, quote, &quote.1, fields, wrap_width)?;
+        }
+        return Ok(());
+    }
+
+    for collection in all_collections(list) {
+        writeln!(f, "## {collection}")?;
+        for quote in list.iter().filter(|quote| quote.in_collection(&collection)) {
+            write_markdown_line(f, quote, &quote.1, fields, wrap_width)?;
+        }
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+fn write_csv(list: &[Quote], fields: FieldSet, f: &mut impl Write) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(f);
+
+    let mut header = vec![];
+    if fields.text {
+        header.push("text");
+    }
+    if fields.author {
+        header.push("author");
+    }
+    if fields.categories {
+        header.push("categories");
+    }
+    if fields.attribution {
+        header.push("attribution");
+    }
+    if fields.tags {
+        header.push("tags");
+    }
+    if fields.source {
+        header.push("source");
+    }
+    if fields.rating {
+        header.push("rating");
+    }
+    if fields.collections {
+        header.push("collections");
+    }
+    if fields.timestamps {
+        header.extend(["added", "modified"]);
+    }
+    writer.write_record(&header)?;
+
+    for quote in list {
+        let mut record = vec![];
+        if fields.text {
+            record.push(quote.0.clone());
+        }
+        if fields.author {
+            record.push(quote.author().unwrap_or_default().to_string());
+        }
+        if fields.categories {
+            record.push(quote.1.join(";"));
+        }
+        if fields.attribution {
+            record.push(
+                quote
+                    .attribution()
+                    .map(|attribution| attribution.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+        if fields.tags {
+            record.push(quote.tags().join(";"));
+        }
+        if fields.source {
+            record.push(quote.source().unwrap_or_default().to_string());
+        }
+        if fields.rating {
+            record.push(
+                quote
+                    .rating()
+                    .map(|rating| rating.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+        if fields.collections {
+            record.push(quote.collections().join(";"));
+        }
+        if fields.timestamps {
+            for timestamp in [quote.2.added, quote.modified()] {
+                record.push(timestamp.map(format_date).unwrap_or_default());
+            }
+        }
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+///If a quote spans several lines and `separator` can't be told apart from them, a `---` divider is used for the whole file instead.
+fn write_plain_text(
+    list: &[Quote],
+    template: &QuoteTemplate,
+    fields: FieldSet,
+    separator: Separator,
+    wrap_width: Option<usize>,
+    f: &mut impl Write,
+) -> std::io::Result<()> {
+    let entries: Vec<String> = list
+        .iter()
+        .map(|quote| {
+            let entry = if fields == FieldSet::default() {
+                format_quote(quote, template)
+            } else {
+                format_quote(&only_fields(quote, fields), template)
+            };
+            match wrap_width {
+                Some(width) => wrap_text(&entry, width),
+                None => entry,
+            }
+        })
+        .collect();
+    let separator = if entries.iter().all(|entry| separator.fits(entry)) {
+        separator
+    } else {
+        Separator::Divider
+    };
+
+    writeln!(f, "{}", entries.join(separator.as_str()))
+}
+
+const HTML_APP_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Jack's WIB Quotes</title>
+<style>
+body { font-family: sans-serif; max-width: 50em; margin: 2em auto; padding: 0 1em; }
+input { width: 100%; padding: 0.5em; font-size: 1em; box-sizing: border-box; }
+li { margin: 0.75em 0; }
+.meta { color: #666; font-size: 0.9em; }
+</style>
+</head>
+<body>
+<h1>Jack's WIB Quotes</h1>
+<input id="search" type="search" placeholder="Search quotes, authors and categories" autofocus>
+<p id="count" class="meta"></p>
+<div id="quotes"></div>
+<script type="application/json" id="data">{{DATA}}</script>
+<script>
+const quotes = JSON.parse(document.getElementById("data").textContent);
+const list = document.getElementById("quotes");
+const count = document.getElementById("count");
+
+function render(term) {
+    term = term.trim().toLowerCase();
+    list.replaceChildren();
+    let shown = 0;
+    let section;
+    let items = null;
+    for (const quote of quotes) {
+        const haystack = [quote.text, quote.author, (quote.categories || []).join(" "), (quote.tags || []).join(" ")].join(" ").toLowerCase();
+        if (term && !haystack.includes(term)) {
+            continue;
+        }
+        shown += 1;
+
+        const item = document.createElement("li");
+        if (quote.text !== undefined) {
+            const text = document.createElement("em");
+            text.textContent = quote.text;
+            item.append(text);
+        }
+        const meta = [];
+        if (quote.author) {
+            meta.push(quote.author);
+        }
+        if (quote.attribution) {
+            meta.push(quote.attribution.toLowerCase());
+        }
+        if (quote.source) {
+            meta.push("from " + quote.source);
+        }
+        if (quote.rating) {
+            meta.push(quote.rating + "/{{MAX_RATING}}");
+        }
+        if (quote.tags && quote.tags.length) {
+            meta.push(quote.tags.map((tag) => '#' + tag).join(" "));
+        }
+        if (quote.categories && quote.categories.length) {
+            meta.push(quote.categories.join(", "));
+        }
+        if (quote.collections && quote.collections.length) {
+            meta.push("in " + quote.collections.join(", "));
+        }
+        if (quote.dates) {
+            meta.push(quote.dates);
+        }
+        if (meta.length) {
+            const span = document.createElement("div");
+            span.className = "meta";
+            span.textContent = meta.join(" | ");
+            item.append(span);
+        }
+        //a heading for each section that still has a quote showing
+        if (items === null || quote.section !== section) {
+            section = quote.section;
+            if (section !== undefined) {
+                const heading = document.createElement("h2");
+                heading.textContent = section;
+                list.append(heading);
+            }
+            items = document.createElement("ul");
+            list.append(items);
+        }
+        items.append(item);
+    }
+    count.textContent = shown + " of " + quotes.length + " quotes";
+}
 
-            writeln!(f, " - *{}*, related to **{:?}**", quote.0, new_list).map(|_| ())?;
+document.getElementById("search").addEventListener("input", (event) => render(event.target.value));
+render("");
+</script>
+</body>
+</html>
+"#;
+
+#[derive(serde::Serialize)]
+struct HtmlAppQuote<'a> {
+    ///The heading it goes under, if the page is grouped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    categories: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attribution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dates: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collections: Option<&'a [String]>,
+}
+
+///Makes JSON safe to put inside a `<script>` tag - nothing in it can close the tag, start a comment or break a JS string
+fn escape_json_for_html(json: &str) -> String {
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => escaped.push_str("\\u003c"),
+            '>' => escaped.push_str("\\u003e"),
+            '&' => escaped.push_str("\\u0026"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            c => escaped.push(c),
         }
-        writeln!(f).map(|_| ())?;
     }
+    escaped
+}
+
+fn write_html_app(
+    list: &[Quote],
+    grouping: ExportGrouping,
+    fields: FieldSet,
+    f: &mut impl Write,
+) -> Result<(), Error> {
+    //like Markdown, the headings would give the authors away, so they're only used along with them
+    let sections: Vec<(Option<&str>, Vec<&Quote>)> =
+        if grouping == ExportGrouping::Author && fields.author {
+            group_by_author(list)
+                .into_iter()
+                .map(|(author, quotes)| {
+                    (
+                        Some(if author.is_empty() { "Unknown" } else { author }),
+                        quotes,
+                    )
+                })
+                .collect()
+        } else {
+            vec![(None, list.iter().collect())]
+        };
+
+    let quotes: Vec<HtmlAppQuote> = sections
+        .into_iter()
+        .flat_map(|(section, quotes)| quotes.into_iter().map(move |quote| (section, quote)))
+        .map(|(section, quote)| HtmlAppQuote {
+            section,
+            text: fields.text.then_some(quote.0.as_str()),
+            author: quote.author().filter(|_| fields.author),
+            categories: fields.categories.then_some(quote.1.as_slice()),
+            attribution: quote
+                .attribution()
+                .filter(|_| fields.attribution)
+                .map(|attribution| attribution.to_string()),
+            dates: describe_dates(quote).filter(|_| fields.timestamps),
+            tags: fields.tags.then_some(quote.tags()),
+            source: quote.source().filter(|_| fields.source),
+            rating: quote.rating().filter(|_| fields.rating),
+            collections: fields.collections.then_some(quote.collections()),
+        })
+        .collect();
+    let data = escape_json_for_html(&serde_json::to_string(&quotes)?);
 
+    let page = HTML_APP_TEMPLATE
+        .replace("{{MAX_RATING}}", &MAX_RATING.to_string())
+        .replace("{{DATA}}", &data);
+    f.write_all(page.as_bytes())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::Attribution;
+
+    ///2024-01-31 and 2024-02-02
+    const ADDED: u64 = 1_706_659_200;
+    const MODIFIED: u64 = 1_706_832_000;
+
+    fn sample() -> Quote {
+        let mut quote = Quote::new("Hello there", vec!["Stella".to_string()]);
+        quote.2.author = Some("Anne Author".to_string());
+        quote.2.attribution = Some(Attribution::Disputed);
+        quote.2.added = Some(ADDED);
+        quote.2.modified = Some(MODIFIED);
+        quote.2.tags = vec!["greeting".to_string()];
+        quote.2.source = Some("Space Film".to_string());
+        quote.2.rating = Some(4);
+        quote.2.collections = vec!["Openers".to_string()];
+        quote
+    }
+
+    fn export_string(list: &[Quote], format: ExportFormat, fields: FieldSet) -> String {
+        let mut contents = vec![];
+        export_to_writer(
+            list,
+            &mut contents,
+            format,
+            ExportOptions::default(),
+            fields,
+        )
+        .unwrap();
+        String::from_utf8(contents).unwrap()
+    }
+
+    const FORMATS: [ExportFormat; 5] = [
+        ExportFormat::Markdown,
+        ExportFormat::Csv,
+        ExportFormat::HtmlApp,
+        ExportFormat::PlainText(Separator::Newline),
+        ExportFormat::JsonLines,
+    ];
+
+    #[test]
+    fn exports_between_in_memory_buffers() {
+        let input = br#"[["Hello", ["Stella"]], ["Fog", ["Esme"]]]"#;
+        let list = crate::db::read_db_from_reader(&input[..]).unwrap();
+        let fields = text_only();
+        assert_eq!(
+            export_string(&list, ExportFormat::Csv, fields),
+            "text\nHello\nFog\n"
+        );
+    }
+
+    #[test]
+    fn exports_a_filtered_sorted_view_as_shown() {
+        use crate::{
+            db::{sort_list_by, SortKey},
+            utils::filter::{build_category_index, filter_quotes, QuoteSelectionFilter},
+        };
+
+        let mut db: Vec<Quote> = [
+            ("zebra", "Stella", Some("Bea")),
+            ("apple", "Esme", Some("Al")),
+            ("mango", "Stella", None),
+            ("kiwi", "Stella", Some("Al")),
+        ]
+        .into_iter()
+        .map(|(text, category, author)| {
+            let mut quote = Quote::new(text, vec![category.to_string()]);
+            quote.2.author = author.map(ToString::to_string);
+            quote
+        })
+        .collect();
+        sort_list_by(Some(&mut db), SortKey::Author).unwrap();
+        let shown = filter_quotes(
+            &db,
+            &build_category_index(&db),
+            &["Stella".to_string()],
+            QuoteSelectionFilter::Or,
+            None,
+        );
+        let view: Vec<Quote> = shown.iter().map(|&i| db[i].clone()).collect();
+
+        let fields = text_only();
+        let exported = export_string(&view, ExportFormat::Csv, fields);
+        let rows: Vec<&str> = exported.lines().skip(1).collect();
+        let expected: Vec<&str> = view.iter().map(|quote| quote.0.as_str()).collect();
+        assert_eq!(rows, expected);
+        assert_eq!(rows, ["kiwi", "zebra", "mango"]);
+    }
+
+    #[test]
+    fn errors_flushing_are_passed_on() {
+        struct FailsToFlush;
+        impl Write for FailsToFlush {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Err(ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        for format in FORMATS {
+            let exported = export_to_writer(
+                &[sample()],
+                BufWriter::new(FailsToFlush),
+                format,
+                ExportOptions::default(),
+                FieldSet::default(),
+            );
+            assert!(exported.is_err(), "{format}");
+        }
+    }
+
+    #[test]
+    fn markdown_matches_categories_like_everywhere_else() {
+        let list = vec![Quote::new(
+            "Hello world",
+            vec![" stella ".to_string(), "esme".to_string()],
+        )];
+        let exported = export_string(&list, ExportFormat::Markdown, FieldSet::default());
+        assert!(exported.contains("## Stella\n - *Hello world*, related to **[\"esme\"]**"));
+        assert!(exported.contains("## Esme\n - *Hello world*, related to **[\" stella \"]**"));
+        //not also given their own sections
+        assert!(!exported.contains("## esme"));
+    }
+
+    fn text_only() -> FieldSet {
+        FieldSet {
+            author: false,
+            categories: false,
+            attribution: false,
+            timestamps: false,
+            tags: false,
+            source: false,
+            rating: false,
+            collections: false,
+            ..FieldSet::default()
+        }
+    }
+
+    fn plain_text_round_trip(texts: &[&str], separator: Separator) -> (String, Vec<String>) {
+        let list: Vec<Quote> = texts.iter().map(|text| Quote::new(*text, vec![])).collect();
+        let exported = export_string(&list, ExportFormat::PlainText(separator), text_only());
+        let imported = crate::utils::imports::import_plain_text(&exported)
+            .into_iter()
+            .map(|quote| quote.0)
+            .collect();
+        (exported, imported)
+    }
+
+    #[test]
+    fn plain_text_round_trips_with_every_separator() {
+        let texts = ["First quote", "Second, with a comma", "Third"];
+        for separator in Separator::ALL {
+            let (exported, imported) = plain_text_round_trip(&texts, separator);
+            assert_eq!(imported, texts, "{separator}");
+            assert_eq!(exported, format!("{}\n", texts.join(separator.as_str())));
+        }
+    }
+
+    #[test]
+    fn multi_line_quotes_fall_back_to_a_divider() {
+        let texts = ["One line", "Two\nlines", "Has a\n\nblank line"];
+        for separator in Separator::ALL {
+            let (exported, imported) = plain_text_round_trip(&texts, separator);
+            assert_eq!(imported, texts, "{separator}");
+            assert_eq!(exported.matches("\n---\n").count(), 2, "{separator}");
+        }
+    }
+
+    #[test]
+    fn every_field_is_exported_by_default() {
+        //plain text only has what its template asks for - see `plain_text_follows_the_template`
+        for format in FORMATS
+            .into_iter()
+            .filter(|format| !matches!(format, ExportFormat::PlainText(_)))
+        {
+            let exported = export_string(&[sample()], format, FieldSet::default());
+            //JSON lines keeps the timestamps as they're stored
+            let (added, modified) = if format == ExportFormat::JsonLines {
+                (ADDED.to_string(), MODIFIED.to_string())
+            } else {
+                ("2024-01-31".to_string(), "2024-02-02".to_string())
+            };
+            for expected in [
+                "Hello there",
+                "Anne Author",
+                "Stella",
+                &added,
+                &modified,
+                "greeting",
+                "Space Film",
+                "4",
+                "Openers",
+            ] {
+                assert!(
+                    exported.contains(expected),
+                    "{format} is missing {expected}"
+                );
+            }
+            assert!(exported.to_lowercase().contains("disputed"), "{format}");
+        }
+    }
+
+    #[test]
+    fn deselected_fields_are_left_out() {
+        let fields = FieldSet {
+            author: false,
+            attribution: false,
+            timestamps: false,
+            tags: false,
+            source: false,
+            rating: false,
+            collections: false,
+            ..FieldSet::default()
+        };
+        for format in FORMATS {
+            let exported = export_string(&[sample()], format, fields);
+            assert!(exported.contains("Hello there"), "{format}");
+            for absent in [
+                "Anne Author",
+                "2024-01-31",
+                "2024-02-02",
+                &ADDED.to_string(),
+                &MODIFIED.to_string(),
+                "added",
+                "greeting",
+                "Space Film",
+                "Openers",
+            ] {
+                assert!(!exported.contains(absent), "{format} still has {absent}");
+            }
+            assert!(!exported.to_lowercase().contains("disputed"), "{format}");
+        }
+    }
+
+    #[test]
+    fn text_can_be_left_out() {
+        let fields = FieldSet {
+            text: false,
+            ..FieldSet::default()
+        };
+        for format in FORMATS {
+            let exported = export_string(&[sample()], format, fields);
+            assert!(!exported.contains("Hello there"), "{format}");
+            assert!(exported.contains("Anne Author"), "{format}");
+        }
+    }
+
+    #[test]
+    fn grouping_by_collection_without_collections_is_flat() {
+        let options = ExportOptions {
+            grouping: ExportGrouping::Collection,
+            ..ExportOptions::default()
+        };
+        let export = |fields: FieldSet| {
+            let exported = ExportFormat::Markdown
+                .exporter(options.clone(), fields)
+                .serialize(&[sample()])
+                .unwrap();
+            String::from_utf8(exported).unwrap()
+        };
+
+        assert!(export(FieldSet::default()).contains("## Openers\n"));
+        let flat = export(FieldSet {
+            collections: false,
+            ..FieldSet::default()
+        });
+        assert!(!flat.contains("Openers"));
+        assert!(flat.contains(" - *Hello there* - Anne Author"));
+    }
+
+    #[test]
+    fn html_can_be_grouped_by_author() {
+        let mut anonymous = Quote::new("Anon", vec![]);
+        anonymous.2.author = Some("  ".to_string());
+        let mut zed = sample();
+        zed.0 = "Later".to_string();
+        zed.2.author = Some("zed".to_string());
+        let list = [anonymous, zed, sample()];
+        let export = |grouping: ExportGrouping, fields: FieldSet| {
+            let exported = ExportFormat::HtmlApp
+                .exporter(
+                    ExportOptions {
+                        grouping,
+                        ..ExportOptions::default()
+                    },
+                    fields,
+                )
+                .serialize(&list)
+                .unwrap();
+            String::from_utf8(exported).unwrap()
+        };
+
+        let grouped = export(ExportGrouping::Author, FieldSet::default());
+        let sections: Vec<&str> = grouped
+            .match_indices(r#""section":""#)
+            .map(|(i, _)| grouped[i..].split('"').nth(3).unwrap())
+            .collect();
+        assert_eq!(sections, ["Anne Author", "zed", "Unknown"]);
+
+        //not grouped otherwise, or when it'd give the authors away
+        for (grouping, fields) in [
+            (ExportGrouping::Category, FieldSet::default()),
+            (
+                ExportGrouping::Author,
+                FieldSet {
+                    author: false,
+                    ..FieldSet::default()
+                },
+            ),
+        ] {
+            let exported = export(grouping, fields);
+            assert!(!exported.contains(r#""section""#), "{grouping}");
+            assert!(exported.find("Anon") < exported.find("Hello there"));
+        }
+    }
+
+    fn plain_text(template: &str, fields: FieldSet) -> String {
+        let options = ExportOptions {
+            template: template.parse().unwrap(),
+            ..ExportOptions::default()
+        };
+        let exported = ExportFormat::PlainText(Separator::Newline)
+            .exporter(options, fields)
+            .serialize(&[sample()])
+            .unwrap();
+        String::from_utf8(exported).unwrap()
+    }
+
+    #[test]
+    fn plain_text_follows_the_template() {
+        assert_eq!(
+            plain_text("{text} - {author}", FieldSet::default()),
+            "Hello there - Anne Author\n"
+        );
+        let everything =
+            "\"{text}\" - {author} ({attribution}) <{source}> {rating}/5 {tags} [{categories}]";
+        assert_eq!(
+            plain_text(everything, FieldSet::default()),
+            "\"Hello there\" - Anne Author (disputed) <Space Film> 4/5 #greeting [Stella]\n"
+        );
+        //left out fields are empty placeholders
+        assert_eq!(plain_text(everything, text_only()), "\"Hello there\"\n");
+    }
+
+    #[test]
+    fn plain_text_without_text_starts_with_the_author() {
+        let fields = FieldSet {
+            text: false,
+            ..FieldSet::default()
+        };
+        assert_eq!(
+            plain_text("{text} - {author} ({attribution})", fields),
+            "Anne Author (disputed)\n"
+        );
+    }
+
+    #[test]
+    fn only_later_edits_are_mentioned() {
+        let mut quote = sample();
+        assert_eq!(
+            describe_dates(&quote).as_deref(),
+            Some("added 2024-01-31, edited 2024-02-02")
+        );
+        quote.2.modified = Some(ADDED);
+        assert_eq!(describe_dates(&quote).as_deref(), Some("added 2024-01-31"));
+        quote.2.added = None;
+        quote.2.modified = None;
+        assert_eq!(describe_dates(&quote), None);
+    }
+
+    #[test]
+    fn wrapping_is_an_export_option() {
+        let list = [Quote::new("the only way to do great work", vec![])];
+        let options = ExportOptions {
+            wrap_width: Some(14),
+            ..ExportOptions::default()
+        };
+        let export = |format: ExportFormat| {
+            String::from_utf8(
+                format
+                    .exporter(options.clone(), text_only())
+                    .serialize(&list)
+                    .unwrap(),
+            )
+            .unwrap()
+        };
+
+        assert!(
+            export(ExportFormat::Markdown).contains(" - *the only\n   way to do\n   great work*\n")
+        );
+        assert_eq!(
+            export(ExportFormat::PlainText(Separator::Newline)),
+            "the only way\nto do great\nwork\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Csv),
+            "text\nthe only way to do great work\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_read_back_in() {
+        use crate::utils::imports::read_json_lines;
+
+        let mut tagged = Quote::new("Fog", vec!["Esme".to_string()]);
+        tagged.2.tags = vec!["weather".to_string()];
+        let list = [sample(), tagged];
+        let exported = export_string(&list, ExportFormat::JsonLines, FieldSet::default());
+        assert_eq!(exported.lines().count(), 2);
+
+        let read = read_json_lines(exported.as_bytes()).unwrap();
+        assert_eq!(read, list);
+        assert_eq!(read[0].2, list[0].2);
+        assert_eq!(read[1].2, list[1].2);
+
+        //blank lines are skipped
+        let spaced = format!("\n{}\n   \n", exported.replace('\n', "\n\n"));
+        assert_eq!(read_json_lines(spaced.as_bytes()).unwrap(), list);
+
+        //but a corrupt line is an error rather than being quietly dropped
+        let corrupt = format!("{exported}[\"Half a quote\n");
+        assert!(read_json_lines(corrupt.as_bytes()).is_err());
+
+        let trimmed = export_string(&list, ExportFormat::JsonLines, text_only());
+        let read = read_json_lines(trimmed.as_bytes()).unwrap();
+        assert_eq!(read[0].0, "Hello there");
+        assert!(read[0].1.is_empty());
+        assert_eq!(read[0].author(), None);
+        assert_eq!(read[0].2.added, None);
+        assert!(read[1].2.tags.is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+//!Pulling quotes in from RSS/Atom feeds, eg. a quote-of-the-day feed - only built with the `feed` feature
+
+use crate::{
+    quote::{is_valid_source, parse_tags, Quote},
+    utils::{
+        imports::{preview_import, ImportPreview},
+        Error,
+    },
+};
+use std::time::Duration;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+///Drops anything between `<` and `>` and decodes the common entities, as descriptions are often HTML
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+///Turns each entry into a quote in `categories`.
+///
+///The summary (or content) is used as the text, falling back to the title if there isn't one. The entry's author, link, publish date and categories (as tags) are kept too.
+pub fn parse_feed(contents: &[u8], categories: &[String]) -> Result<Vec<Quote>, Error> {
+    let feed = feed_rs::parser::parse(contents)?;
+
+    Ok(feed
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            let text = [
+                entry.summary.map(|summary| summary.content),
+                entry.content.and_then(|content| content.body),
+                entry.title.map(|title| title.content),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|text| strip_html(&text))
+            .find(|text| !text.is_empty())?;
+
+            let mut builder = Quote::builder(text)
+                .categories(categories.to_vec())
+                .maybe_author(entry.authors.first().map(|author| author.name.clone()))
+                .tags(parse_tags(
+                    &entry
+                        .categories
+                        .iter()
+                        .map(|category| category.term.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ));
+            if let Some(link) = entry
+                .links
+                .first()
+                .filter(|link| is_valid_source(&link.href))
+            {
+                builder = builder.source(link.href.as_str());
+            }
+            if let Some(published) = entry.published.or(entry.updated) {
+                builder = builder.added(u64::try_from(published.timestamp()).unwrap_or_default());
+            }
+            Some(builder.build())
+        })
+        .collect())
+}
+
+///Downloads the feed at `url` and parses it with [`parse_feed`]
+pub fn fetch_feed(url: &str, categories: &[String]) -> Result<Vec<Quote>, Error> {
+    let response = reqwest::blocking::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()?
+        .get(url)
+        .send()?
+        .error_for_status()?;
+    parse_feed(&response.bytes()?, categories)
+}
+
+///Fetches the feed at `url` and adds any quotes that aren't already in `db`, going by [`preview_import`]
+pub fn import_feed(
+    url: &str,
+    categories: &[String],
+    db: &mut Vec<Quote>,
+) -> Result<ImportPreview, Error> {
+    let (preview, new_quotes) = preview_import(db, fetch_feed(url, categories)?);
+    db.extend(new_quotes);
+    Ok(preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0">
+<channel>
+<title>Quotes</title>
+<link>https://example.com</link>
+<description>A quote a day</description>
+<item>
+<title>Ignored title</title>
+<description>&lt;p&gt;Fish &amp;amp;lt;3 &lt;b&gt;chips&lt;/b&gt;&lt;/p&gt;</description>
+<link>https://example.com/1</link>
+<category>food</category>
+<category>#food</category>
+<category>seaside</category>
+</item>
+<item>
+<title>Only a title</title>
+<link>not a link</link>
+</item>
+<item>
+<description>&lt;br/&gt;</description>
+</item>
+</channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+<title>Quotes</title>
+<id>urn:quotes</id>
+<updated>2024-01-31T00:00:00Z</updated>
+<entry>
+<title>Ignored title</title>
+<id>urn:quotes:1</id>
+<updated>2024-01-31T00:00:00Z</updated>
+<author><name>Anne</name></author>
+<content type="html">&lt;em&gt;From the content&lt;/em&gt;</content>
+<link href="https://example.com/a"/>
+<category term="wit"/>
+</entry>
+<entry>
+<title>Ignored title</title>
+<id>urn:quotes:2</id>
+<updated>2024-01-31T00:00:00Z</updated>
+<summary>From the summary</summary>
+<content type="text">Not the content</content>
+</entry>
+</feed>"#;
+
+    fn categories() -> Vec<String> {
+        vec!["Feed".to_string()]
+    }
+
+    #[test]
+    fn rss_items_become_quotes() {
+        let quotes = parse_feed(RSS.as_bytes(), &categories()).unwrap();
+        //the last item has nothing left once the HTML is gone
+        assert_eq!(quotes.len(), 2);
+
+        assert_eq!(quotes[0].0, "Fish &lt;3 chips");
+        assert_eq!(quotes[0].1, ["Feed"]);
+        assert_eq!(quotes[0].tags(), ["food", "seaside"]);
+        assert_eq!(quotes[0].source(), Some("https://example.com/1"));
+
+        assert_eq!(quotes[1].0, "Only a title");
+        assert_eq!(quotes[1].source(), None);
+        assert!(quotes[1].tags().is_empty());
+    }
+
+    #[test]
+    fn atom_entries_become_quotes() {
+        let quotes = parse_feed(ATOM.as_bytes(), &categories()).unwrap();
+        assert_eq!(quotes.len(), 2);
+
+        assert_eq!(quotes[0].0, "From the content");
+        assert_eq!(quotes[0].author(), Some("Anne"));
+        assert_eq!(quotes[0].tags(), ["wit"]);
+        assert_eq!(quotes[0].source(), Some("https://example.com/a"));
+        assert_eq!(quotes[0].2.added, Some(1_706_659_200));
+
+        //the summary comes before the content
+        assert_eq!(quotes[1].0, "From the summary");
+        assert_eq!(quotes[1].author(), None);
+    }
+
+    #[test]
+    fn html_is_stripped_and_decoded_once() {
+        assert_eq!(
+            strip_html("<p>Tom &amp; Jerry</p>\n<p>&quot;hi&quot;&nbsp;&#39;there&apos;</p>"),
+            "Tom & Jerry \"hi\" 'there'"
+        );
+        //`&amp;` goes last, so what it decodes to isn't decoded again
+        assert_eq!(strip_html("a &amp;lt; b &amp;amp; c"), "a &lt; b &amp; c");
+        assert_eq!(strip_html("<br/>  "), "");
+    }
+}
@@ -0,0 +1,86 @@
+//!Narrowing the database down to the quotes in certain categories, kept apart from the UI so it can be benchmarked
+
+use crate::quote::{category_key, Quote};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum QuoteSelectionFilter {
+    And,
+    #[default]
+    Or,
+}
+
+///[Category key](category_key) to the indices of every quote in that category, in database order
+pub type CategoryIndex = HashMap<String, Vec<usize>>;
+
+#[must_use]
+pub fn build_category_index(quotes: &[Quote]) -> CategoryIndex {
+    let mut index = CategoryIndex::new();
+    for (i, quote) in quotes.iter().enumerate() {
+        for cat in &quote.1 {
+            let indices = index.entry(category_key(cat)).or_default();
+            if indices.last() != Some(&i) {
+                indices.push(i);
+            }
+        }
+    }
+    index
+}
+
+///Indices (in database order) of the quotes matching the chosen categories, looked up from the index rather than scanning every quote.
+///
+///With no categories chosen, `And` matches all `len` quotes and `Or` matches none.
+#[must_use]
+pub fn indices_matching(
+    index: &CategoryIndex,
+    chosen_types: &[String],
+    filter: QuoteSelectionFilter,
+    len: usize,
+) -> Vec<usize> {
+    let lookup = |cat: &String| index.get(&category_key(cat)).map_or(&[][..], Vec::as_slice);
+
+    match filter {
+        QuoteSelectionFilter::And => {
+            let Some(smallest) = chosen_types.iter().map(lookup).min_by_key(|ixs| ixs.len()) else {
+                return (0..len).collect();
+            };
+            smallest
+                .iter()
+                .copied()
+                .filter(|i| {
+                    chosen_types
+                        .iter()
+                        .all(|cat| lookup(cat).binary_search(i).is_ok())
+                })
+                .collect()
+        }
+        QuoteSelectionFilter::Or => chosen_types
+            .iter()
+            .flat_map(lookup)
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    }
+}
+
+///Indices (in database order) of the quotes in the chosen categories that `narrow` keeps, eg. the category view's quick filters.
+///
+///With nothing chosen but something to `narrow` by, every quote is a candidate, rather than none of them as [`indices_matching`] would have it.
+#[must_use]
+pub fn filter_quotes(
+    db: &[Quote],
+    index: &CategoryIndex,
+    chosen_types: &[String],
+    filter: QuoteSelectionFilter,
+    narrow: Option<&dyn Fn(&Quote) -> bool>,
+) -> Vec<usize> {
+    let candidates = match narrow {
+        Some(_) if chosen_types.is_empty() => (0..db.len()).collect(),
+        _ => indices_matching(index, chosen_types, filter, db.len()),
+    };
+    match narrow {
+        Some(narrow) => candidates.into_iter().filter(|&i| narrow(&db[i])).collect(),
+        None => candidates,
+    }
+}
@@ -0,0 +1,581 @@
+use crate::{
+    quote::{same_categories, Quote},
+    utils::Error,
+};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    Json,
+    ///One quote per line, as written by [`write_json_lines`](crate::utils::exports::write_json_lines)
+    JsonLines,
+    Csv,
+    PlainText,
+}
+
+impl ImportFormat {
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            "csv" => Some(Self::Csv),
+            "txt" => Some(Self::PlainText),
+            _ => None,
+        }
+    }
+
+    ///Works out the format from what's actually in the file, or `None` if it could be anything.
+    ///
+    ///JSON has to parse as a list of quotes, and JSON lines needs every non-blank line to be a quote. CSV needs either a header with a `text` or `quote` column, or at least three rows that all have the same number (more than one) of fields - so a few lines of prose with the odd comma aren't mistaken for it.
+    #[must_use]
+    pub fn sniff(contents: &str) -> Option<Self> {
+        let trimmed = contents.trim_start();
+        if trimmed.starts_with('[') && serde_json::from_str::<Vec<Quote>>(contents).is_ok() {
+            return Some(Self::Json);
+        }
+        if trimmed.starts_with('[') && read_json_lines(contents.as_bytes()).is_ok() {
+            return Some(Self::JsonLines);
+        }
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(contents.as_bytes());
+        let records: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>().ok()?;
+        let width = records.first()?.len();
+        let has_header = records[0]
+            .iter()
+            .any(|column| ["text", "quote"].contains(&column.trim().to_lowercase().as_str()));
+        let consistent = records.len() >= 3 && records.iter().all(|record| record.len() == width);
+        (width > 1 && (has_header || consistent)).then_some(Self::Csv)
+    }
+
+    ///[`Self::sniff`]s `contents`, falling back to the extension and then plain text - so a `.txt` file full of JSON is read as JSON
+    #[must_use]
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        Self::sniff(contents)
+            .or_else(|| Self::from_path(path))
+            .unwrap_or(Self::PlainText)
+    }
+}
+
+impl Display for ImportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "JSON"),
+            Self::JsonLines => write!(f, "JSON lines"),
+            Self::Csv => write!(f, "CSV"),
+            Self::PlainText => write!(f, "plain text"),
+        }
+    }
+}
+
+///What an import would do to the database, worked out before anything is added
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportPreview {
+    pub new: usize,
+    ///Same text, author and categories as a quote that's already there
+    pub duplicates: usize,
+    ///Same text and author as a quote that's already there, but with different categories - these are skipped too
+    pub category_conflicts: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ImportKind {
+    New,
+    Duplicate,
+    CategoryConflict,
+}
+
+type DedupKey = (String, Option<String>);
+
+fn classify(
+    seen: &HashMap<DedupKey, Vec<Vec<String>>>,
+    key: &DedupKey,
+    quote: &Quote,
+) -> ImportKind {
+    match seen.get(key) {
+        None => ImportKind::New,
+        Some(categories)
+            if categories
+                .iter()
+                .any(|cats| same_categories(cats, &quote.1)) =>
+        {
+            ImportKind::Duplicate
+        }
+        Some(_) => ImportKind::CategoryConflict,
+    }
+}
+
+///Splits `incoming` into the quotes that would actually be added and a summary of what was skipped.
+///
+///Quotes are matched up by [`Quote::dedup_key`], and ones repeated within `incoming` only count as new once.
+#[must_use]
+pub fn preview_import(existing: &[Quote], incoming: Vec<Quote>) -> (ImportPreview, Vec<Quote>) {
+    let mut preview = ImportPreview::default();
+    let mut new_quotes: Vec<Quote> = vec![];
+
+    let mut seen: HashMap<DedupKey, Vec<Vec<String>>> = HashMap::new();
+    for quote in existing {
+        seen.entry(quote.dedup_key())
+            .or_default()
+            .push(quote.1.clone());
+    }
+
+    for quote in incoming {
+        let key = quote.dedup_key();
+        match classify(&seen, &key, &quote) {
+            ImportKind::New => {
+                preview.new += 1;
+                seen.entry(key).or_default().push(quote.1.clone());
+                new_quotes.push(quote);
+            }
+            ImportKind::Duplicate => preview.duplicates += 1,
+            ImportKind::CategoryConflict => preview.category_conflicts += 1,
+        }
+    }
+
+    (preview, new_quotes)
+}
+
+///Reads `path` without parsing it yet, along with the format it looks to be in - see [`ImportFormat::detect`]
+pub fn read_import(path: &Path) -> Result<(ImportFormat, String), Error> {
+    let contents = String::from_utf8(std::fs::read(path)?)
+        .map_err(|_| Error::UnsupportedImport(path.display().to_string()))?;
+    Ok((ImportFormat::detect(path, &contents), contents))
+}
+
+///Reads the quotes in `path`, along with the format they turned out to be in - see [`ImportFormat::detect`]
+///
+///Files ending in `.jsonl` are streamed in a line at a time rather than read in whole.
+pub fn import_file(path: &Path) -> Result<(ImportFormat, Vec<Quote>), Error> {
+    if ImportFormat::from_path(path) == Some(ImportFormat::JsonLines) {
+        let quotes = read_json_lines(BufReader::new(File::open(path)?))?;
+        return Ok((ImportFormat::JsonLines, quotes));
+    }
+
+    let (format, contents) = read_import(path)?;
+    Ok((format, parse_import(format, &contents)?))
+}
+
+pub fn parse_import(format: ImportFormat, contents: &str) -> Result<Vec<Quote>, Error> {
+    match format {
+        ImportFormat::Json => Ok(serde_json::from_str(contents)?),
+        ImportFormat::JsonLines => read_json_lines(contents.as_bytes()),
+        ImportFormat::Csv => import_csv(contents),
+        ImportFormat::PlainText => Ok(import_plain_text(contents)),
+    }
+}
+
+///Reads quotes written one per line by [`write_json_lines`](crate::utils::exports::write_json_lines), a line at a time. Blank lines are skipped.
+pub fn read_json_lines(reader: impl BufRead) -> Result<Vec<Quote>, Error> {
+    let mut quotes = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            quotes.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(quotes)
+}
+
+///One quote per line, blank lines are skipped - unless there are `---` lines, in which case everything between them is a quote
+#[must_use]
+pub fn import_plain_text(contents: &str) -> Vec<Quote> {
+    if contents.lines().any(|line| line.trim() == "---") {
+        let mut quotes = vec![];
+        let mut current: Vec<&str> = vec![];
+        for line in contents.lines().chain(std::iter::once("---")) {
+            if line.trim() == "---" {
+                let text = current.join("\n");
+                if !text.trim().is_empty() {
+                    quotes.push(Quote::builder(text.trim()).build());
+                }
+                current.clear();
+            } else {
+                current.push(line);
+            }
+        }
+        return quotes;
+    }
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| Quote::builder(line).build())
+        .collect()
+}
+
+///What a CSV column is read into
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsvField {
+    Text,
+    Author,
+    Categories,
+    Ignore,
+}
+
+impl CsvField {
+    pub const ALL: [Self; 4] = [Self::Text, Self::Author, Self::Categories, Self::Ignore];
+}
+
+impl Display for CsvField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "Text"),
+            Self::Author => write!(f, "Author"),
+            Self::Categories => write!(f, "Categories"),
+            Self::Ignore => write!(f, "Ignore"),
+        }
+    }
+}
+
+///How the columns of a CSV file turn into quotes.
+///
+///The first [`CsvField::Text`] and [`CsvField::Author`] columns are used, and the categories from every [`CsvField::Categories`] column are put together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvMapping {
+    ///Whether the first row names the columns, rather than being a quote
+    pub has_header: bool,
+    ///One for each column, going by the first row
+    pub columns: Vec<CsvField>,
+    ///What separates categories within a cell
+    pub category_delimiter: String,
+}
+
+impl CsvMapping {
+    ///Goes by the names in the first row - `text` (or `quote`), `author` and `categories` (or `category`), separated by `;`.
+    ///
+    ///If none of those turn up, the first row is taken to be a quote rather than a header, and the first column is used as the text.
+    #[must_use]
+    pub fn guess(first_row: &[String]) -> Self {
+        let mut columns: Vec<CsvField> = first_row
+            .iter()
+            .map(|name| match name.trim().to_lowercase().as_str() {
+                "text" | "quote" => CsvField::Text,
+                "author" => CsvField::Author,
+                "categories" | "category" => CsvField::Categories,
+                _ => CsvField::Ignore,
+            })
+            .collect();
+        let has_header = columns.iter().any(|&field| field != CsvField::Ignore);
+        if !columns.contains(&CsvField::Text) {
+            if let Some(first) = columns.first_mut() {
+                *first = CsvField::Text;
+            }
+        }
+
+        Self {
+            has_header,
+            columns,
+            category_delimiter: ";".into(),
+        }
+    }
+
+    fn column(&self, field: CsvField) -> Option<usize> {
+        self.columns.iter().position(|&column| column == field)
+    }
+
+    ///Nothing can be imported without somewhere to get the text from
+    #[must_use]
+    pub fn has_text(&self) -> bool {
+        self.column(CsvField::Text).is_some()
+    }
+
+    ///The quote in `record`, or `None` if it doesn't have any text
+    fn quote(&self, record: &csv::StringRecord) -> Option<Quote> {
+        let text = record.get(self.column(CsvField::Text)?)?.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        let categories: Vec<&str> = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, &field)| field == CsvField::Categories)
+            .filter_map(|(i, _)| record.get(i))
+            .flat_map(|cell| {
+                if self.category_delimiter.is_empty() {
+                    vec![cell]
+                } else {
+                    cell.split(self.category_delimiter.as_str()).collect()
+                }
+            })
+            .map(str::trim)
+            .filter(|cat| !cat.is_empty())
+            .collect();
+
+        Some(
+            Quote::builder(text)
+                .categories(categories)
+                .maybe_author(
+                    self.column(CsvField::Author)
+                        .and_then(|i| record.get(i))
+                        .map(str::trim),
+                )
+                .build(),
+        )
+    }
+}
+
+fn csv_reader(contents: &str, has_header: bool) -> csv::Reader<&[u8]> {
+    csv::ReaderBuilder::new()
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(contents.as_bytes())
+}
+
+///The first row of a CSV file, for [`CsvMapping::guess`] and to label the columns with
+pub fn csv_first_row(contents: &str) -> Result<Vec<String>, Error> {
+    Ok(csv_reader(contents, false)
+        .records()
+        .next()
+        .transpose()?
+        .map(|record| record.iter().map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+///Reads up to `limit` quotes from a CSV file with `mapping` - rows without any text are skipped
+pub fn import_csv_with(
+    contents: &str,
+    mapping: &CsvMapping,
+    limit: usize,
+) -> Result<Vec<Quote>, Error> {
+    let mut quotes = vec![];
+    for record in csv_reader(contents, mapping.has_header).records() {
+        if quotes.len() >= limit {
+            break;
+        }
+        if let Some(quote) = mapping.quote(&record?) {
+            quotes.push(quote);
+        }
+    }
+    Ok(quotes)
+}
+
+///Reads a CSV file with the columns worked out by [`CsvMapping::guess`]
+pub fn import_csv(contents: &str) -> Result<Vec<Quote>, Error> {
+    import_csv_with(
+        contents,
+        &CsvMapping::guess(&csv_first_row(contents)?),
+        usize::MAX,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(text: &str, author: Option<&str>, categories: &[&str]) -> Quote {
+        let mut quote = Quote::new(text, categories.iter().map(ToString::to_string).collect());
+        quote.2.author = author.map(ToString::to_string);
+        quote
+    }
+
+    #[test]
+    fn same_text_from_another_author_is_new() {
+        let existing = vec![quote(
+            "To be or not to be",
+            Some("Shakespeare"),
+            &["Stella"],
+        )];
+        let (preview, new_quotes) = preview_import(
+            &existing,
+            vec![quote(
+                "To be or not to be",
+                Some("Someone Else"),
+                &["Stella"],
+            )],
+        );
+        assert_eq!(
+            preview,
+            ImportPreview {
+                new: 1,
+                ..ImportPreview::default()
+            }
+        );
+        assert_eq!(new_quotes.len(), 1);
+    }
+
+    #[test]
+    fn identical_quotes_are_duplicates() {
+        let existing = vec![quote(
+            "To be or not to be",
+            Some("Shakespeare"),
+            &["Stella", "Esme"],
+        )];
+        let (preview, new_quotes) = preview_import(
+            &existing,
+            vec![
+                quote(
+                    "To be  or not to be",
+                    Some(" shakespeare"),
+                    &["esme", "Stella"],
+                ),
+                quote("To be or not to be", Some("Shakespeare"), &["Arthur"]),
+            ],
+        );
+        assert_eq!(
+            preview,
+            ImportPreview {
+                new: 0,
+                duplicates: 1,
+                category_conflicts: 1,
+            }
+        );
+        assert!(new_quotes.is_empty());
+    }
+
+    #[test]
+    fn repeats_within_an_import_only_count_once() {
+        let (preview, new_quotes) = preview_import(
+            &[],
+            vec![
+                quote("Fog", None, &["Stella"]),
+                quote(" Fog ", None, &["stella"]),
+            ],
+        );
+        assert_eq!(preview.new, 1);
+        assert_eq!(preview.duplicates, 1);
+        assert_eq!(new_quotes.len(), 1);
+    }
+
+    #[test]
+    fn sniffing_goes_by_the_contents() {
+        let quotes = vec![
+            quote("Fog", Some("Dickens"), &["Stella"]),
+            quote("Rain", None, &["Esme"]),
+        ];
+        let json = serde_json::to_string_pretty(&quotes).unwrap();
+        let json_lines: String = quotes
+            .iter()
+            .map(|quote| serde_json::to_string(quote).unwrap() + "\n\n")
+            .collect();
+
+        assert_eq!(ImportFormat::sniff(&json), Some(ImportFormat::Json));
+        assert_eq!(
+            ImportFormat::sniff(&json_lines),
+            Some(ImportFormat::JsonLines)
+        );
+        assert_eq!(
+            ImportFormat::sniff("quote,author\nFog,Dickens\n"),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(
+            ImportFormat::sniff("Fog,Dickens\nRain,Me\nSnow,You\n"),
+            Some(ImportFormat::Csv)
+        );
+
+        //prose with the odd comma, and things that only look like JSON
+        assert_eq!(ImportFormat::sniff("Fog, of course\nRain\nSnow\n"), None);
+        assert_eq!(ImportFormat::sniff("Fog,Dickens\nRain,Me\n"), None);
+        assert_eq!(ImportFormat::sniff("[not json]\n"), None);
+        assert_eq!(ImportFormat::sniff(""), None);
+    }
+
+    #[test]
+    fn contents_beat_the_extension() {
+        let json = serde_json::to_string(&[quote("Fog", None, &[])]).unwrap();
+        assert_eq!(
+            ImportFormat::detect(Path::new("quotes.txt"), &json),
+            ImportFormat::Json
+        );
+        assert_eq!(
+            ImportFormat::detect(Path::new("quotes.csv"), "Fog\n"),
+            ImportFormat::Csv
+        );
+        assert_eq!(
+            ImportFormat::detect(Path::new("quotes"), "Fog\n"),
+            ImportFormat::PlainText
+        );
+    }
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn guessing_csv_columns() {
+        let mapping = CsvMapping::guess(&row(&["ID", " Quote", "AUTHOR", "category", "notes"]));
+        assert!(mapping.has_header);
+        assert_eq!(
+            mapping.columns,
+            [
+                CsvField::Ignore,
+                CsvField::Text,
+                CsvField::Author,
+                CsvField::Categories,
+                CsvField::Ignore,
+            ]
+        );
+
+        //no names it knows, so the first row is a quote
+        let mapping = CsvMapping::guess(&row(&["Fog", "Dickens"]));
+        assert!(!mapping.has_header);
+        assert_eq!(mapping.columns, [CsvField::Text, CsvField::Ignore]);
+
+        //an author column alone still means a header, with the text taken from the first column
+        let mapping = CsvMapping::guess(&row(&["words", "author"]));
+        assert!(mapping.has_header);
+        assert_eq!(mapping.columns, [CsvField::Text, CsvField::Author]);
+
+        assert!(!CsvMapping::guess(&[]).has_text());
+    }
+
+    #[test]
+    fn importing_csv_with_a_mapping() {
+        let contents = "text,author,tags,more\n\
+                        Fog,Dickens,Stella; Esme,Arthur\n\
+                        ,Nobody,Stella,\n\
+                        Rain,,,\n\
+                        Snow,Me,Esme,\n";
+        assert_eq!(
+            csv_first_row(contents).unwrap(),
+            row(&["text", "author", "tags", "more"])
+        );
+
+        let mapping = CsvMapping {
+            has_header: true,
+            columns: vec![
+                CsvField::Text,
+                CsvField::Author,
+                CsvField::Categories,
+                CsvField::Categories,
+            ],
+            category_delimiter: ";".into(),
+        };
+        let quotes = import_csv_with(contents, &mapping, usize::MAX).unwrap();
+        assert_eq!(quotes.len(), 3, "the row without text is skipped");
+        assert_eq!(quotes[0].0, "Fog");
+        assert_eq!(quotes[0].author(), Some("Dickens"));
+        assert_eq!(quotes[0].1, ["Stella", "Esme", "Arthur"]);
+        assert_eq!(quotes[1].author(), None);
+
+        assert_eq!(import_csv_with(contents, &mapping, 2).unwrap().len(), 2);
+
+        //without a delimiter, each cell is one category
+        let whole_cells = CsvMapping {
+            category_delimiter: String::new(),
+            ..mapping
+        };
+        assert_eq!(
+            import_csv_with(contents, &whole_cells, 1).unwrap()[0].1,
+            ["Stella; Esme", "Arthur"]
+        );
+
+        //the guessed mapping ignores the unknown columns
+        let guessed = import_csv(contents).unwrap();
+        assert_eq!(guessed.len(), 3);
+        assert!(guessed[0].1.is_empty());
+    }
+}
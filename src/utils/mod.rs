@@ -1,7 +1,20 @@
+pub mod diff;
 pub mod either;
 pub mod exports;
+#[cfg(feature = "feed")]
+pub mod feed;
+pub mod filter;
+pub mod imports;
+#[cfg(feature = "spellcheck")]
+pub mod spellcheck;
+pub mod stats;
+pub mod template;
 
-use crate::quote::Quote;
+use crate::{
+    db::{SortError, ValidationError},
+    quote::Quote,
+    utils::exports::ExportError,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,11 +24,37 @@ pub enum Error {
     #[error("error parsing the DB file: {0}")]
     ParseDBError(#[from] serde_json::Error),
     #[error("Error finding quote in DB: {0}")]
-    QuoteNotFoundInDB(Quote),
+    QuoteNotFoundInDB(Box<Quote>),
     #[error("Unable to find quote in category {0} index {1}")]
     QuoteNotFoundIndex(usize, usize),
     #[error("Unable to find a quote with content: {0}")]
     QuoteNotFoundStr(String),
+    #[error("Error parsing CSV: {0}")]
+    CsvError(#[from] csv::Error),
+    #[error("Unable to import {0}, as it isn't a text file")]
+    UnsupportedImport(String),
+    #[error("Unknown category: {0}")]
+    UnknownCategory(String),
+    #[error("Error exporting: {0}")]
+    Export(#[from] ExportError),
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+    #[error("Error sorting quotes: {0}")]
+    Sort(#[from] SortError),
+    #[error("Unable to merge quote {1} into quote {0}")]
+    InvalidMerge(usize, usize),
+    #[error("There's no quote at index {0}")]
+    NoQuoteAt(usize),
+    #[error("{0} quote(s) couldn't be saved and were left out")]
+    QuotesLeftOut(usize),
+    #[error("{0} to {1} isn't a valid date range")]
+    InvalidDateRange(String, String),
+    #[cfg(feature = "feed")]
+    #[error("Unable to fetch feed: {0}")]
+    FeedFetch(#[from] reqwest::Error),
+    #[cfg(feature = "feed")]
+    #[error("Unable to read feed: {0}")]
+    FeedParse(#[from] feed_rs::parser::ParseFeedError),
 }
 
 #[derive(Clone, Copy, Debug)]
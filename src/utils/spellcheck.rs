@@ -0,0 +1,186 @@
+//!Advisory spellchecking against a bundled word list - only built with the `spellcheck` feature
+
+use std::{collections::HashSet, sync::LazyLock};
+
+static DICTIONARY: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    include_str!("words.txt")
+        .lines()
+        .filter(|line| !line.starts_with("//"))
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .collect()
+});
+
+const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Misspelling {
+    pub word: String,
+    ///Character (not byte) offsets of the word in the checked text
+    pub start: usize,
+    pub end: usize,
+    ///Dictionary words one edit away, if there are any
+    pub suggestions: Vec<String>,
+}
+
+///Whether `word` (already lowercase) is in the dictionary, allowing for common endings like -s, -ed, -ing and -ly
+fn is_known(word: &str) -> bool {
+    if DICTIONARY.contains(word) {
+        return true;
+    }
+
+    let word = word.strip_suffix("'s").unwrap_or(word);
+    ["s", "es", "ed", "d", "ing", "ly", "er", "est", "ness"]
+        .iter()
+        .filter_map(|suffix| word.strip_suffix(suffix))
+        .filter(|stem| stem.len() > 1)
+        .any(|stem| {
+            DICTIONARY.contains(stem)
+                || DICTIONARY.contains(format!("{stem}e").as_str())
+                || stem
+                    .strip_suffix('i')
+                    .is_some_and(|stem| DICTIONARY.contains(format!("{stem}y").as_str()))
+                //doubled consonants, eg. stopped -> stop
+                || (stem.len() > 2
+                    && stem.as_bytes()[stem.len() - 1] == stem.as_bytes()[stem.len() - 2]
+                    && DICTIONARY.contains(&stem[..stem.len() - 1]))
+        })
+}
+
+///Whether `b` is `a` with two neighbouring letters swapped - the likeliest slip
+fn swapped(a: &[char], b: &[char]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diffs: Vec<usize> = (0..a.len()).filter(|&i| a[i] != b[i]).collect();
+    matches!(diffs.as_slice(), [i, j] if *j == i + 1 && a[*i] == b[*j] && a[*j] == b[*i])
+}
+
+///Whether `a` can be turned into `b` with one insertion, deletion, substitution or swap of neighbouring letters
+fn one_edit_apart(a: &[char], b: &[char]) -> bool {
+    match a.len().abs_diff(b.len()) {
+        0 => (0..a.len()).filter(|&i| a[i] != b[i]).count() == 1 || swapped(a, b),
+        1 => {
+            let (short, long) = if a.len() < b.len() { (a, b) } else { (b, a) };
+            let prefix = short.iter().zip(long).take_while(|(x, y)| x == y).count();
+            short[prefix..] == long[prefix + 1..]
+        }
+        _ => false,
+    }
+}
+
+///Up to [`MAX_SUGGESTIONS`] dictionary words one edit away from `word` - swapped letters first, then ones that start with the same letter, as the first letter is rarely the one that's wrong
+fn suggestions_for(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut suggestions: Vec<(bool, bool, &str)> = DICTIONARY
+        .iter()
+        .filter(|candidate| candidate.len().abs_diff(word.len()) <= 1)
+        .filter_map(|candidate| {
+            let candidate_chars: Vec<char> = candidate.chars().collect();
+            one_edit_apart(&chars, &candidate_chars).then(|| {
+                (
+                    !swapped(&chars, &candidate_chars),
+                    candidate_chars.first() != chars.first(),
+                    *candidate,
+                )
+            })
+        })
+        .collect();
+    suggestions.sort_unstable();
+    suggestions
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, _, suggestion)| suggestion.to_string())
+        .collect()
+}
+
+///Words in `text` that aren't in the dictionary.
+///
+///This is only a hint - anything with a capital letter (names, mostly) or a digit is skipped.
+#[must_use]
+pub fn suggest_corrections(text: &str) -> Vec<Misspelling> {
+    let mut misspellings = vec![];
+    let mut word = String::new();
+    let mut start = 0;
+
+    for (i, c) in text.chars().chain(std::iter::once(' ')).enumerate() {
+        if c.is_alphanumeric() || (c == '\'' && !word.is_empty()) {
+            if word.is_empty() {
+                start = i;
+            }
+            word.push(c);
+            continue;
+        }
+        if word.is_empty() {
+            continue;
+        }
+
+        let trimmed = word.trim_end_matches('\'');
+        let skip = trimmed.chars().any(|c| c.is_uppercase() || c.is_numeric())
+            || trimmed.chars().count() < 2;
+        if !skip && !is_known(trimmed) {
+            misspellings.push(Misspelling {
+                word: trimmed.to_string(),
+                start,
+                end: start + trimmed.chars().count(),
+                suggestions: suggestions_for(trimmed),
+            });
+        }
+        word.clear();
+    }
+
+    misspellings
+}
+
+#[cfg(all(test, feature = "spellcheck"))]
+mod tests {
+    use super::*;
+
+    fn flagged(text: &str) -> Vec<String> {
+        suggest_corrections(text)
+            .into_iter()
+            .map(|misspelling| misspelling.word)
+            .collect()
+    }
+
+    #[test]
+    fn offsets_are_in_characters() {
+        let text = "café naïve — teh end";
+        let misspellings = suggest_corrections(text);
+        let typo = misspellings
+            .iter()
+            .find(|misspelling| misspelling.word == "teh")
+            .unwrap();
+        assert_eq!((typo.start, typo.end), (13, 16));
+        let chars: Vec<char> = text.chars().collect();
+        assert_eq!(
+            chars[typo.start..typo.end].iter().collect::<String>(),
+            "teh"
+        );
+    }
+
+    #[test]
+    fn names_and_numbers_are_skipped() {
+        assert!(flagged("Zyxwv said qwrtp").contains(&"qwrtp".to_string()));
+        assert!(!flagged("Zyxwv said qwrtp").contains(&"Zyxwv".to_string()));
+        assert!(flagged("room 101b and 4th floor").is_empty());
+    }
+
+    #[test]
+    fn common_endings_are_known() {
+        assert!(is_known("stopped"));
+        assert!(is_known("tries"));
+        assert!(flagged("it stopped and tries again").is_empty());
+    }
+
+    #[test]
+    fn swapped_letters_are_suggested_first() {
+        let misspellings = suggest_corrections("teh");
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!(
+            misspellings[0].suggestions.first().map(String::as_str),
+            Some("the")
+        );
+        assert!(misspellings[0].suggestions.len() <= MAX_SUGGESTIONS);
+    }
+}
@@ -0,0 +1,137 @@
+//!Numbers about the whole collection, eg. for graphing it somewhere else
+
+use crate::quote::{category_key, Quote, MAX_RATING};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub total: usize,
+    ///How many quotes are in each category - a quote in two categories counts towards both.
+    ///
+    ///Categories are matched by [`category_key`], and named the way they were first spelled.
+    pub per_category: BTreeMap<String, usize>,
+    ///In characters, 0 with no quotes
+    pub average_length: f64,
+    ///In characters - the mean of the middle two with an even number of quotes
+    pub median_length: f64,
+    ///Distinct authors, not counting quotes without one
+    pub authors: usize,
+    ///Quotes rated the full [`MAX_RATING`] stars
+    pub favorites: usize,
+}
+
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn compute_stats(list: &[Quote]) -> Stats {
+    let mut names: HashMap<String, &str> = HashMap::new();
+    let mut per_category: BTreeMap<String, usize> = BTreeMap::new();
+    for quote in list {
+        let keys: BTreeSet<String> = quote.1.iter().map(|cat| category_key(cat)).collect();
+        for cat in &quote.1 {
+            names.entry(category_key(cat)).or_insert_with(|| cat.trim());
+        }
+        for key in keys {
+            *per_category.entry(names[&key].to_string()).or_default() += 1;
+        }
+    }
+
+    let mut lengths: Vec<usize> = list.iter().map(|quote| quote.0.chars().count()).collect();
+    lengths.sort_unstable();
+    let (average_length, median_length) = if lengths.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let middle = lengths.len() / 2;
+        let median = if lengths.len().is_multiple_of(2) {
+            (lengths[middle - 1] + lengths[middle]) as f64 / 2.0
+        } else {
+            lengths[middle] as f64
+        };
+        (
+            lengths.iter().sum::<usize>() as f64 / lengths.len() as f64,
+            median,
+        )
+    };
+
+    Stats {
+        total: list.len(),
+        per_category,
+        average_length,
+        median_length,
+        authors: list
+            .iter()
+            .filter_map(Quote::author)
+            .map(str::trim)
+            .collect::<BTreeSet<_>>()
+            .len(),
+        favorites: list
+            .iter()
+            .filter(|quote| quote.rating() == Some(MAX_RATING))
+            .count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(text: &str, categories: &[&str]) -> Quote {
+        Quote::new(text, categories.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn no_quotes() {
+        assert_eq!(compute_stats(&[]), Stats::default());
+    }
+
+    #[test]
+    fn one_quote() {
+        let mut only = quote("Fog", &["Stella"]);
+        only.2.author = Some("Dickens".into());
+        only.2.rating = Some(MAX_RATING);
+
+        let stats = compute_stats(&[only]);
+        assert_eq!(stats.total, 1);
+        assert_eq!(stats.per_category, BTreeMap::from([("Stella".into(), 1)]));
+        assert!((stats.average_length - 3.0).abs() < f64::EPSILON);
+        assert!((stats.median_length - 3.0).abs() < f64::EPSILON);
+        assert_eq!(stats.authors, 1);
+        assert_eq!(stats.favorites, 1);
+    }
+
+    #[test]
+    fn categories_are_counted_however_they_are_spelled() {
+        let stats = compute_stats(&[
+            quote("a", &["Stella"]),
+            quote("b", &[" stella ", "Esme"]),
+            quote("c", &["STELLA", "stella"]),
+        ]);
+        assert_eq!(
+            stats.per_category,
+            BTreeMap::from([("Esme".into(), 1), ("Stella".into(), 3)])
+        );
+    }
+
+    #[test]
+    fn many_quotes() {
+        //lengths 1 to 10,000
+        let list: Vec<Quote> = (1..=10_000)
+            .map(|n| {
+                let mut quote = quote(&"x".repeat(n), &[if n % 2 == 0 { "Even" } else { "Odd" }]);
+                quote.2.author = Some(format!("Author {}", n % 100));
+                quote
+            })
+            .collect();
+
+        let stats = compute_stats(&list);
+        assert_eq!(stats.total, 10_000);
+        assert_eq!(
+            stats.per_category,
+            BTreeMap::from([("Even".into(), 5_000), ("Odd".into(), 5_000)])
+        );
+        assert!((stats.average_length - 5_000.5).abs() < f64::EPSILON);
+        assert!((stats.median_length - 5_000.5).abs() < f64::EPSILON);
+        assert_eq!(stats.authors, 100);
+        assert_eq!(stats.favorites, 0);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::quote::Quote;
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+pub const DEFAULT_TEMPLATE: &str = "{text} - {author}";
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("unknown placeholder {{{0}}}")]
+    UnknownPlaceholder(String),
+    #[error("a {{ is never closed")]
+    Unclosed,
+    #[error("the template has to include {{text}}")]
+    MissingText,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    Text,
+    Author,
+    Categories,
+    Tags,
+    Source,
+    Rating,
+    Attribution,
+}
+
+impl Placeholder {
+    fn value(self, quote: &Quote) -> String {
+        match self {
+            Self::Text => quote.0.clone(),
+            Self::Author => quote.author().unwrap_or_default().to_string(),
+            Self::Categories => quote.1.join(", "),
+            Self::Tags => quote
+                .tags()
+                .iter()
+                .map(|tag| format!("#{tag}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Self::Source => quote.source().unwrap_or_default().to_string(),
+            Self::Rating => quote
+                .rating()
+                .map(|rating| rating.to_string())
+                .unwrap_or_default(),
+            Self::Attribution => quote
+                .attribution()
+                .map(|attribution| attribution.to_string().to_lowercase())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl FromStr for Placeholder {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "author" => Ok(Self::Author),
+            "categories" => Ok(Self::Categories),
+            "tags" => Ok(Self::Tags),
+            "source" => Ok(Self::Source),
+            "rating" => Ok(Self::Rating),
+            "attribution" => Ok(Self::Attribution),
+            _ => Err(TemplateError::UnknownPlaceholder(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+///One whitespace-separated chunk of a template - it's dropped whole if all of its placeholders come out empty
+type Word = Vec<Piece>;
+
+///How a quote is laid out as a single line of text, eg. `"{text}" - {author} [{categories}]`.
+///
+///Anything touching an empty placeholder without a space in between goes with it, as does a lone bit of punctuation (like ` - `) right before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuoteTemplate {
+    source: String,
+    words: Vec<Word>,
+}
+
+impl Default for QuoteTemplate {
+    fn default() -> Self {
+        DEFAULT_TEMPLATE
+            .parse()
+            .expect("the default template is valid")
+    }
+}
+
+impl FromStr for QuoteTemplate {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = vec![];
+        for chunk in s.split_whitespace() {
+            let mut word = vec![];
+            let mut rest = chunk;
+            while let Some(open) = rest.find('{') {
+                if open > 0 {
+                    word.push(Piece::Literal(rest[..open].to_string()));
+                }
+                let close = rest[open..].find('}').ok_or(TemplateError::Unclosed)? + open;
+                word.push(Piece::Placeholder(rest[open + 1..close].parse()?));
+                rest = &rest[close + 1..];
+            }
+            if !rest.is_empty() {
+                word.push(Piece::Literal(rest.to_string()));
+            }
+            words.push(word);
+        }
+
+        let template = Self {
+            source: s.to_string(),
+            words,
+        };
+        if template.contains(Placeholder::Text) {
+            Ok(template)
+        } else {
+            Err(TemplateError::MissingText)
+        }
+    }
+}
+
+impl Display for QuoteTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl QuoteTemplate {
+    ///Parses `template`, using [`DEFAULT_TEMPLATE`] if it isn't valid
+    #[must_use]
+    pub fn parse_or_default(template: &str) -> Self {
+        template.parse().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn contains(&self, placeholder: Placeholder) -> bool {
+        self.words
+            .iter()
+            .flatten()
+            .any(|piece| piece == &Piece::Placeholder(placeholder))
+    }
+
+    #[must_use]
+    pub fn render(&self, quote: &Quote) -> String {
+        //`None` for words that have been dropped, and `Some(true)` for punctuation-only words, which go if the next word does
+        let rendered: Vec<Option<(String, bool)>> = self
+            .words
+            .iter()
+            .map(|word| {
+                let mut out = String::new();
+                let mut has_placeholder = false;
+                let mut filled = false;
+                for piece in word {
+                    match piece {
+                        Piece::Literal(literal) => out.push_str(literal),
+                        Piece::Placeholder(placeholder) => {
+                            has_placeholder = true;
+                            let value = placeholder.value(quote);
+                            filled |= !value.is_empty();
+                            out.push_str(&value);
+                        }
+                    }
+                }
+
+                if has_placeholder && !filled {
+                    None
+                } else {
+                    let separator = !has_placeholder && !out.chars().any(char::is_alphanumeric);
+                    Some((out, separator))
+                }
+            })
+            .collect();
+
+        let mut kept: Vec<&(String, bool)> = vec![];
+        for (i, word) in rendered.iter().enumerate() {
+            if let Some(word) = word {
+                let next_dropped = matches!(rendered.get(i + 1), Some(None));
+                if !(word.1 && next_dropped) {
+                    kept.push(word);
+                }
+            }
+        }
+        //no leading or trailing separators either, eg. if the template ended in `- {author}`
+        while kept.first().is_some_and(|word| word.1) {
+            kept.remove(0);
+        }
+        while kept.last().is_some_and(|word| word.1) {
+            kept.pop();
+        }
+
+        kept.iter()
+            .map(|(word, _)| word.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::Attribution;
+
+    fn sample() -> Quote {
+        let mut quote = Quote::new("Hello", vec!["Stella".to_string(), "Esme".to_string()]);
+        quote.2.author = Some("Anne".to_string());
+        quote.2.tags = vec!["fog".to_string()];
+        quote.2.rating = Some(4);
+        quote.2.attribution = Some(Attribution::Disputed);
+        quote.2.source = Some("https://example.com".to_string());
+        quote
+    }
+
+    fn render(template: &str, quote: &Quote) -> String {
+        template.parse::<QuoteTemplate>().unwrap().render(quote)
+    }
+
+    #[test]
+    fn substitutes_every_placeholder() {
+        assert_eq!(
+            render(
+                "\"{text}\" - {author} [{categories}] {tags} {rating}* ({attribution}) <{source}>",
+                &sample()
+            ),
+            "\"Hello\" - Anne [Stella, Esme] #fog 4* (disputed) <https://example.com>"
+        );
+    }
+
+    #[test]
+    fn drops_empty_placeholders_and_their_punctuation() {
+        let quote = Quote::new("Hello", vec![]);
+        assert_eq!(render("{text} - {author}", &quote), "Hello");
+        assert_eq!(render("{text} - {author} [{categories}]", &quote), "Hello");
+        assert_eq!(render("{author} - {text}", &quote), "Hello");
+        assert_eq!(render("\"{text}\" — {author}", &quote), "\"Hello\"");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert_eq!(
+            "{text} {nope}".parse::<QuoteTemplate>(),
+            Err(TemplateError::UnknownPlaceholder("nope".to_string()))
+        );
+        assert_eq!(
+            "{text} {author".parse::<QuoteTemplate>(),
+            Err(TemplateError::Unclosed)
+        );
+        assert_eq!(
+            "{author}".parse::<QuoteTemplate>(),
+            Err(TemplateError::MissingText)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_default() {
+        assert_eq!(
+            QuoteTemplate::parse_or_default("{author"),
+            QuoteTemplate::default()
+        );
+        assert_eq!(QuoteTemplate::default().to_string(), DEFAULT_TEMPLATE);
+        assert_eq!(
+            QuoteTemplate::parse_or_default("{author}").render(&sample()),
+            "Hello - Anne"
+        );
+    }
+}
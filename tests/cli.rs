@@ -0,0 +1,100 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+const DB: &str =
+    r#"[["Hello, world", ["Stella"], {"author": "Anne"}], ["Fog", ["Esme", "Stella"]]]"#;
+
+///Runs the CLI from the crate root (where types.txt is), feeding it `stdin`
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_english_quotes"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the CLI runs");
+    //the CLI can exit without reading everything, eg. on bad arguments
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn exports_stdin_to_stdout() {
+    let csv = stdout(&run(&["export", "--format", "csv"], DB));
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("text,author,categories,attribution,tags,source,rating,collections,added,modified")
+    );
+    assert_eq!(lines.next(), Some("\"Hello, world\",Anne,Stella,,,,,,,"));
+    assert_eq!(lines.next(), Some("Fog,,Esme;Stella,,,,,,,"));
+    assert_eq!(lines.next(), None);
+
+    //`-` means stdin too
+    assert_eq!(stdout(&run(&["export", "-f", "csv", "-"], DB)), csv);
+}
+
+#[test]
+fn reads_a_db_file_when_given_one() {
+    let path = std::env::temp_dir().join(format!("english_quotes_cli_{}.json", std::process::id()));
+    std::fs::write(&path, DB).unwrap();
+    let from_file = stdout(&run(&["export", "-f", "csv", path.to_str().unwrap()], ""));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(from_file, stdout(&run(&["export", "-f", "csv"], DB)));
+}
+
+#[test]
+fn bad_arguments_exit_with_usage() {
+    let output = run(&["export", "--format", "pdf"], DB);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("usage:"));
+}
+
+#[test]
+fn bad_input_fails() {
+    let output = run(&["export"], "not json");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn markdown_copes_with_differently_spelled_categories() {
+    let markdown = stdout(&run(
+        &["export"],
+        r#"[["Hello world", ["arthurkipps"], {}]]"#,
+    ));
+    assert!(markdown.contains("## ArthurKipps\n - *Hello world*"));
+}
+
+#[test]
+fn plain_text_follows_the_template() {
+    assert_eq!(
+        stdout(&run(&["export", "-f", "text"], DB)),
+        "Hello, world - Anne\nFog\n"
+    );
+    assert_eq!(
+        stdout(&run(
+            &["export", "-f", "text", "-t", "{text} [{categories}]"],
+            DB
+        )),
+        "Hello, world [Stella]\nFog [Esme, Stella]\n"
+    );
+    assert_eq!(
+        run(&["export", "-f", "text", "-t", "{author}"], DB)
+            .status
+            .code(),
+        Some(2)
+    );
+}